@@ -0,0 +1,186 @@
+//! Features an operator turned off for the whole deployment.
+//!
+//! Set with `DISABLED_FEATURES`, a comma-separated list of feature names, e.g.
+//! `control_playback,modify_library` for a bot that should never touch
+//! playback or Liked Songs. Commands needing a disabled feature refuse to run
+//! and are left out of `/help`. The features `/login` asks for can't be turned
+//! off, since nearly every command relies on them.
+//!
+//! `READ_ONLY=true` turns off everything that changes the account at once,
+//! for demo instances and cautious users.
+
+use std::collections::HashSet;
+use tracing::warn;
+
+use super::scopes::{Feature, LOGIN_FEATURES};
+
+lazy_static::lazy_static! {
+    static ref READ_ONLY: bool = std::env::var("READ_ONLY")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false);
+    static ref DISABLED: HashSet<Feature> = {
+        let value = std::env::var("DISABLED_FEATURES").unwrap_or_default();
+        let (disabled, unknown) = parse_disabled(&value);
+        for name in unknown {
+            warn!("Ignoring unknown or required feature in DISABLED_FEATURES: {name}");
+        }
+        disabled
+    };
+}
+
+/// Features that change something on the account: playlists, Liked Songs,
+/// playback and cover images
+pub const WRITE_FEATURES: &[Feature] = &[
+    Feature::ModifyPlaylists,
+    Feature::ModifyLibrary,
+    Feature::ControlPlayback,
+    Feature::UploadImages,
+];
+
+/// Why a feature can't be used on this deployment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Blocked {
+    ReadOnly,
+    Disabled(Feature),
+}
+
+pub fn read_only() -> bool {
+    *READ_ONLY
+}
+
+/// Pure function: the features named in `value`, and the names that aren't a
+/// feature that can be turned off
+pub fn parse_disabled(value: &str) -> (HashSet<Feature>, Vec<String>) {
+    let mut disabled = HashSet::new();
+    let mut unknown = Vec::new();
+    for name in value
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+    {
+        match Feature::ALL
+            .into_iter()
+            .find(|feature| feature.name().eq_ignore_ascii_case(name))
+        {
+            Some(feature) if !LOGIN_FEATURES.contains(&feature) => {
+                disabled.insert(feature);
+            }
+            _ => unknown.push(name.to_string()),
+        }
+    }
+    (disabled, unknown)
+}
+
+/// Pure function: why `features` can't be used, read-only mode first
+pub fn check(
+    features: &[Feature],
+    read_only: bool,
+    disabled: &HashSet<Feature>,
+) -> Option<Blocked> {
+    if read_only
+        && features
+            .iter()
+            .any(|feature| WRITE_FEATURES.contains(feature))
+    {
+        return Some(Blocked::ReadOnly);
+    }
+    features
+        .iter()
+        .find(|feature| disabled.contains(feature))
+        .map(|feature| Blocked::Disabled(*feature))
+}
+
+/// Pure function: `features` without the ones that change the account, for
+/// a dry run that only reads
+pub fn without_writes(features: &[Feature]) -> Vec<Feature> {
+    features
+        .iter()
+        .filter(|feature| !WRITE_FEATURES.contains(feature))
+        .copied()
+        .collect()
+}
+
+/// Why `features` can't be used on this deployment, if they can't
+pub fn blocked(features: &[Feature]) -> Option<Blocked> {
+    check(features, read_only(), &DISABLED)
+}
+
+/// Pure function: `help` without the lines whose command `hidden` returns
+/// true for. Command lines start with `<code>/name`.
+pub fn hide_commands(help: &str, hidden: impl Fn(&str) -> bool) -> String {
+    help.split_inclusive('\n')
+        .filter(|line| {
+            let command = line
+                .strip_prefix("<code>/")
+                .and_then(|rest| rest.split([' ', '<']).next());
+            !command.is_some_and(&hidden)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_disabled() {
+        let (disabled, unknown) = parse_disabled(" control_playback, Modify_Library,,bogus ");
+        assert_eq!(
+            disabled,
+            HashSet::from([Feature::ControlPlayback, Feature::ModifyLibrary])
+        );
+        assert_eq!(unknown, vec!["bogus"]);
+
+        // Login features stay on
+        let (disabled, unknown) = parse_disabled("listening");
+        assert!(disabled.is_empty());
+        assert_eq!(unknown, vec!["listening"]);
+
+        assert_eq!(parse_disabled(""), (HashSet::new(), Vec::new()));
+    }
+
+    #[test]
+    fn test_check_read_only_before_disabled() {
+        let disabled = HashSet::from([Feature::ReadLibrary]);
+        let features = [Feature::ReadLibrary, Feature::ModifyLibrary];
+        assert_eq!(check(&features, true, &disabled), Some(Blocked::ReadOnly));
+        assert_eq!(
+            check(&features, false, &disabled),
+            Some(Blocked::Disabled(Feature::ReadLibrary))
+        );
+
+        // Reading stays allowed in read-only mode
+        assert_eq!(
+            check(&[Feature::ReadPlaylists], true, &HashSet::new()),
+            None
+        );
+        assert_eq!(check(&[], true, &disabled), None);
+    }
+
+    #[test]
+    fn test_without_writes() {
+        assert_eq!(
+            without_writes(&[Feature::ReadPlaylists, Feature::ModifyPlaylists]),
+            vec![Feature::ReadPlaylists]
+        );
+        // A dry run of a write-only command needs nothing, so read-only mode
+        // doesn't block it
+        let reads = without_writes(&[Feature::ModifyPlaylists]);
+        assert!(reads.is_empty());
+        assert_eq!(check(&reads, true, &HashSet::new()), None);
+    }
+
+    #[test]
+    fn test_hide_commands_keeps_other_lines() {
+        let help = "<b>Commands:</b>\n\
+                    <code>/like</code> / <code>/unlike</code> - Save\n\
+                    <code>/sleep minutes</code> - Pause later\n\
+                    <code>/me</code> - Profile\n\
+                    Tap <code>/login</code>.";
+        assert_eq!(
+            hide_commands(help, |command| ["like", "sleep"].contains(&command)),
+            "<b>Commands:</b>\n<code>/me</code> - Profile\nTap <code>/login</code>."
+        );
+        assert_eq!(hide_commands(help, |_| false), help);
+    }
+}
@@ -1 +1,3 @@
+pub mod flags;
+pub mod scopes;
 pub mod spotify;
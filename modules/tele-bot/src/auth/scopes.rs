@@ -0,0 +1,148 @@
+//! Registry of the Spotify scopes each feature needs.
+//!
+//! `/login` only asks for what the read-only commands use. Commands that need
+//! more declare their features, and when the chat's token falls short the
+//! user gets a re-auth link for the granted scopes plus the missing ones.
+
+use rspotify::http::HttpError;
+use rspotify::ClientError;
+use std::collections::{BTreeSet, HashSet};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// Top items and recently played
+    Listening,
+    FollowedArtists,
+    ReadPlaylists,
+    ModifyPlaylists,
+    /// Saved ("Liked") tracks
+    ReadLibrary,
+    ModifyLibrary,
+    /// What is playing right now
+    CurrentlyPlaying,
+    /// Pausing and queueing on the active device
+    ControlPlayback,
+    /// Where the user stopped in podcast episodes
+    PlaybackPosition,
+    /// Custom playlist cover images
+    UploadImages,
+}
+
+/// Requested by `/login` up front
+pub const LOGIN_FEATURES: &[Feature] = &[Feature::Listening, Feature::FollowedArtists];
+
+impl Feature {
+    pub const ALL: [Feature; 10] = [
+        Feature::Listening,
+        Feature::FollowedArtists,
+        Feature::ReadPlaylists,
+        Feature::ModifyPlaylists,
+        Feature::ReadLibrary,
+        Feature::ModifyLibrary,
+        Feature::CurrentlyPlaying,
+        Feature::ControlPlayback,
+        Feature::PlaybackPosition,
+        Feature::UploadImages,
+    ];
+
+    /// How the feature is named in `DISABLED_FEATURES` and error messages
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::Listening => "listening",
+            Feature::FollowedArtists => "followed_artists",
+            Feature::ReadPlaylists => "read_playlists",
+            Feature::ModifyPlaylists => "modify_playlists",
+            Feature::ReadLibrary => "read_library",
+            Feature::ModifyLibrary => "modify_library",
+            Feature::CurrentlyPlaying => "currently_playing",
+            Feature::ControlPlayback => "control_playback",
+            Feature::PlaybackPosition => "playback_position",
+            Feature::UploadImages => "upload_images",
+        }
+    }
+
+    pub fn scopes(self) -> &'static [&'static str] {
+        match self {
+            Feature::Listening => &["user-top-read", "user-read-recently-played"],
+            Feature::FollowedArtists => &["user-follow-read"],
+            Feature::ReadPlaylists => &["playlist-read-private"],
+            Feature::ModifyPlaylists => &["playlist-modify-public", "playlist-modify-private"],
+            Feature::ReadLibrary => &["user-library-read"],
+            Feature::ModifyLibrary => &["user-library-modify"],
+            Feature::CurrentlyPlaying => &["user-read-currently-playing"],
+            Feature::ControlPlayback => &["user-modify-playback-state"],
+            Feature::PlaybackPosition => &["user-read-playback-position"],
+            Feature::UploadImages => &["ugc-image-upload"],
+        }
+    }
+}
+
+pub fn scopes_for(features: &[Feature]) -> HashSet<String> {
+    features
+        .iter()
+        .flat_map(|feature| feature.scopes())
+        .map(|scope| scope.to_string())
+        .collect()
+}
+
+/// Pure function: scopes `features` need that were not granted, sorted
+pub fn missing_scopes(granted: &HashSet<String>, features: &[Feature]) -> Vec<&'static str> {
+    features
+        .iter()
+        .flat_map(|feature| feature.scopes())
+        .copied()
+        .filter(|scope| !granted.contains(*scope))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Scopes to request when re-authorizing, so consent is only ever added to
+pub fn reauth_scopes(granted: &HashSet<String>, features: &[Feature]) -> HashSet<String> {
+    let mut scopes = scopes_for(LOGIN_FEATURES);
+    scopes.extend(granted.iter().cloned());
+    scopes.extend(scopes_for(features));
+    scopes
+}
+
+/// Spotify answers 403 when the token lacks a scope for the endpoint
+pub fn is_insufficient_scope(err: &ClientError) -> bool {
+    match err {
+        ClientError::Http(http) => {
+            matches!(&**http, HttpError::StatusCode(response) if response.status() == 403)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn granted(scopes: &[&str]) -> HashSet<String> {
+        scopes.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_missing_scopes_lists_only_ungranted() {
+        let granted = granted(&["user-top-read", "playlist-modify-public"]);
+        assert_eq!(
+            missing_scopes(
+                &granted,
+                &[Feature::ModifyPlaylists, Feature::ReadPlaylists]
+            ),
+            vec!["playlist-modify-private", "playlist-read-private"]
+        );
+        assert!(missing_scopes(&granted, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_reauth_keeps_granted_scopes() {
+        let granted = granted(&["user-library-read"]);
+        let scopes = reauth_scopes(&granted, &[Feature::ReadPlaylists]);
+
+        assert!(scopes.contains("user-library-read"));
+        assert!(scopes.contains("playlist-read-private"));
+        assert!(scopes.contains("user-top-read"));
+    }
+}
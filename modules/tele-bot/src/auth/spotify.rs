@@ -1,9 +1,17 @@
 use rspotify::{Credentials, OAuth};
+use std::collections::HashSet;
+
+use super::scopes::{scopes_for, LOGIN_FEATURES};
 
 pub fn spotify_oauth() -> OAuth {
+    spotify_oauth_with_scopes(scopes_for(LOGIN_FEATURES))
+}
+
+/// OAuth settings asking for a specific set of scopes, used for re-auth
+pub fn spotify_oauth_with_scopes(scopes: HashSet<String>) -> OAuth {
     OAuth {
         redirect_uri: std::env::var("SPOTIFY_REDIRECT_URI").expect("SPOTIFY_REDIRECT_URI not set"),
-        scopes: rspotify::scopes!("user-top-read", "user-read-recently-played"),
+        scopes,
         ..Default::default()
     }
 }
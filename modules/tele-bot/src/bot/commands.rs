@@ -2,7 +2,7 @@ use teloxide::utils::command::BotCommands;
 
 #[derive(BotCommands, Clone)]
 #[command(
-    rename_rule = "lowercase",
+    rename_rule = "snake_case",
     description = "Spotify Dashboard Bot Commands"
 )]
 pub enum Command {
@@ -12,18 +12,94 @@ pub enum Command {
     #[command(description = "authenticate with Spotify")]
     Login,
 
+    #[command(description = "disconnect your Spotify account")]
+    Logout,
+
     #[command(description = "show current user info")]
     Me,
 
-    #[command(description = "show top tracks")]
-    TopTracks,
+    #[command(
+        description = "show top tracks (usage: /top_tracks [count] [short|medium|long] [genre])"
+    )]
+    TopTracks(String),
 
-    #[command(description = "show top artists")]
-    TopArtists,
+    #[command(description = "show top artists (usage: /top_artists [count] [short|medium|long])")]
+    TopArtists(String),
 
     #[command(description = "show recently played")]
     RecentlyPlayed,
 
+    #[command(description = "show when you listen (weekday × hour heatmap)")]
+    Patterns,
+
+    #[command(description = "the mood of each track you played today")]
+    MoodToday,
+
+    #[command(description = "how many new artists you've been listening to")]
+    Discovery,
+
+    #[command(description = "your most skipped tracks (usage: /skips [on|off])")]
+    Skips(String),
+
+    #[command(description = "show your listening streak (usage: /streak [on|off])")]
+    Streak(String),
+
+    #[command(
+        description = "new releases from artists you follow (usage: /new_releases [on|off])"
+    )]
+    NewReleases(String),
+
+    #[command(
+        description = "copy Discover Weekly and Release Radar every Monday (usage: /archive [on|off])"
+    )]
+    Archive(String),
+
+    #[command(description = "upcoming concerts of your top artists")]
+    Events,
+
+    #[command(
+        description = "pause playback after a while (usage: /sleep minutes [wind] or /sleep off)"
+    )]
+    Sleep(String),
+
+    #[command(description = "save the currently playing track to Liked Songs")]
+    Like,
+
+    #[command(description = "remove the currently playing track from Liked Songs")]
+    Unlike,
+
+    #[command(description = "shared favorites of everyone connected in this chat")]
+    GroupVibe,
+
+    #[command(description = "make a 50/50 playlist with another member (usage: /blend @user)")]
+    Blend(String),
+
+    #[command(
+        description = "how well your taste matches another member's (usage: /compare @user)"
+    )]
+    Compare(String),
+
+    #[command(description = "compare the sound of two tracks (usage: /compare_tracks A | B)")]
+    CompareTracks(String),
+
+    #[command(description = "details of many tracks at once (usage: /tracks link1 link2 ...)")]
+    Tracks(String),
+
+    #[command(description = "latest episodes of the podcasts you follow")]
+    Podcasts,
+
+    #[command(description = "albums saved in your library")]
+    Albums,
+
+    #[command(description = "podcasts and audiobooks you follow")]
+    Shows,
+
+    #[command(description = "tracks and albums you saved most recently")]
+    NewInLibrary,
+
+    #[command(description = "all-time favorites you haven't played lately")]
+    Rediscover,
+
     #[command(description = "search for a track (usage: /search song_name)")]
     Search(String),
 
@@ -36,6 +112,117 @@ pub enum Command {
     #[command(description = "create a new playlist (usage: /create_playlist playlist_name)")]
     CreatePlaylist(String),
 
-    #[command(description = "add track to playlist (usage: /add_to_playlist song_name | playlist_name)")]
+    #[command(
+        description = "add track to playlist (usage: /add_to_playlist song_name | playlist_name)"
+    )]
     AddToPlaylist(String),
+
+    #[command(
+        description = "add many tracks at once (usage: /fill_playlist name from top|recent|liked [mood] [genre] [count])"
+    )]
+    FillPlaylist(String),
+
+    #[command(
+        description = "make a playlist at your running pace (usage: /run_playlist bpm [tolerance])"
+    )]
+    RunPlaylist(String),
+
+    #[command(
+        description = "make a danceable playlist of the given length (usage: /party_playlist [minutes])"
+    )]
+    PartyPlaylist(String),
+
+    #[command(description = "save a playlist's track order (usage: /backup_playlist name)")]
+    BackupPlaylist(String),
+
+    #[command(
+        description = "list or restore playlist backups (usage: /restore_playlist name [| number])"
+    )]
+    RestorePlaylist(String),
+
+    #[command(
+        description = "tempo, energy, genre and mood breakdown of a playlist (usage: /analyze_playlist name)"
+    )]
+    AnalyzePlaylist(String),
+
+    #[command(
+        description = "find tracks that don't fit a playlist's sound (usage: /playlist_outliers name)"
+    )]
+    PlaylistOutliers(String),
+
+    #[command(
+        description = "tracks that sat unplayed in a playlist (usage: /staleness name [| weeks])"
+    )]
+    Staleness(String),
+
+    #[command(
+        description = "drop tracks unplayed for weeks every day (usage: /rotate name [| weeks|off])"
+    )]
+    Rotate(String),
+
+    #[command(
+        description = "reorder a playlist by energy (usage: /smart_shuffle playlist [| arc|rise|fall])"
+    )]
+    SmartShuffle(String),
+
+    #[command(
+        description = "reorder a playlist so adjacent keys mix well (usage: /harmonic_sort name)"
+    )]
+    HarmonicSort(String),
+
+    #[command(
+        description = "find and remove tracks unavailable in your market (usage: /clean_playlist name)"
+    )]
+    CleanPlaylist(String),
+
+    #[command(
+        description = "use a photo as a playlist cover (reply to a photo with /set_cover playlist_name)"
+    )]
+    SetCover(String),
+
+    #[command(
+        description = "show what a playlist command would change without doing it (usage: /preview smart_shuffle name)"
+    )]
+    Preview(String),
+
+    #[command(
+        description = "record labels and release eras of your top tracks (usage: /labels [short|medium|long])"
+    )]
+    Labels(String),
+
+    #[command(
+        description = "default count and time range of the top lists (usage: /top_defaults [count] [range|reset])"
+    )]
+    TopDefaults(String),
+
+    #[command(description = "set how dates and numbers are shown (usage: /locale [en|vi])")]
+    Locale(String),
+
+    #[command(description = "country used for search and availability (usage: /market [XX|auto])")]
+    Market(String),
+
+    #[command(
+        description = "hide explicit tracks from search and blends (usage: /explicit_filter [on|off])"
+    )]
+    ExplicitFilter(String),
+
+    #[command(
+        description = "POST plays and new releases to your URL (usage: /webhook [url secret|test|off])"
+    )]
+    Webhook(String),
+
+    #[command(description = "publish what you're playing to MQTT (usage: /mqtt [on|off])")]
+    Mqtt(String),
+
+    #[command(description = "what the bot changed on your Spotify account")]
+    HistoryActions,
+
+    #[command(description = "show local command usage statistics (admin)")]
+    Usage,
+
+    #[command(description = "show background job status (admin)")]
+    Jobs,
+
+    #[command(description = "run a background job now (admin, usage: /run_job name)")]
+    RunJob(String),
 }
@@ -1,29 +1,235 @@
+use base64::Engine;
 use rspotify::clients::{BaseClient, OAuthClient};
+use rspotify::model::AdditionalType;
+use rspotify::model::AlbumType;
 use rspotify::model::Market;
+use rspotify::model::PlaylistId;
 use rspotify::model::SearchResult;
 use rspotify::model::SearchType;
-use rspotify::AuthCodeSpotify;
+use rspotify::model::TimeLimits;
+use rspotify::prelude::{Id, PlayableId};
+use rspotify::{AuthCodeSpotify, ClientError};
+use std::collections::{HashMap, HashSet};
+use teloxide::net::Download;
 use teloxide::prelude::*;
-use teloxide::types::InlineKeyboardMarkup;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
 use tokio::sync::Mutex;
 use tracing::error;
 
-use crate::auth::spotify::{spotify_credentials, spotify_oauth};
+use crate::auth::flags::{self, Blocked};
+use crate::auth::scopes::{self, Feature};
+use crate::auth::spotify::{spotify_credentials, spotify_oauth, spotify_oauth_with_scopes};
+use crate::detector::classifier::backend;
+use crate::detector::energy::{energy_score, level_sequence, meter};
+use crate::detector::genre::{AudioFeatures, Genre};
+use crate::detector::mood::Mood;
+use crate::events::{self, EventProvider};
+use crate::models::convert;
+use crate::models::spotify::{
+    Artist, ContextKind, Episode, EpisodeProgress, Play, PlayContext, Playlist, Release,
+    SavedAlbum, Show, TopRange, Track, UserProfile,
+};
 use crate::state::AppState;
-use crate::utils::stream::collect_stream;
+use crate::stats::archive::{archive_name, ARCHIVED_PLAYLISTS, SPOTIFY_OWNER};
+use crate::stats::backups::{PlaylistBackup, UndoPlan, UNDO_WINDOW_MINUTES};
+use crate::stats::blend::{blend, Taste};
+use crate::stats::discovery::discovery;
+use crate::stats::fill::{parse_fill_args, plan_add, AddPlan, FillSource};
+use crate::stats::group::shared_items;
+use crate::stats::harmonic::{clashes, harmonic_order, Camelot};
+use crate::stats::labels::{decade_breakdown, release_year};
+use crate::stats::moods::{day_timeline, dominant_mood, render_strip};
+use crate::stats::party::{
+    build_party, parse_minutes, PartyCandidate, DEFAULT_PARTY_MINUTES, MAX_PARTY_MINUTES,
+    MIN_PARTY_MINUTES, TEMPO_STEP,
+};
+use crate::stats::patterns::{analyze_patterns, render_heatmap};
+use crate::stats::playlist_analysis::{
+    energy_curve, find_outliers, sparkline, spread, MIN_TRACKS_FOR_OUTLIERS,
+};
+use crate::stats::rediscover::{forgotten_favorites, MAX_REDISCOVER};
+use crate::stats::releases::{is_recent_release, RELEASE_WINDOW_DAYS};
+use crate::stats::share_breakdown;
+use crate::stats::shuffle::{arc_order, reorder_moves, ArcShape};
+use crate::stats::similarity::{compare, feature_centroid, shared_artists, TasteProfile};
+use crate::stats::sleep::{
+    parse_sleep, pick_wind_down, SleepCommand, SleepTimer, WindDownCandidate, MAX_MINUTES,
+};
+use crate::stats::staleness::{
+    parse_weeks, stale_tracks, Rotation, DEFAULT_STALE_WEEKS, MAX_STALE_WEEKS,
+};
+use crate::stats::streak::{longest_streak, MILESTONES};
+use crate::stats::tempo::{parse_pace, pick_for_pace, MAX_BPM, MAX_TOLERANCE, MAX_TRACKS, MIN_BPM};
+use crate::stats::track_compare::{feature_rows, key_name, verdict};
+use crate::utils::audit::Actor;
+use crate::utils::bounded_cache::BoundedCache;
+use crate::utils::format::{
+    format_count, format_date, format_datetime, format_duration, format_number,
+    format_track_length, Locale,
+};
+use crate::utils::links::{parse_id, spotify_uri, web_url, Entity};
+use crate::utils::list_args::{range_label, ListArgs, MAX_COUNT};
+use crate::utils::matching::{fuzzy_pick, is_close_match, FuzzyMatch};
+use crate::utils::messages::{split_message, TELEGRAM_LIMIT};
+use crate::utils::stream::{collect_stream, collect_stream_n, fetch_all, find_in_stream};
+use crate::utils::webhooks::{self, Webhook, SIGNATURE_HEADER};
+use crate::utils::{jobs, usage};
 
 use super::commands::Command;
 
-// Global state for storing user Spotify sessions per chat
+// Maximum number of Spotify requests a single command keeps in flight
+const SPOTIFY_CONCURRENCY: usize = 4;
+
+/// State is kept per (chat, user) so every member of a group chat can
+/// connect their own Spotify account
+pub(crate) type StateKey = (i64, u64);
+
+// Global state for storing user Spotify sessions per chat member
 lazy_static::lazy_static! {
-    static ref CHAT_STATES: Mutex<std::collections::HashMap<i64, AppState>> =
+    static ref CHAT_STATES: Mutex<std::collections::HashMap<StateKey, AppState>> =
         Mutex::new(std::collections::HashMap::new());
+    // Audio features never change for a track, so they are shared by all
+    // chats. The Camelot key is kept next to them since AudioFeatures leaves
+    // it out.
+    static ref AUDIO_FEATURES: Mutex<BoundedCache<String, (AudioFeatures, Option<Camelot>)>> =
+        Mutex::new(BoundedCache::new(MAX_CACHED_FEATURES));
+    // Album ID → record label, `None` when Spotify lists none
+    static ref ALBUM_LABELS: Mutex<BoundedCache<String, Option<String>>> =
+        Mutex::new(BoundedCache::new(MAX_CACHED_ALBUMS));
 }
 
+/// The caches are in memory only, so they start empty after a restart. The
+/// oldest entries are dropped beyond these sizes, which only costs a refetch.
+const MAX_CACHED_FEATURES: usize = 50_000;
+const MAX_CACHED_ALBUMS: usize = 20_000;
+
 pub fn schema() -> teloxide::dispatching::UpdateHandler<teloxide::RequestError> {
-    Update::filter_message()
-        .filter_command::<Command>()
-        .endpoint(handle_commands)
+    dptree::entry()
+        .branch(
+            Update::filter_message()
+                .filter_command::<Command>()
+                .endpoint(handle_commands),
+        )
+        .branch(Update::filter_callback_query().endpoint(handle_callback))
+}
+
+// Inline keyboard buttons carry their action in the callback data
+async fn handle_callback(bot: Bot, q: CallbackQuery) -> Result<(), teloxide::RequestError> {
+    bot.answer_callback_query(q.id.clone()).await?;
+
+    let (Some(data), Some(message)) = (q.data.as_deref(), q.message.as_ref()) else {
+        return Ok(());
+    };
+    let chat_id = message.chat().id;
+    let key = (chat_id.0, q.from.id.0);
+
+    if let Some(cursor) = data.strip_prefix("recent:") {
+        let state = get_or_create_state(key).await;
+        match get_recently_played(&state, parse_recent_cursor(cursor)).await {
+            Ok((response, keyboard)) => {
+                bot.edit_message_text(chat_id, message.id(), response)
+                    .parse_mode(teloxide::types::ParseMode::Html)
+                    .reply_markup(keyboard)
+                    .await?;
+            }
+            Err(e) => {
+                let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                bot.send_message(chat_id, err_msg)
+                    .parse_mode(teloxide::types::ParseMode::Html)
+                    .await?;
+            }
+        }
+    } else if let Some(page) = data.strip_prefix("playlist:") {
+        let state = get_or_create_state(key).await;
+        match get_playlist_page(&state, page).await {
+            Ok((response, keyboard)) => {
+                bot.edit_message_text(chat_id, message.id(), response)
+                    .parse_mode(teloxide::types::ParseMode::Html)
+                    .reply_markup(keyboard)
+                    .await?;
+            }
+            Err(e) => {
+                let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                bot.send_message(chat_id, err_msg)
+                    .parse_mode(teloxide::types::ParseMode::Html)
+                    .await?;
+            }
+        }
+    } else if let Some(playlist_id) = data.strip_prefix("clean:") {
+        let state = get_or_create_state(key).await;
+        let (response, keyboard) = match remove_unplayable(&state, playlist_id).await {
+            Ok(result) => result,
+            Err(e) => (
+                format!("<b>❌ Error</b>\n\n{}", e),
+                InlineKeyboardMarkup::default(),
+            ),
+        };
+        bot.edit_message_text(chat_id, message.id(), response)
+            .parse_mode(teloxide::types::ParseMode::Html)
+            .reply_markup(keyboard)
+            .await?;
+    } else if let Some(choice) = data.strip_prefix("outlier:") {
+        let state = get_or_create_state(key).await;
+        let (response, keyboard) = match remove_outlier(&state, choice).await {
+            Ok(result) => result,
+            Err(e) => (
+                format!("<b>❌ Error</b>\n\n{}", e),
+                InlineKeyboardMarkup::default(),
+            ),
+        };
+        // Other outlier buttons stay usable, so reply instead of editing
+        send_html_with_keyboard(&bot, chat_id, &response, keyboard).await?;
+    } else if data == "rediscover:create" {
+        let state = get_or_create_state(key).await;
+        match create_rediscover_playlist(&state).await {
+            Ok(response) => {
+                // The list stays readable; only the button goes
+                bot.edit_message_reply_markup(chat_id, message.id()).await?;
+                send_html(&bot, chat_id, &response).await?;
+            }
+            Err(e) => {
+                let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                bot.send_message(chat_id, err_msg)
+                    .parse_mode(teloxide::types::ParseMode::Html)
+                    .await?;
+            }
+        }
+    } else if let Some(backup) = data.strip_prefix("undo:") {
+        let state = get_or_create_state(key).await;
+        match undo_change(&state, backup).await {
+            Ok(response) => {
+                // A change is undone once; the button goes with it
+                bot.edit_message_reply_markup(chat_id, message.id()).await?;
+                send_html(&bot, chat_id, &response).await?;
+            }
+            Err(e) => {
+                let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                bot.send_message(chat_id, err_msg)
+                    .parse_mode(teloxide::types::ParseMode::Html)
+                    .await?;
+            }
+        }
+    } else if let Some(choice) = data.strip_prefix("add:") {
+        let state = get_or_create_state(key).await;
+        let response = match add_chosen_track(&state, choice).await {
+            Ok(response) => response,
+            Err(e) => format!("<b>❌ Error</b>\n\n{}", e),
+        };
+        bot.edit_message_text(chat_id, message.id(), response)
+            .parse_mode(teloxide::types::ParseMode::Html)
+            .await?;
+    } else if let Some(action) = data.strip_prefix("logout:") {
+        let state = get_or_create_state(key).await;
+        let response = match action {
+            "confirm" => logout(&state).await,
+            _ => "👍 <b>Still Connected</b>\n\nYour Spotify account stays linked.".to_string(),
+        };
+        bot.edit_message_text(chat_id, message.id(), response)
+            .parse_mode(teloxide::types::ParseMode::Html)
+            .await?;
+    }
+
+    Ok(())
 }
 
 async fn handle_commands(
@@ -32,23 +238,86 @@ async fn handle_commands(
     cmd: Command,
 ) -> Result<(), teloxide::RequestError> {
     let chat_id = msg.chat.id;
+    // Channel posts have no sender; they all share one anonymous slot
+    let key = (chat_id.0, msg.from.as_ref().map_or(0, |user| user.id.0));
+
+    // Remembered so group members can be addressed by @username
+    if let Some(username) = msg.from.as_ref().and_then(|user| user.username.clone()) {
+        *get_or_create_state(key).await.username.lock().await = Some(username);
+    }
+
+    if let Some(text) = msg.text() {
+        usage::record_command(text).await;
+    }
 
     match cmd {
         Command::Help => {
             let help_text = "<b>🎵 Spotify Dashboard Bot</b>\n\n\
                  <b>Available Commands:</b>\n\n\
                  <code>/login</code> - Authenticate with Spotify\n\
+                 <code>/logout</code> - Disconnect your Spotify account\n\
                  <code>/me</code> - View your profile\n\
-                 <code>/top_tracks</code> - Your 10 most played tracks\n\
-                 <code>/top_artists</code> - Your 10 most played artists\n\
+                 <code>/top_tracks [count] [short|medium|long] [genre]</code> - Your most played tracks\n\
+                 <code>/top_artists [count] [short|medium|long]</code> - Your most played artists\n\
+                 <code>/labels [short|medium|long]</code> - Record labels and eras of your top tracks\n\
+                 <code>/top_defaults [count] [range]</code> - Default size and range of the top lists\n\
                  <code>/recently_played</code> - Last 10 tracks you played\n\
+                 <code>/patterns</code> - When you listen during the week\n\
+                 <code>/mood_today</code> - Your day in moods\n\
+                 <code>/discovery</code> - New artists vs. your favorites\n\
+                 <code>/rediscover</code> - Old favorites you stopped playing\n\
+                 <code>/skips [on|off]</code> - Tracks you skip the most\n\
+                 <code>/streak [on|off]</code> - Daily listening streak and milestone alerts\n\
+                 <code>/new_releases [on|off]</code> - New music from artists you follow\n\
+                 <code>/archive [on|off]</code> - Keep a weekly copy of Discover Weekly\n\
+                 <code>/events</code> - Upcoming concerts of your top artists\n\
+                 <code>/group_vibe</code> - Shared favorites of everyone connected in this chat\n\
+                 <code>/blend @user</code> - Make a 50/50 playlist with another member\n\
+                 <code>/compare @user</code> - How well your tastes match\n\
+                 <code>/compare_tracks A | B</code> - Audio features of two tracks side by side\n\
+                 <code>/tracks link1 link2 ...</code> - Details of many tracks at once\n\
+                 <code>/locale [en|vi]</code> - How dates and numbers are shown\n\
+                 <code>/market [XX|auto]</code> - Country used for availability\n\
+                 <code>/explicit_filter [on|off]</code> - Hide explicit tracks\n\
+                 <code>/webhook [url secret|test|off]</code> - Send plays and releases to your URL\n\
+                 <code>/mqtt [on|off]</code> - Publish what you're playing to Home Assistant\n\
+                 <code>/sleep minutes [wind]</code> - Pause playback later, optionally winding down\n\
+                 <code>/like</code> / <code>/unlike</code> - Save or remove the playing track\n\
+                 <code>/podcasts</code> - Latest episodes of the shows you follow\n\
+                 <code>/albums</code> - Albums saved in your library\n\
+                 <code>/shows</code> - Podcasts and audiobooks you follow\n\
+                 <code>/new_in_library</code> - What you saved lately\n\
                  <code>/search query</code> - Search for a track\n\
                  <code>/playlists</code> - List your playlists\n\
                  <code>/playlist name</code> - View playlist details\n\
                  <code>/create_playlist name</code> - Create a new playlist\n\
-                 <code>/add_to_playlist song | playlist</code> - Add song to playlist\n\n\
+                 <code>/add_to_playlist song | playlist</code> - Add song to playlist\n\
+                 <code>/fill_playlist name from top|recent|liked [mood] [genre] [count]</code> - Add many at once\n\
+                 <code>/run_playlist bpm [tolerance]</code> - Running playlist at your pace\n\
+                 <code>/party_playlist [minutes]</code> - Danceable mix with smooth transitions\n\
+                 <code>/backup_playlist name</code> - Save the current track order\n\
+                 <code>/restore_playlist name [| n]</code> - List or restore backups\n\
+                 <code>/analyze_playlist name</code> - Tempo, energy, genres and moods of a playlist\n\
+                 <code>/playlist_outliers name</code> - Tracks that don't fit a playlist\n\
+                 <code>/smart_shuffle playlist [| arc|rise|fall]</code> - Reorder by energy\n\
+                 <code>/harmonic_sort name</code> - Reorder so neighbouring keys mix well\n\
+                 <code>/staleness name [| weeks]</code> - Tracks nobody played in weeks\n\
+                 <code>/rotate name [| weeks|off]</code> - Drop those tracks automatically\n\
+                 <code>/clean_playlist name</code> - Find and remove unavailable tracks\n\
+                 <code>/set_cover name</code> - Reply to a photo to make it a playlist cover\n\
+                 <code>/preview command args</code> - See what a playlist command would change\n\
+                 <code>/history_actions</code> - What the bot changed on your account\n\n\
                  <b>Getting Started:</b>\n\
                  Tap <code>/login</code> to connect your Spotify account.";
+            let mut help_text = flags::hide_commands(help_text, |command| {
+                flags::blocked(command_features(command)).is_some()
+            });
+            if flags::read_only() {
+                help_text.insert_str(
+                    0,
+                    "🔒 <b>Read-only mode</b> — this bot never changes your Spotify account\n\n",
+                );
+            }
             bot.send_message(chat_id, help_text)
                 .parse_mode(teloxide::types::ParseMode::Html)
                 .await?;
@@ -56,7 +325,11 @@ async fn handle_commands(
 
         Command::Login => {
             let spotify = AuthCodeSpotify::new(spotify_credentials(), spotify_oauth());
-            let url = match spotify.get_authorize_url(false) {
+            let url = match spotify
+                .get_authorize_url(false)
+                .map_err(|e| e.to_string())
+                .and_then(|u| u.parse::<reqwest::Url>().map_err(|e| e.to_string()))
+            {
                 Ok(u) => u,
                 Err(e) => {
                     error!("Failed to get auth URL: {e}");
@@ -73,7 +346,7 @@ async fn handle_commands(
             let kb =
                 InlineKeyboardMarkup::new(vec![vec![teloxide::types::InlineKeyboardButton::url(
                     "🔐 Login with Spotify".to_string(),
-                    url.parse().expect("valid URL"),
+                    url,
                 )]]);
 
             let login_msg = "<b>🎵 Spotify Authentication</b>\n\n\
@@ -86,13 +359,32 @@ async fn handle_commands(
                 .await?;
         }
 
+        Command::Logout => {
+            let state = get_or_create_state(key).await;
+            if state.spotify.lock().await.is_none() {
+                bot.send_message(chat_id, "ℹ️ You're not connected to Spotify.")
+                    .await?;
+                return Ok(());
+            }
+
+            let kb = InlineKeyboardMarkup::new(vec![vec![
+                InlineKeyboardButton::callback("Yes, disconnect", "logout:confirm"),
+                InlineKeyboardButton::callback("Cancel", "logout:cancel"),
+            ]]);
+            let confirm_msg = "<b>🔌 Disconnect Spotify?</b>\n\n\
+                               The bot will forget your Spotify session for this chat and \
+                               stop streak and release alerts.";
+            bot.send_message(chat_id, confirm_msg)
+                .parse_mode(teloxide::types::ParseMode::Html)
+                .reply_markup(kb)
+                .await?;
+        }
+
         Command::Me => {
-            let state = get_or_create_state(chat_id.0).await;
+            let state = get_or_create_state(key).await;
             match get_me(&state).await {
                 Ok(response) => {
-                    bot.send_message(chat_id, response)
-                        .parse_mode(teloxide::types::ParseMode::Html)
-                        .await?;
+                    send_html(&bot, chat_id, &response).await?;
                 }
                 Err(e) => {
                     let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
@@ -103,14 +395,27 @@ async fn handle_commands(
             }
         }
 
-        Command::TopTracks => {
-            let state = get_or_create_state(chat_id.0).await;
-            match get_top_tracks(&state).await {
+        Command::TopTracks(args) => {
+            let state = get_or_create_state(key).await;
+            match get_top_tracks(&state, &args).await {
                 Ok(response) => {
-                    bot.send_message(chat_id, response)
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
                         .parse_mode(teloxide::types::ParseMode::Html)
                         .await?;
                 }
+            }
+        }
+
+        Command::Labels(args) => {
+            let state = get_or_create_state(key).await;
+            match get_labels(&state, &args).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
                 Err(e) => {
                     let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
                     bot.send_message(chat_id, err_msg)
@@ -120,14 +425,27 @@ async fn handle_commands(
             }
         }
 
-        Command::TopArtists => {
-            let state = get_or_create_state(chat_id.0).await;
-            match get_top_artists(&state).await {
+        Command::TopArtists(args) => {
+            let state = get_or_create_state(key).await;
+            match get_top_artists(&state, &args).await {
                 Ok(response) => {
-                    bot.send_message(chat_id, response)
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
                         .parse_mode(teloxide::types::ParseMode::Html)
                         .await?;
                 }
+            }
+        }
+
+        Command::RecentlyPlayed => {
+            let state = get_or_create_state(key).await;
+            match get_recently_played(&state, None).await {
+                Ok((response, keyboard)) => {
+                    send_html_with_keyboard(&bot, chat_id, &response, keyboard).await?;
+                }
                 Err(e) => {
                     let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
                     bot.send_message(chat_id, err_msg)
@@ -137,14 +455,27 @@ async fn handle_commands(
             }
         }
 
-        Command::RecentlyPlayed => {
-            let state = get_or_create_state(chat_id.0).await;
-            match get_recently_played(&state).await {
+        Command::MoodToday => {
+            let state = get_or_create_state(key).await;
+            match get_mood_today(&state).await {
                 Ok(response) => {
-                    bot.send_message(chat_id, response)
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
                         .parse_mode(teloxide::types::ParseMode::Html)
                         .await?;
                 }
+            }
+        }
+
+        Command::Discovery => {
+            let state = get_or_create_state(key).await;
+            match get_discovery(&state).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
                 Err(e) => {
                     let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
                     bot.send_message(chat_id, err_msg)
@@ -154,14 +485,27 @@ async fn handle_commands(
             }
         }
 
-        Command::Search(query) => {
-            let state = get_or_create_state(chat_id.0).await;
-            match search_track(&state, &query).await {
+        Command::Skips(arg) => {
+            let state = get_or_create_state(key).await;
+            match get_skips(&state, &arg).await {
                 Ok(response) => {
-                    bot.send_message(chat_id, response)
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
                         .parse_mode(teloxide::types::ParseMode::Html)
                         .await?;
                 }
+            }
+        }
+
+        Command::Archive(arg) => {
+            let state = get_or_create_state(key).await;
+            match set_archive(&state, &arg).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
                 Err(e) => {
                     let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
                     bot.send_message(chat_id, err_msg)
@@ -171,14 +515,27 @@ async fn handle_commands(
             }
         }
 
-        Command::Playlists => {
-            let state = get_or_create_state(chat_id.0).await;
-            match list_playlists(&state).await {
+        Command::Patterns => {
+            let state = get_or_create_state(key).await;
+            match get_patterns(&state).await {
                 Ok(response) => {
-                    bot.send_message(chat_id, response)
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
                         .parse_mode(teloxide::types::ParseMode::Html)
                         .await?;
                 }
+            }
+        }
+
+        Command::Streak(arg) => {
+            let state = get_or_create_state(key).await;
+            match get_streak(&state, &arg).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
                 Err(e) => {
                     let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
                     bot.send_message(chat_id, err_msg)
@@ -188,14 +545,27 @@ async fn handle_commands(
             }
         }
 
-        Command::Playlist(playlist_name) => {
-            let state = get_or_create_state(chat_id.0).await;
-            match get_playlist(&state, &playlist_name).await {
+        Command::NewReleases(arg) => {
+            let state = get_or_create_state(key).await;
+            match get_new_releases(&state, &arg).await {
                 Ok(response) => {
-                    bot.send_message(chat_id, response)
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
                         .parse_mode(teloxide::types::ParseMode::Html)
                         .await?;
                 }
+            }
+        }
+
+        Command::Events => {
+            let state = get_or_create_state(key).await;
+            match get_events(&state).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
                 Err(e) => {
                     let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
                     bot.send_message(chat_id, err_msg)
@@ -205,14 +575,28 @@ async fn handle_commands(
             }
         }
 
-        Command::CreatePlaylist(playlist_name) => {
-            let state = get_or_create_state(chat_id.0).await;
-            match create_playlist(&state, &playlist_name).await {
+        Command::Like | Command::Unlike => {
+            let state = get_or_create_state(key).await;
+            match set_liked(&state, matches!(cmd, Command::Like)).await {
                 Ok(response) => {
-                    bot.send_message(chat_id, response)
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
                         .parse_mode(teloxide::types::ParseMode::Html)
                         .await?;
                 }
+            }
+        }
+
+        Command::GroupVibe => {
+            let state = get_or_create_state(key).await;
+            let locale = *state.locale.lock().await;
+            match get_group_vibe(chat_id.0, locale).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
                 Err(e) => {
                     let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
                     bot.send_message(chat_id, err_msg)
@@ -222,28 +606,75 @@ async fn handle_commands(
             }
         }
 
-        Command::AddToPlaylist(input) => {
-            let state = get_or_create_state(chat_id.0).await;
-            // Parse input: "song_name | playlist_name"
-            let parts: Vec<&str> = input.split('|').collect();
-            if parts.len() != 2 {
-                let err_msg = "<b>❌ Invalid Format</b>\n\n\
-                               Usage: <code>/add_to_playlist song_name | playlist_name</code>";
-                bot.send_message(chat_id, err_msg)
-                    .parse_mode(teloxide::types::ParseMode::Html)
-                    .await?;
-                return Ok(());
+        Command::Blend(target) => {
+            let state = get_or_create_state(key).await;
+            let initiator = msg
+                .from
+                .as_ref()
+                .map_or_else(|| "You".to_string(), |user| user.first_name.clone());
+            let result = match resolve_member(&msg, &target).await {
+                Some((user_id, _)) if user_id == key.1 => {
+                    Err("You can't blend with yourself.".to_string())
+                }
+                Some((user_id, partner_name)) => {
+                    let partner_state = get_or_create_state((chat_id.0, user_id)).await;
+                    create_blend(&state, &partner_state, &initiator, &partner_name).await
+                }
+                None => Err(
+                    "Usage: <code>/blend @username</code>, or reply to a message of \
+                     the member you want to blend with. They need to have used the bot in \
+                     this chat."
+                        .to_string(),
+                ),
+            };
+            match result {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
             }
+        }
 
-            let song_name = parts[0].trim();
-            let playlist_name = parts[1].trim();
-
-            match add_to_playlist(&state, song_name, playlist_name).await {
+        Command::Compare(target) => {
+            let state = get_or_create_state(key).await;
+            let result = match resolve_member(&msg, &target).await {
+                Some((user_id, _)) if user_id == key.1 => {
+                    Err("You always match yourself 100%.".to_string())
+                }
+                Some((user_id, partner_name)) => {
+                    let partner_state = get_or_create_state((chat_id.0, user_id)).await;
+                    compare_tastes(&state, &partner_state, &partner_name).await
+                }
+                None => Err(
+                    "Usage: <code>/compare @username</code>, or reply to a message of \
+                     the member to compare with. They need to have used the bot in this chat."
+                        .to_string(),
+                ),
+            };
+            match result {
                 Ok(response) => {
-                    bot.send_message(chat_id, response)
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
                         .parse_mode(teloxide::types::ParseMode::Html)
                         .await?;
                 }
+            }
+        }
+
+        Command::CompareTracks(input) => {
+            let state = get_or_create_state(key).await;
+            match compare_tracks(&state, &input).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
                 Err(e) => {
                     let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
                     bot.send_message(chat_id, err_msg)
@@ -252,373 +683,6220 @@ async fn handle_commands(
                 }
             }
         }
-    }
 
-    Ok(())
-}
+        Command::Tracks(input) => {
+            let state = get_or_create_state(key).await;
+            match get_tracks(&state, &input).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
 
-async fn get_or_create_state(chat_id: i64) -> AppState {
-    let mut states = CHAT_STATES.lock().await;
-    states
-        .entry(chat_id)
-        .or_insert_with(|| AppState {
-            spotify: std::sync::Arc::new(tokio::sync::Mutex::new(None)),
-        })
-        .clone()
-}
+        Command::Podcasts => {
+            let state = get_or_create_state(key).await;
+            match get_podcasts(&state).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
 
-async fn get_me(state: &AppState) -> Result<String, String> {
-    let guard = state.spotify.lock().await;
-    let spotify = guard
-        .as_ref()
-        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+        Command::Albums => {
+            let state = get_or_create_state(key).await;
+            match get_saved_albums(&state).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
 
-    match spotify.current_user().await {
-        Ok(user) => {
-            let display_name = user.display_name.unwrap_or_else(|| "User".to_string());
-            let email = user.email.unwrap_or_else(|| "No email".to_string());
+        Command::Shows => {
+            let state = get_or_create_state(key).await;
+            match get_saved_shows(&state).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
 
-            let profile = format!(
-                "<b>👤 Your Spotify Profile</b>\n\n\
-                 <b>Name:</b> {}\n\
-                 <b>Email:</b> <code>{}</code>\n\
-                 <b>Status:</b> ✅ Connected",
-                html_escape(&display_name),
-                html_escape(&email)
-            );
-            Ok(profile)
+        Command::Rediscover => {
+            let state = get_or_create_state(key).await;
+            match get_rediscover(&state).await {
+                Ok((response, keyboard)) => {
+                    send_html_with_keyboard(&bot, chat_id, &response, keyboard).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
         }
-        Err(err) => {
-            error!("Spotify API error: {:?}", err);
-            Err("Failed to fetch profile. Please try again.".to_string())
+
+        Command::NewInLibrary => {
+            let state = get_or_create_state(key).await;
+            match get_new_in_library(&state).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
         }
-    }
-}
 
-async fn get_top_tracks(state: &AppState) -> Result<String, String> {
-    let guard = state.spotify.lock().await;
+        Command::Search(query) => {
+            let state = get_or_create_state(key).await;
+            match search_track(&state, &query).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Playlists => {
+            let state = get_or_create_state(key).await;
+            match list_playlists(&state).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Playlist(playlist_name) => {
+            let state = get_or_create_state(key).await;
+            match get_playlist(&state, &playlist_name).await {
+                Ok((response, keyboard)) => {
+                    send_html_with_keyboard(&bot, chat_id, &response, keyboard).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::CreatePlaylist(playlist_name) => {
+            let state = get_or_create_state(key).await;
+            match create_playlist(&state, &playlist_name).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::AddToPlaylist(input) => {
+            let state = get_or_create_state(key).await;
+            // Parse input: "song_name | playlist_name"
+            let parts: Vec<&str> = input.split('|').collect();
+            if parts.len() != 2 {
+                let err_msg = "<b>❌ Invalid Format</b>\n\n\
+                               Usage: <code>/add_to_playlist song_name | playlist_name</code>";
+                bot.send_message(chat_id, err_msg)
+                    .parse_mode(teloxide::types::ParseMode::Html)
+                    .await?;
+                return Ok(());
+            }
+
+            let song_name = parts[0].trim();
+            let playlist_name = parts[1].trim();
+
+            match add_to_playlist(&state, song_name, playlist_name).await {
+                Ok((response, keyboard)) => {
+                    send_html_with_keyboard(&bot, chat_id, &response, keyboard).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::FillPlaylist(input) => {
+            let state = get_or_create_state(key).await;
+            match fill_playlist(&state, &input, false).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::RunPlaylist(input) => {
+            let state = get_or_create_state(key).await;
+            match run_playlist(&state, &input, false).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::PartyPlaylist(input) => {
+            let state = get_or_create_state(key).await;
+            match party_playlist(&state, &input, false).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::BackupPlaylist(playlist_name) => {
+            let state = get_or_create_state(key).await;
+            match backup_playlist(&state, &playlist_name).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::RestorePlaylist(input) => {
+            let state = get_or_create_state(key).await;
+            match restore_playlist(&state, &input, false).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::AnalyzePlaylist(playlist_name) => {
+            let state = get_or_create_state(key).await;
+            match analyze_playlist(&state, &playlist_name).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::PlaylistOutliers(playlist_name) => {
+            let state = get_or_create_state(key).await;
+            match playlist_outliers(&state, &playlist_name).await {
+                Ok((response, keyboard)) => {
+                    send_html_with_keyboard(&bot, chat_id, &response, keyboard).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Preview(input) => {
+            let state = get_or_create_state(key).await;
+            match preview_command(&state, &input).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Staleness(input) => {
+            let state = get_or_create_state(key).await;
+            match get_staleness(&state, &input).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Rotate(input) => {
+            let state = get_or_create_state(key).await;
+            match set_rotation(&state, &input).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::HarmonicSort(playlist_name) => {
+            let state = get_or_create_state(key).await;
+            match harmonic_sort(&state, &playlist_name, false).await {
+                Ok((response, keyboard)) => {
+                    send_html_with_keyboard(&bot, chat_id, &response, keyboard).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::SmartShuffle(input) => {
+            let state = get_or_create_state(key).await;
+            match smart_shuffle(&state, &input, false).await {
+                Ok((response, keyboard)) => {
+                    send_html_with_keyboard(&bot, chat_id, &response, keyboard).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::CleanPlaylist(playlist_name) => {
+            let state = get_or_create_state(key).await;
+            match clean_playlist(&state, &playlist_name).await {
+                Ok((response, keyboard)) => {
+                    send_html_with_keyboard(&bot, chat_id, &response, keyboard).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Sleep(input) => {
+            let state = get_or_create_state(key).await;
+            match sleep_timer(&bot, &state, chat_id, &input).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::SetCover(playlist_name) => {
+            let state = get_or_create_state(key).await;
+            match set_cover(&bot, &state, &msg, &playlist_name).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Webhook(input) => {
+            let state = get_or_create_state(key).await;
+            match set_webhook(&state, &input).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::ExplicitFilter(arg) => {
+            let state = get_or_create_state(key).await;
+            match set_explicit_filter(&state, &arg).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Mqtt(arg) => {
+            let state = get_or_create_state(key).await;
+            match set_mqtt(&state, &arg).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Market(arg) => {
+            let state = get_or_create_state(key).await;
+            match set_market(&state, &arg).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::HistoryActions => {
+            let state = get_or_create_state(key).await;
+            match get_history_actions(&state).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::TopDefaults(args) => {
+            let state = get_or_create_state(key).await;
+            match set_top_defaults(&state, &args).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Locale(arg) => {
+            let state = get_or_create_state(key).await;
+            match set_locale(&state, &arg).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Usage => {
+            let state = get_or_create_state(key).await;
+            match get_usage(&state, chat_id.0).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::Jobs => {
+            let state = get_or_create_state(key).await;
+            match get_jobs(&state, chat_id.0).await {
+                Ok(response) => {
+                    send_html(&bot, chat_id, &response).await?;
+                }
+                Err(e) => {
+                    let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                    bot.send_message(chat_id, err_msg)
+                        .parse_mode(teloxide::types::ParseMode::Html)
+                        .await?;
+                }
+            }
+        }
+
+        Command::RunJob(name) => match run_job(chat_id.0, &name).await {
+            Ok(response) => {
+                send_html(&bot, chat_id, &response).await?;
+            }
+            Err(e) => {
+                let err_msg = format!("<b>❌ Error</b>\n\n{}", e);
+                bot.send_message(chat_id, err_msg)
+                    .parse_mode(teloxide::types::ParseMode::Html)
+                    .await?;
+            }
+        },
+    }
+
+    Ok(())
+}
+
+async fn get_or_create_state(key: StateKey) -> AppState {
+    let mut states = CHAT_STATES.lock().await;
+    states.entry(key).or_insert_with(AppState::default).clone()
+}
+
+/// Snapshot of every chat member that has interacted with the bot
+pub(crate) async fn all_states() -> Vec<(StateKey, AppState)> {
+    let states = CHAT_STATES.lock().await;
+    states
+        .iter()
+        .map(|(key, state)| (*key, state.clone()))
+        .collect()
+}
+
+/// Members of one chat that have interacted with the bot, by user ID
+async fn chat_members(chat_id: i64) -> Vec<(u64, AppState)> {
+    let states = CHAT_STATES.lock().await;
+    states
+        .iter()
+        .filter(|((chat, _), _)| *chat == chat_id)
+        .map(|((_, user), state)| (*user, state.clone()))
+        .collect()
+}
+
+/// Forget the chat's Spotify client and token; alerts can't run without them
+async fn logout(state: &AppState) -> String {
+    if state.spotify.lock().await.take().is_none() {
+        return "ℹ️ You're not connected to Spotify.".to_string();
+    }
+    state.streak.lock().await.notify = false;
+    state.releases.lock().await.notify = false;
+
+    "👋 <b>Disconnected</b>\n\nYour Spotify session was removed from this chat. \
+     Use <code>/login</code> to connect again."
+        .to_string()
+}
+
+async fn get_me(state: &AppState) -> Result<String, String> {
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    match spotify.current_user().await {
+        Ok(user) => {
+            let user = UserProfile::from(user);
+            let display_name = user.display_name.unwrap_or_else(|| "User".to_string());
+            let email = user.email.unwrap_or_else(|| "No email".to_string());
+            let locale = *state.locale.lock().await;
+
+            let mut profile = format!(
+                "<b>👤 Your Spotify Profile</b>\n\n\
+                 <b>Name:</b> {}\n\
+                 <b>Email:</b> <code>{}</code>\n\
+                 <b>Country:</b> {}\n\
+                 <b>Plan:</b> {}\n\
+                 <b>Followers:</b> {}\n\
+                 <b>Status:</b> ✅ Connected",
+                spotify_link(Entity::User, Some(&user.id), &display_name),
+                html_escape(&email),
+                user.country.as_deref().unwrap_or("Unknown"),
+                match user.product.as_deref() {
+                    Some("premium") => "Premium",
+                    Some(_) => "Free",
+                    None => "Unknown",
+                },
+                format_count(user.followers as u64, locale)
+            );
+            if let Some(avatar) = &user.avatar_url {
+                profile.push_str(&format!(
+                    "\n<a href=\"{}\">🖼️ Avatar</a>",
+                    html_escape(avatar)
+                ));
+            }
+            Ok(profile)
+        }
+        Err(err) => {
+            error!("Spotify API error: {:?}", err);
+            Err("Failed to fetch profile. Please try again.".to_string())
+        }
+    }
+}
+
+async fn get_top_tracks(state: &AppState, args: &str) -> Result<String, String> {
+    let args = ListArgs::parse(args).map_err(|problems| {
+        list_args_error(
+            &problems,
+            "<code>/top_tracks [count] [short|medium|long] [genre]</code>",
+        )
+    })?;
+    let (count, range) = args.resolve(*state.list_defaults.lock().await);
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    // A genre filter drops tracks, so start from the whole ranking
+    let wanted = if args.genre.is_some() {
+        MAX_COUNT
+    } else {
+        count
+    };
+    let stream = spotify.current_user_top_tracks(Some(convert::time_range(range)));
+    let mut tracks = collect_stream_n(stream, wanted, Track::from)
+        .await
+        .map_err(|_| "Failed to fetch top tracks. Please try again.".to_string())?;
+
+    if tracks.is_empty() {
+        return Ok("📭 No top tracks found. Start listening to see your favorites!".to_string());
+    }
+
+    if let Some(genre) = args.genre {
+        tracks = tracks_of_genre(state, spotify, tracks, range, genre).await?;
+        tracks.truncate(count);
+        if tracks.is_empty() {
+            return Ok(format!(
+                "📭 None of your top {} tracks ({}) sound like {}.",
+                MAX_COUNT,
+                range_label(range),
+                genre.as_str()
+            ));
+        }
+    }
+
+    let title = match args.genre {
+        Some(genre) => format!("Your Top {} Tracks", html_escape(genre.as_str())),
+        None => "Your Top Tracks".to_string(),
+    };
+    let mut response = format!("<b>🎵 {}</b>\n<i>{}</i>\n\n", title, range_label(range));
+    for (idx, track) in tracks.iter().enumerate() {
+        let artists = track.artists.join(", ");
+        response.push_str(&format!(
+            "<b>{}</b>. {}\n<i>{}</i>\n{}\n\n",
+            idx + 1,
+            spotify_link(Entity::Track, track.id.as_deref(), &track.name),
+            html_escape(&artists),
+            track_details(track)
+        ));
+    }
+
+    Ok(response)
+}
+
+/// Tracks the genre detector puts in `genre`, using audio features plus the
+/// genre tags of the user's top artists over the same range
+async fn tracks_of_genre(
+    state: &AppState,
+    spotify: &AuthCodeSpotify,
+    tracks: Vec<Track>,
+    range: TopRange,
+    genre: Genre,
+) -> Result<Vec<Track>, String> {
+    let ids: Vec<String> = tracks.iter().filter_map(|track| track.id.clone()).collect();
+    let features = fetch_audio_features(spotify, &ids)
+        .await
+        .map_err(|_| "Failed to fetch audio features. Please try again.".to_string())?;
+    let artist_genres = top_artist_genres(state, spotify, range).await;
+
+    Ok(tracks
+        .into_iter()
+        .filter(|track| {
+            let Some(f) = track.id.as_deref().and_then(|id| features.get(id)) else {
+                return false;
+            };
+            backend()
+                .classify_genre(*f, &artist_tags(track, &artist_genres), track.popularity)
+                .genre
+                == genre
+        })
+        .collect())
+}
+
+/// Genre tags of the user's top artists over `range`, by artist name. Tags
+/// only sharpen the genre detection, so there are none when the fetch fails.
+async fn top_artist_genres(
+    state: &AppState,
+    spotify: &AuthCodeSpotify,
+    range: TopRange,
+) -> HashMap<String, Vec<String>> {
+    top_artists(state, spotify, range)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|artist| (artist.name, artist.genres))
+        .collect()
+}
+
+/// Tags of whichever of the track's artists are in `artist_genres`
+fn artist_tags(track: &Track, artist_genres: &HashMap<String, Vec<String>>) -> Vec<String> {
+    track
+        .artists
+        .iter()
+        .filter_map(|name| artist_genres.get(name))
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+/// Which record labels release the user's top tracks, and from which eras
+async fn get_labels(state: &AppState, args: &str) -> Result<String, String> {
+    const MAX_LABELS: usize = 10;
+
+    let (_, range) = ListArgs::parse_without_genre(args)
+        .map_err(|problems| list_args_error(&problems, "<code>/labels [short|medium|long]</code>"))?
+        .resolve(*state.list_defaults.lock().await);
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    let stream = spotify.current_user_top_tracks(Some(convert::time_range(range)));
+    let tracks = collect_stream_n(stream, MAX_COUNT, Track::from)
+        .await
+        .map_err(|_| "Failed to fetch top tracks. Please try again.".to_string())?;
+    if tracks.is_empty() {
+        return Ok("📭 No top tracks found. Start listening to see your favorites!".to_string());
+    }
+
+    let album_ids: Vec<String> = tracks
+        .iter()
+        .filter_map(|track| track.album_id.clone())
+        .collect();
+    let labels = fetch_album_labels(spotify, &album_ids)
+        .await
+        .map_err(|_| "Failed to fetch album details. Please try again.".to_string())?;
+    let breakdown = share_breakdown(
+        tracks
+            .iter()
+            .filter_map(|track| labels.get(track.album_id.as_deref()?).map(String::as_str)),
+    );
+    let years: Vec<i32> = tracks
+        .iter()
+        .filter_map(|track| release_year(track.release_date.as_deref()?))
+        .collect();
+
+    let locale = *state.locale.lock().await;
+    let percent = |share: f32| format_number(share as f64 * 100.0, 0, locale);
+    let mut response = format!(
+        "<b>🏷 Your Labels</b>\n<i>top {} tracks · {}</i>\n\n",
+        tracks.len(),
+        range_label(range)
+    );
+    if breakdown.is_empty() {
+        response.push_str("Spotify lists no label for these albums.\n");
+    }
+    for (label, share) in breakdown.iter().take(MAX_LABELS) {
+        response.push_str(&format!(
+            "• {} — {}%\n",
+            html_escape(label),
+            percent(*share)
+        ));
+    }
+
+    let decades = decade_breakdown(&years);
+    if !decades.is_empty() {
+        response.push_str("\n<b>📅 Release Eras</b>\n");
+        for (decade, share) in &decades {
+            response.push_str(&format!("• {}s — {}%\n", decade, percent(*share)));
+        }
+    }
+
+    Ok(response)
+}
+
+async fn get_top_artists(state: &AppState, args: &str) -> Result<String, String> {
+    let (count, range) = ListArgs::parse_without_genre(args)
+        .map_err(|problems| {
+            list_args_error(
+                &problems,
+                "<code>/top_artists [count] [short|medium|long]</code>",
+            )
+        })?
+        .resolve(*state.list_defaults.lock().await);
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    let stream = spotify.current_user_top_artists(Some(convert::time_range(range)));
+    let artists = collect_stream_n(stream, count, Artist::from)
+        .await
+        .map_err(|_| "Failed to fetch top artists. Please try again.".to_string())?;
+
+    if artists.is_empty() {
+        return Ok(
+            "📭 No top artists found. Start following artists to see your favorites!".to_string(),
+        );
+    }
+
+    let mut response = format!(
+        "<b>🎤 Your Top Artists</b>\n<i>{}</i>\n\n",
+        range_label(range)
+    );
+    for (idx, artist) in artists.iter().enumerate() {
+        let genres = if !artist.genres.is_empty() {
+            format!("\n<i>{}</i>", html_escape(&artist.genres.join(", ")))
+        } else {
+            String::new()
+        };
+        response.push_str(&format!(
+            "<b>{}</b>. {}{}\n\n",
+            idx + 1,
+            spotify_link(Entity::Artist, Some(&artist.id), &artist.name),
+            genres
+        ));
+    }
+
+    Ok(response)
+}
+
+async fn get_recently_played(
+    state: &AppState,
+    cursor: Option<TimeLimits>,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    const PAGE_SIZE: u32 = 10;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    let is_first_page = cursor.is_none();
+    // Coming back from older plays means there is always something older
+    let came_from_older = matches!(cursor, Some(TimeLimits::After(_)));
+    let page = spotify
+        .current_user_recently_played(Some(PAGE_SIZE), cursor)
+        .await
+        .map_err(|_| "Failed to fetch recent tracks. Please try again.".to_string())?;
+    let has_older = page.next.is_some() || came_from_older;
+    let mut plays: Vec<Play> = page.items.into_iter().map(Play::from).collect();
+
+    state
+        .streak
+        .lock()
+        .await
+        .record(plays.iter().map(|play| &play.played_at));
+    record_plays(state, &plays).await;
+
+    if plays.is_empty() {
+        let (text, keyboard) = if is_first_page {
+            ("📭 No recently played tracks found.", vec![])
+        } else {
+            (
+                "📭 No more plays in this direction.",
+                vec![vec![InlineKeyboardButton::callback(
+                    "⏮ Latest",
+                    "recent:latest",
+                )]],
+            )
+        };
+        return Ok((text.to_string(), InlineKeyboardMarkup::new(keyboard)));
+    }
+
+    // Look up each distinct playlist/album/artist once
+    let mut contexts: Vec<(ContextKind, String)> = plays
+        .iter()
+        .filter_map(|play| play.context.as_ref())
+        .map(|context| (context.kind, context.id.clone()))
+        .collect();
+    contexts.sort();
+    contexts.dedup();
+    let names = fetch_all(
+        contexts.clone(),
+        SPOTIFY_CONCURRENCY,
+        |(kind, id)| async move { Ok::<_, ()>(context_name(spotify, kind, &id).await) },
+    )
+    .await
+    .unwrap_or_default();
+    let names: std::collections::HashMap<_, _> = contexts
+        .into_iter()
+        .zip(names)
+        .filter_map(|(key, name)| Some((key, name?)))
+        .collect();
+    for context in plays.iter_mut().filter_map(|play| play.context.as_mut()) {
+        context.name = names.get(&(context.kind, context.id.clone())).cloned();
+    }
+
+    // Energy levels run oldest to newest so the hysteresis follows playback;
+    // the annotation is optional, so a failed lookup only leaves it out
+    let ids: Vec<String> = plays
+        .iter()
+        .filter_map(|play| play.track.id.clone())
+        .collect();
+    let features = fetch_audio_features(spotify, &ids)
+        .await
+        .unwrap_or_default();
+    let scores: Vec<Option<f32>> = plays
+        .iter()
+        .rev()
+        .map(|play| features.get(play.track.id.as_deref()?).map(energy_score))
+        .collect();
+    let known: Vec<f32> = scores.iter().flatten().copied().collect();
+    let mut known_levels = level_sequence(&known).into_iter();
+    let mut levels: Vec<Option<u8>> = scores
+        .iter()
+        .map(|score| score.and_then(|_| known_levels.next()))
+        .collect();
+    levels.reverse();
+
+    let locale = *state.locale.lock().await;
+    let mut response = "<b>⏱️ Recently Played</b>\n\n".to_string();
+    for (idx, (play, level)) in plays.iter().zip(&levels).enumerate() {
+        let energy = level
+            .map(|level| format!(" · ⚡{}", meter(level)))
+            .unwrap_or_default();
+        response.push_str(&format!(
+            "<b>{}</b>. {}\n<i>{}</i> · {}{}\n",
+            idx + 1,
+            spotify_link(Entity::Track, play.track.id.as_deref(), &play.track.name),
+            html_escape(&play.track.artists.join(", ")),
+            format_datetime(play.played_at, locale),
+            energy
+        ));
+        if let Some(source) = play.context.as_ref().and_then(describe_context) {
+            response.push_str(&format!("📂 {}\n", source));
+        }
+        response.push('\n');
+    }
+
+    // Plays are ordered newest first, and the list is not empty here
+    let newest = plays[0].played_at.timestamp_millis();
+    let oldest = plays[plays.len() - 1].played_at.timestamp_millis();
+    let mut buttons = Vec::new();
+    if !is_first_page {
+        buttons.push(InlineKeyboardButton::callback(
+            "◀ Newer",
+            format!("recent:after:{}", newest),
+        ));
+    }
+    if has_older {
+        buttons.push(InlineKeyboardButton::callback(
+            "Older ▶",
+            format!("recent:before:{}", oldest),
+        ));
+    }
+
+    Ok((response, InlineKeyboardMarkup::new(vec![buttons])))
+}
+
+async fn context_name(spotify: &AuthCodeSpotify, kind: ContextKind, id: &str) -> Option<String> {
+    match kind {
+        ContextKind::Playlist => spotify
+            .playlist(convert::playlist_id(id)?, None, None)
+            .await
+            .ok()
+            .map(|playlist| playlist.name),
+        ContextKind::Album => spotify
+            .album(convert::album_id(id)?, None)
+            .await
+            .ok()
+            .map(|album| album.name),
+        ContextKind::Artist => spotify
+            .artist(convert::artist_id(id)?)
+            .await
+            .ok()
+            .map(|artist| artist.name),
+        ContextKind::LikedSongs | ContextKind::Other => None,
+    }
+}
+
+fn describe_context(context: &PlayContext) -> Option<String> {
+    let kind = match context.kind {
+        ContextKind::Playlist => "playlist",
+        ContextKind::Album => "album",
+        ContextKind::Artist => "artist",
+        ContextKind::LikedSongs => return Some("from <b>Liked Songs</b>".to_string()),
+        ContextKind::Other => return None,
+    };
+    Some(match &context.name {
+        Some(name) => format!("from {} <b>{}</b>", kind, html_escape(name)),
+        None => format!("from a {}", kind),
+    })
+}
+
+// Callback data is "latest", "before:<ms>" or "after:<ms>"
+fn parse_recent_cursor(data: &str) -> Option<TimeLimits> {
+    let (direction, millis) = data.split_once(':')?;
+    let timestamp = chrono::DateTime::from_timestamp_millis(millis.parse().ok()?)?;
+    match direction {
+        "before" => Some(TimeLimits::Before(timestamp)),
+        "after" => Some(TimeLimits::After(timestamp)),
+        _ => None,
+    }
+}
+
+async fn get_patterns(state: &AppState) -> Result<String, String> {
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    // Spotify only exposes the last 50 plays, so patterns are based on those
+    let plays: Vec<Play> = spotify
+        .current_user_recently_played(Some(50), None)
+        .await
+        .map_err(|_| "Failed to fetch recent tracks. Please try again.".to_string())?
+        .items
+        .into_iter()
+        .map(Play::from)
+        .collect();
+
+    state
+        .streak
+        .lock()
+        .await
+        .record(plays.iter().map(|play| &play.played_at));
+    record_plays(state, &plays).await;
+
+    if plays.is_empty() {
+        return Ok("📭 No recently played tracks found.".to_string());
+    }
+
+    let played_at: Vec<_> = plays.iter().map(|play| play.played_at).collect();
+    let patterns = analyze_patterns(&played_at);
+    let locale = *state.locale.lock().await;
+
+    let mut response = format!(
+        "<b>🗓️ Listening Patterns</b>\n<i>Last {} plays, UTC</i>\n\n<code>{}</code>\n",
+        patterns.total_plays,
+        render_heatmap(&patterns)
+    );
+    if let Some(hour) = patterns.most_active_hour {
+        response.push_str(&format!(
+            "<b>Most active hour:</b> {:02}:00–{:02}:59\n",
+            hour, hour
+        ));
+    }
+    response.push_str(&format!(
+        "<b>Weekdays:</b> {} plays\n<b>Weekend:</b> {} plays ({}%)",
+        patterns.weekday_plays,
+        patterns.weekend_plays,
+        format_number(patterns.weekend_ratio() as f64 * 100.0, 0, locale)
+    ));
+
+    Ok(response)
+}
+
+async fn get_mood_today(state: &AppState) -> Result<String, String> {
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    let plays: Vec<Play> = spotify
+        .current_user_recently_played(Some(50), None)
+        .await
+        .map_err(|_| "Failed to fetch recent tracks. Please try again.".to_string())?
+        .items
+        .into_iter()
+        .map(Play::from)
+        .collect();
+
+    let today = chrono::Utc::now().date_naive();
+    let ids: Vec<String> = plays
+        .iter()
+        .filter(|play| play.played_at.date_naive() == today)
+        .filter_map(|play| play.track.id.clone())
+        .collect();
+    if ids.is_empty() {
+        return Ok("📭 You haven't played anything today (UTC) yet.".to_string());
+    }
+
+    let features = fetch_audio_features(spotify, &ids)
+        .await
+        .map_err(|_| "Failed to analyze today's tracks. Please try again.".to_string())?;
+    let timeline = day_timeline(&plays, &features, today);
+    let Some(dominant) = dominant_mood(&timeline) else {
+        return Err("No audio features are available for today's tracks.".to_string());
+    };
+
+    let mut response = format!(
+        "<b>🌈 Your Day in Moods</b>\n<i>{} plays today, UTC</i>\n\n{}\n\n",
+        timeline.len(),
+        render_strip(&timeline)
+    );
+    for point in &timeline {
+        response.push_str(&format!(
+            "<code>{}</code> {} {} <i>({}, energy {:.0}%)</i>\n",
+            point.played_at().format("%H:%M"),
+            point.mood.emoji(),
+            spotify_link(
+                Entity::Track,
+                point.play.track.id.as_deref(),
+                &point.play.track.name
+            ),
+            point.mood.as_str(),
+            point.energy * 100.0
+        ));
+    }
+    response.push_str(&format!(
+        "\n<b>Mostly:</b> {} {}",
+        dominant.emoji(),
+        dominant.as_str()
+    ));
+
+    Ok(response)
+}
+
+async fn get_discovery(state: &AppState) -> Result<String, String> {
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    // Favorites are the top artists over months and years; the period is
+    // whatever the last 50 plays cover
+    let (recent, long_term, medium_term) = futures::try_join!(
+        async {
+            spotify
+                .current_user_recently_played(Some(50), None)
+                .await
+                .map_err(|_| {
+                    "Failed to fetch your listening history. Please try again.".to_string()
+                })
+        },
+        top_artists(state, spotify, TopRange::Long),
+        top_artists(state, spotify, TopRange::Medium),
+    )?;
+
+    let plays: Vec<Play> = recent.items.into_iter().map(Play::from).collect();
+    if plays.is_empty() {
+        return Ok("📭 No recently played tracks found.".to_string());
+    }
+    let favorite_names: Vec<String> = long_term
+        .into_iter()
+        .chain(medium_term)
+        .map(|artist| artist.name)
+        .collect();
+    let favorites: HashSet<&str> = favorite_names.iter().map(String::as_str).collect();
+
+    let since = plays.iter().map(|play| play.played_at).min();
+    let result = discovery(
+        plays
+            .iter()
+            .filter_map(|play| play.track.artists.first().map(String::as_str)),
+        &favorites,
+    );
+    let locale = *state.locale.lock().await;
+
+    let mut response = format!(
+        "<b>🧭 Discovery</b>\n<i>Last {} plays{}</i>\n\n",
+        result.total_plays,
+        since
+            .map(|at| format!(", since {}", format_datetime(at, locale)))
+            .unwrap_or_default()
+    );
+    response.push_str(&format!(
+        "<b>Novelty:</b> {}% of plays\n<b>New artists:</b> {}\n<b>Favorites played:</b> {}\n",
+        format_number(result.novelty_ratio() as f64 * 100.0, 0, locale),
+        result.new_artists.len(),
+        result.known_artists
+    ));
+    if let Some((artist, plays)) = result.top_discovery() {
+        response.push_str(&format!(
+            "\n🌟 <b>Top discovery:</b> {} ({} plays)\n",
+            html_escape(artist),
+            plays
+        ));
+    }
+    if result.new_artists.len() > 1 {
+        let others: Vec<String> = result
+            .new_artists
+            .iter()
+            .skip(1)
+            .take(5)
+            .map(|(artist, _)| html_escape(artist))
+            .collect();
+        response.push_str(&format!("<i>Also new: {}</i>", others.join(", ")));
+    }
+
+    Ok(response)
+}
+
+/// All-time favorites missing from the last 6 months' and 4 weeks' tops and
+/// from recent plays. Spotify keeps no longer history, so the long-term top
+/// list stands in for what was played heavily long ago.
+async fn get_rediscover(state: &AppState) -> Result<(String, InlineKeyboardMarkup), String> {
+    const SHOWN: usize = 15;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::Listening]).await?;
+
+    let forgotten = forgotten_tracks(state, spotify).await?;
+    if forgotten.is_empty() {
+        return Ok((
+            "💫 Nothing forgotten: your all-time favorites are still in your rotation.".to_string(),
+            InlineKeyboardMarkup::default(),
+        ));
+    }
+
+    let mut response = format!(
+        "<b>💫 Forgotten Favorites</b>\n<i>{} all-time favorites you haven't played lately</i>\n\n",
+        forgotten.len()
+    );
+    for (idx, track) in forgotten.iter().take(SHOWN).enumerate() {
+        response.push_str(&format!(
+            "<b>{}</b>. {}\n<i>{}</i>\n",
+            idx + 1,
+            spotify_link(Entity::Track, track.id.as_deref(), &track.name),
+            html_escape(&track.artists.join(", "))
+        ));
+    }
+    if forgotten.len() > SHOWN {
+        response.push_str(&format!("…and {} more\n", forgotten.len() - SHOWN));
+    }
+
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+        "🔁 Create Rediscover playlist",
+        "rediscover:create",
+    )]]);
+    Ok((response, keyboard))
+}
+
+async fn create_rediscover_playlist(state: &AppState) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::Listening, Feature::ModifyPlaylists];
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes(spotify, FEATURES).await?;
+
+    // Listening may have changed since the list was shown, so look again
+    let forgotten = forgotten_tracks(state, spotify).await?;
+    let ids: Vec<String> = forgotten
+        .iter()
+        .filter_map(|track| track.canonical_id().map(str::to_string))
+        .collect();
+    if ids.is_empty() {
+        return Ok("💫 Nothing left to rediscover: you've been playing them again.".to_string());
+    }
+
+    let locale = *state.locale.lock().await;
+    let name = format!(
+        "Rediscover · {}",
+        format_date(chrono::Utc::now().date_naive(), locale)
+    );
+    let plan = PlaylistWriter::new(spotify, &granted, FEATURES)
+        .create(
+            &name,
+            "All-time favorites you haven't played lately. Made with Spotify Dashboard Bot",
+            &ids,
+        )
+        .await?;
+    audit(
+        state,
+        "create playlist",
+        format!("{} with {} track(s)", name, plan.ids.len()),
+    )
+    .await;
+
+    Ok(format!(
+        "🔁 <b>Rediscover Playlist Created</b>\n\n<b>Playlist:</b> {}\n<b>Tracks:</b> {}",
+        html_escape(&name),
+        plan.ids.len()
+    ))
+}
+
+async fn forgotten_tracks(
+    state: &AppState,
+    spotify: &AuthCodeSpotify,
+) -> Result<Vec<Track>, String> {
+    let (all_time, half_year, month, played) = futures::try_join!(
+        top_tracks(state, spotify, TopRange::Long),
+        top_tracks(state, spotify, TopRange::Medium),
+        top_tracks(state, spotify, TopRange::Short),
+        async {
+            spotify
+                .current_user_recently_played(Some(50), None)
+                .await
+                .map(|page| {
+                    page.items
+                        .into_iter()
+                        .map(|play| Play::from(play).track)
+                        .collect::<Vec<_>>()
+                })
+                .map_err(|_| "Failed to fetch your recent plays. Please try again.".to_string())
+        },
+    )?;
+
+    let recent: HashSet<&str> = half_year
+        .iter()
+        .chain(&month)
+        .chain(&played)
+        .filter_map(Track::canonical_id)
+        .collect();
+    let hide_explicit = *state.hide_explicit.lock().await;
+    let all_time: Vec<Track> = all_time
+        .into_iter()
+        .filter(|track| !(hide_explicit && track.explicit))
+        .collect();
+    let ids: Vec<String> = all_time
+        .iter()
+        .filter_map(|track| track.canonical_id().map(str::to_string))
+        .collect();
+    let forgotten = forgotten_favorites(&ids, &recent, MAX_REDISCOVER);
+    Ok(tracks_in_order(&all_time, forgotten.into_iter())
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+async fn get_skips(state: &AppState, arg: &str) -> Result<String, String> {
+    let enable = match arg.trim().to_lowercase().as_str() {
+        "" => None,
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => {
+            return Err(
+                "Usage: <code>/skips</code>, <code>/skips on</code> or <code>/skips off</code>"
+                    .to_string(),
+            )
+        }
+    };
+
+    if enable == Some(true) {
+        let guard = state.spotify.lock().await;
+        let spotify = guard
+            .as_ref()
+            .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+        require_scopes(spotify, &[Feature::CurrentlyPlaying]).await?;
+    }
+
+    let mut tracker = state.skips.lock().await;
+    if let Some(enable) = enable {
+        tracker.enabled = enable;
+        return Ok(if enable {
+            "⏭️ <b>Skip Tracking On</b>\n\nI'll check what you're playing every 30 seconds \
+             and note tracks you skip."
+                .to_string()
+        } else {
+            "⏹️ <b>Skip Tracking Off</b>\n\nSkips recorded so far are kept.".to_string()
+        });
+    }
+
+    let ranked = tracker.most_skipped();
+    if ranked.is_empty() {
+        return Ok(format!(
+            "📭 No skips recorded yet.{}",
+            if tracker.enabled {
+                ""
+            } else {
+                " Turn tracking on with <code>/skips on</code>."
+            }
+        ));
+    }
+
+    let mut response = format!(
+        "<b>⏭️ Most Skipped</b>\n<i>{} skips, on average {:.0}% into the track</i>\n\n",
+        tracker.total_skips(),
+        tracker.average_position() * 100.0
+    );
+    for (idx, stats) in ranked.iter().take(10).enumerate() {
+        response.push_str(&format!(
+            "<b>{}</b>. {} — {}× at {:.0}%\n",
+            idx + 1,
+            html_escape(&stats.name),
+            stats.count,
+            stats.average_position() * 100.0
+        ));
+    }
+    response.push_str(&format!(
+        "\n<i>Tracking is {}.</i>",
+        if tracker.enabled { "on" } else { "off" }
+    ));
+
+    Ok(response)
+}
+
+async fn set_archive(state: &AppState, arg: &str) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+
+    let enable = match arg.trim().to_lowercase().as_str() {
+        "" => None,
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => return Err(
+            "Usage: <code>/archive</code>, <code>/archive on</code> or <code>/archive off</code>"
+                .to_string(),
+        ),
+    };
+
+    let Some(enable) = enable else {
+        let archive = state.archive.lock().await;
+        return Ok(format!(
+            "<b>🗄️ Weekly Archive</b>\n\n<b>Status:</b> {}\n\n\
+             Every Monday I copy {} into playlists named after the week.",
+            if archive.enabled { "on" } else { "off" },
+            ARCHIVED_PLAYLISTS.join(" and ")
+        ));
+    };
+
+    if !enable {
+        state.archive.lock().await.enabled = false;
+        return Ok(
+            "🗄️ <b>Weekly Archive Off</b>\n\nPlaylists archived so far are kept.".to_string(),
+        );
+    }
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, FEATURES).await?;
+
+    let found: Vec<Playlist> = generated_playlists(spotify).await?;
+    drop(guard);
+    if found.is_empty() {
+        return Err(format!(
+            "I couldn't find {} in your library. Follow them in Spotify first.",
+            ARCHIVED_PLAYLISTS.join(" or ")
+        ));
+    }
+
+    state.archive.lock().await.enabled = true;
+    let names: Vec<String> = found.iter().map(|p| html_escape(&p.name)).collect();
+    Ok(format!(
+        "🗄️ <b>Weekly Archive On</b>\n\nI'll copy {} into a dated playlist every Monday, \
+         starting with this week's.",
+        names.join(" and ")
+    ))
+}
+
+/// Spotify's generated playlists that the member follows
+async fn generated_playlists(spotify: &AuthCodeSpotify) -> Result<Vec<Playlist>, String> {
+    let playlists = collect_stream(spotify.current_user_playlists(), Playlist::from)
+        .await
+        .map_err(|_| "Failed to fetch playlists. Please try again.".to_string())?;
+    Ok(playlists
+        .into_iter()
+        .filter(|p| p.owner_id == SPOTIFY_OWNER && ARCHIVED_PLAYLISTS.contains(&p.name.as_str()))
+        .collect())
+}
+
+/// Copy the generated playlists into dated archive playlists for `week`.
+/// Returns the archive names with their track counts; a copy that already
+/// exists is left alone.
+pub(crate) async fn archive_week(
+    spotify: &AuthCodeSpotify,
+    week: chrono::NaiveDate,
+) -> Result<Vec<(String, usize)>, String> {
+    let existing: HashSet<String> =
+        collect_stream(spotify.current_user_playlists(), Playlist::from)
+            .await
+            .map_err(|_| "Failed to fetch playlists.".to_string())?
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+
+    // Jobs run without a granted-scope check, so there is nothing to suggest
+    let granted = HashSet::new();
+    let writer = PlaylistWriter::new(spotify, &granted, &[]);
+    let mut archived = Vec::new();
+    for source in generated_playlists(spotify).await? {
+        let name = archive_name(&source.name, week);
+        if existing.contains(&name) {
+            continue;
+        }
+        let Some(source_id) = convert::playlist_id(&source.id) else {
+            continue;
+        };
+
+        let tracks: Vec<Option<Track>> = collect_stream(
+            spotify.playlist_items(source_id, None, None),
+            convert::playlist_item_track,
+        )
+        .await
+        .map_err(|_| format!("Failed to fetch {}.", source.name))?;
+        let ids: Vec<String> = tracks
+            .iter()
+            .flatten()
+            .filter_map(|track| track.id.clone())
+            .collect();
+
+        // A copy keeps the source as it is, repeats included
+        let plan = writer
+            .keep_duplicates()
+            .create(&name, "Archived with Spotify Dashboard Bot", &ids)
+            .await
+            .map_err(|err| format!("{} ({})", err, name))?;
+        archived.push((name, plan.ids.len()));
+    }
+
+    Ok(archived)
+}
+
+async fn get_streak(state: &AppState, arg: &str) -> Result<String, String> {
+    let notify =
+        match arg.trim().to_lowercase().as_str() {
+            "" => None,
+            "on" => Some(true),
+            "off" => Some(false),
+            _ => return Err(
+                "Usage: <code>/streak</code>, <code>/streak on</code> or <code>/streak off</code>"
+                    .to_string(),
+            ),
+        };
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    let plays: Vec<Play> = spotify
+        .current_user_recently_played(Some(50), None)
+        .await
+        .map_err(|_| "Failed to fetch recent tracks. Please try again.".to_string())?
+        .items
+        .into_iter()
+        .map(Play::from)
+        .collect();
+
+    let mut tracker = state.streak.lock().await;
+    tracker.record(plays.iter().map(|play| &play.played_at));
+
+    if let Some(notify) = notify {
+        tracker.notify = notify;
+        return Ok(if notify {
+            "🔔 <b>Streak Alerts On</b>\n\nI'll congratulate you at 7, 30 and 100 days in a row."
+                .to_string()
+        } else {
+            "🔕 <b>Streak Alerts Off</b>\n\nYou won't receive milestone messages anymore."
+                .to_string()
+        });
+    }
+
+    let today = chrono::Utc::now().date_naive();
+    let current = tracker.current(today);
+    let longest = longest_streak(&tracker.days);
+    let next = MILESTONES.iter().find(|m| **m > current);
+
+    let mut response = format!(
+        "<b>🔥 Listening Streak</b>\n\n\
+         <b>Current:</b> {} day(s)\n\
+         <b>Longest seen:</b> {} day(s)\n",
+        current, longest
+    );
+    if let Some(next) = next {
+        response.push_str(&format!("<b>Next milestone:</b> {} days\n", next));
+    }
+    response.push_str(&format!(
+        "\n<i>Alerts are {}. Days are counted from plays seen since the bot started tracking you.</i>",
+        if tracker.notify { "on" } else { "off" }
+    ));
+
+    Ok(response)
+}
+
+async fn get_new_releases(state: &AppState, arg: &str) -> Result<String, String> {
+    let notify = match arg.trim().to_lowercase().as_str() {
+        "" => None,
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => {
+            return Err(
+                "Usage: <code>/new_releases</code>, <code>/new_releases on</code> or \
+                 <code>/new_releases off</code>"
+                    .to_string(),
+            )
+        }
+    };
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    let releases = fetch_recent_releases(spotify)
+        .await
+        .map_err(|_| "Failed to fetch new releases. Please try again.".to_string())?;
+
+    let mut radar = state.releases.lock().await;
+    // Whatever is listed now counts as announced for the background radar
+    radar.take_unseen(&releases);
+
+    if let Some(notify) = notify {
+        radar.notify = notify;
+        return Ok(if notify {
+            "🔔 <b>Release Radar On</b>\n\nI'll message you when an artist you follow drops something new."
+                .to_string()
+        } else {
+            "🔕 <b>Release Radar Off</b>\n\nYou won't receive new release alerts anymore."
+                .to_string()
+        });
+    }
+
+    if releases.is_empty() {
+        return Ok(format!(
+            "📭 No new releases from artists you follow in the last {} days.",
+            RELEASE_WINDOW_DAYS
+        ));
+    }
+
+    let locale = *state.locale.lock().await;
+    let mut response = format!(
+        "<b>📡 New Releases</b>\n<i>Last {} days</i>\n\n",
+        RELEASE_WINDOW_DAYS
+    );
+    for (idx, release) in releases.iter().enumerate() {
+        response.push_str(&format!(
+            "<b>{}</b>. {}\n<i>{} · {} · {}</i>\n\n",
+            idx + 1,
+            spotify_link(Entity::Album, Some(&release.id), &release.name),
+            html_escape(&release.artist),
+            html_escape(&release.album_type),
+            html_escape(&display_date(&release.release_date, locale))
+        ));
+    }
+
+    Ok(response)
+}
+
+/// Albums and singles released within the radar window by followed artists, newest first
+pub(crate) async fn fetch_recent_releases(
+    spotify: &AuthCodeSpotify,
+) -> Result<Vec<Release>, rspotify::ClientError> {
+    const MAX_ARTISTS: usize = 100;
+
+    let mut artists: Vec<Artist> = Vec::new();
+    let mut after: Option<String> = None;
+    loop {
+        let page = spotify
+            .current_user_followed_artists(after.as_deref(), Some(50))
+            .await?;
+        artists.extend(page.items.into_iter().map(Artist::from));
+        after = page.cursors.and_then(|c| c.after);
+        if after.is_none() || artists.len() >= MAX_ARTISTS {
+            break;
+        }
+    }
+
+    let today = chrono::Utc::now().date_naive();
+    let mut releases = Vec::new();
+    // Only the latest few releases can be recent, so one small page per artist is enough
+    let followed = artists
+        .into_iter()
+        .take(MAX_ARTISTS)
+        .filter_map(|artist| Some((convert::artist_id(&artist.id)?, artist.name)));
+    let pages = fetch_all(followed, SPOTIFY_CONCURRENCY, |(id, name)| async move {
+        spotify
+            .artist_albums_manual(
+                id,
+                [AlbumType::Album, AlbumType::Single],
+                None,
+                Some(5),
+                None,
+            )
+            .await
+            .map(|albums| (name, albums))
+    })
+    .await?;
+
+    for (artist_name, albums) in pages {
+        releases.extend(
+            albums
+                .items
+                .into_iter()
+                .filter_map(|album| convert::release_from_album(album, &artist_name))
+                .filter(|r| is_recent_release(&r.release_date, today, RELEASE_WINDOW_DAYS)),
+        );
+    }
+
+    // Collaborations show up once per followed artist
+    releases.sort_by(|a, b| {
+        b.release_date
+            .cmp(&a.release_date)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    releases.dedup_by(|a, b| a.id == b.id);
+
+    Ok(releases)
+}
+
+async fn get_events(state: &AppState) -> Result<String, String> {
+    // Each artist costs one lookup on the events provider
+    const MAX_ARTISTS: usize = 5;
+    const MAX_EVENTS: usize = 10;
+
+    let provider = events::provider_from_env().ok_or_else(|| {
+        "Concert lookup is not configured. Set <code>BANDSINTOWN_APP_ID</code> to enable it."
+            .to_string()
+    })?;
+    let location = events::location_from_env();
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    let artists = top_artists(state, spotify, TopRange::Medium).await?;
+    drop(guard);
+
+    // A failing lookup for one artist shouldn't hide the others
+    let provider = &provider;
+    let names = artists
+        .into_iter()
+        .take(MAX_ARTISTS)
+        .map(|artist| artist.name);
+    let lookups = fetch_all(names, SPOTIFY_CONCURRENCY, |name| async move {
+        match provider.upcoming_events(&name).await {
+            Ok(found) => Ok::<_, ()>(found),
+            Err(err) => {
+                error!("Events lookup failed for {name}: {err}");
+                Ok(Vec::new())
+            }
+        }
+    })
+    .await
+    .unwrap_or_default();
+    let mut upcoming: Vec<_> = lookups.into_iter().flatten().collect();
+
+    if let Some(location) = &location {
+        upcoming.retain(|event| events::is_near(event, location));
+    }
+    upcoming.sort_by(|a, b| a.datetime.cmp(&b.datetime));
+
+    let place = location
+        .as_deref()
+        .map(|l| format!(" near {}", html_escape(l)))
+        .unwrap_or_default();
+
+    if upcoming.is_empty() {
+        return Ok(format!(
+            "📭 No upcoming concerts{} for your top artists.",
+            place
+        ));
+    }
+
+    let locale = *state.locale.lock().await;
+    let mut response = format!("<b>🎫 Upcoming Concerts{}</b>\n\n", place);
+    for (idx, event) in upcoming.iter().enumerate().take(MAX_EVENTS) {
+        let date = event.datetime.split('T').next().unwrap_or(&event.datetime);
+        response.push_str(&format!(
+            "<b>{}</b>. <a href=\"{}\">{}</a>\n<i>{} · {}, {} · {}</i>\n\n",
+            idx + 1,
+            html_escape(&event.url),
+            html_escape(&event.artist),
+            html_escape(&event.venue),
+            html_escape(&event.city),
+            html_escape(&event.country),
+            html_escape(&display_date(date, locale))
+        ));
+    }
+
+    Ok(response)
+}
+
+/// Overlap of the top tracks of every connected member in a chat, plus
+/// their combined genre mix
+async fn get_group_vibe(chat_id: i64, locale: Locale) -> Result<String, String> {
+    const MIN_MEMBERS: usize = 2;
+    const MAX_SHARED: usize = 10;
+    const MAX_GENRES: usize = 5;
+
+    let mut member_tracks: Vec<Vec<Track>> = Vec::new();
+    let mut genres: Vec<String> = Vec::new();
+    for (user_id, state) in chat_members(chat_id).await {
+        let guard = state.spotify.lock().await;
+        let Some(spotify) = guard.as_ref() else {
+            continue;
+        };
+
+        let tops = futures::try_join!(
+            top_tracks(&state, spotify, TopRange::Medium),
+            top_artists(&state, spotify, TopRange::Medium),
+        );
+        match tops {
+            Ok((tracks, artists)) => {
+                member_tracks.push(tracks);
+                genres.extend(artists.into_iter().flat_map(|artist| artist.genres));
+            }
+            Err(err) => {
+                error!("Group vibe lookup failed for user {user_id} in chat {chat_id}: {err}")
+            }
+        }
+    }
+
+    if member_tracks.len() < MIN_MEMBERS {
+        return Ok(format!(
+            "👥 <b>Group Vibe</b>\n\nAt least {} members need to connect with \
+             <code>/login</code> in this chat ({} so far).",
+            MIN_MEMBERS,
+            member_tracks.len()
+        ));
+    }
+
+    let members = member_tracks.len();
+    let ids: Vec<Vec<String>> = member_tracks
+        .iter()
+        .map(|tracks| tracks.iter().filter_map(|t| t.id.clone()).collect())
+        .collect();
+    let tracks_by_id: HashMap<&str, &Track> = member_tracks
+        .iter()
+        .flatten()
+        .filter_map(|track| Some((track.id.as_deref()?, track)))
+        .collect();
+
+    let mut response = format!(
+        "<b>👥 Group Vibe</b>\n<i>{} members · top tracks and artists of the last 6 months</i>\n\n\
+         <b>🤝 Shared Tracks</b>\n",
+        members
+    );
+    let shared = shared_items(&ids, MIN_MEMBERS);
+    if shared.is_empty() {
+        response.push_str("No track is in more than one member's top 50 yet.\n");
+    }
+    for (idx, (id, count)) in shared.iter().take(MAX_SHARED).enumerate() {
+        let Some(track) = tracks_by_id.get(id.as_str()) else {
+            continue;
+        };
+        response.push_str(&format!(
+            "<b>{}</b>. {} — <i>{}</i> ({}/{})\n",
+            idx + 1,
+            spotify_link(Entity::Track, track.id.as_deref(), &track.name),
+            html_escape(&track.artists.join(", ")),
+            count,
+            members
+        ));
+    }
+
+    let breakdown = share_breakdown(genres.iter().map(String::as_str));
+    if !breakdown.is_empty() {
+        response.push_str("\n<b>🎨 Combined Genres</b>\n");
+        for (genre, share) in breakdown.iter().take(MAX_GENRES) {
+            response.push_str(&format!(
+                "• {} — {}%\n",
+                html_escape(genre),
+                format_number(*share as f64 * 100.0, 0, locale)
+            ));
+        }
+    }
+
+    Ok(response)
+}
+
+/// The member a command refers to: the author of the replied-to message
+/// (works without a username), or an `@username` argument
+async fn resolve_member(msg: &Message, target: &str) -> Option<(u64, String)> {
+    match msg.reply_to_message().and_then(|reply| reply.from.as_ref()) {
+        Some(user) => Some((user.id.0, user.first_name.clone())),
+        None => find_member(msg.chat.id.0, target).await,
+    }
+}
+
+/// A chat member who used the bot before, looked up by `@username`
+async fn find_member(chat_id: i64, username: &str) -> Option<(u64, String)> {
+    let username = username.trim().trim_start_matches('@');
+    if username.is_empty() {
+        return None;
+    }
+
+    for (user_id, state) in chat_members(chat_id).await {
+        let known = state.username.lock().await.clone();
+        if known.is_some_and(|known| known.eq_ignore_ascii_case(username)) {
+            return Some((user_id, format!("@{}", username)));
+        }
+    }
+    None
+}
+
+/// Mix two members' favorites 50/50 into a new playlist in the initiator's account
+async fn create_blend(
+    state: &AppState,
+    partner: &AppState,
+    initiator_name: &str,
+    partner_name: &str,
+) -> Result<String, String> {
+    const BLEND_SIZE: usize = 30;
+    const FEATURES: &[Feature] = &[
+        Feature::Listening,
+        Feature::ReadLibrary,
+        Feature::ModifyPlaylists,
+    ];
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes(spotify, FEATURES).await?;
+
+    let partner_guard = partner.spotify.lock().await;
+    let partner_spotify = partner_guard.as_ref().ok_or_else(|| {
+        format!(
+            "{} needs to connect Spotify with <code>/login</code> in this chat first.",
+            html_escape(partner_name)
+        )
+    })?;
+    // The partner may not have granted library access; their tops still count
+    let partner_saved = scopes::missing_scopes(
+        &granted_scopes(partner_spotify).await,
+        &[Feature::ReadLibrary],
+    )
+    .is_empty();
+
+    // The playlist is the initiator's, so their explicit setting applies
+    let hide_explicit = *state.hide_explicit.lock().await;
+    let (mine, theirs) = futures::try_join!(
+        fetch_taste(state, spotify, true, hide_explicit),
+        fetch_taste(partner, partner_spotify, partner_saved, hide_explicit),
+    )?;
+    drop(partner_guard);
+
+    let ids = blend(&mine, &theirs, BLEND_SIZE);
+    if ids.is_empty() {
+        return Ok("📭 Neither of you has top tracks to blend yet.".to_string());
+    }
+
+    let name = format!("Blend: {} + {}", initiator_name, partner_name);
+    let plan = PlaylistWriter::new(spotify, &granted, FEATURES)
+        .create(&name, "A 50/50 mix made with Spotify Dashboard Bot", &ids)
+        .await?;
+    let count = plan.ids.len();
+
+    audit(
+        state,
+        "create playlist",
+        format!("{} with {} track(s)", name, count),
+    )
+    .await;
+
+    Ok(format!(
+        "🫂 <b>Blend Created</b>\n\n<b>Playlist:</b> {}\n<b>Tracks:</b> {}\n\n\
+         <i>Shared favorites come first, then picks alternate between you two.</i>{}",
+        html_escape(&name),
+        count,
+        skipped_note(plan.skipped)
+    ))
+}
+
+async fn compare_tracks(state: &AppState, input: &str) -> Result<String, String> {
+    let (query_a, query_b) = input
+        .split_once('|')
+        .map(|(a, b)| (a.trim(), b.trim()))
+        .filter(|(a, b)| !a.is_empty() && !b.is_empty())
+        .ok_or_else(|| "Usage: <code>/compare_tracks song A | song B</code>".to_string())?;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    let market = market(state).await;
+    let (a, b) = futures::try_join!(
+        find_track(spotify, query_a, market),
+        find_track(spotify, query_b, market)
+    )?;
+    let (Some(id_a), Some(id_b)) = (a.id.clone(), b.id.clone()) else {
+        return Err("One of the tracks has no ID to look up.".to_string());
+    };
+
+    let ids = [id_a, id_b];
+    let features = fetch_audio_features(spotify, &ids)
+        .await
+        .map_err(|_| "Failed to fetch audio features. Please try again.".to_string())?;
+    let (Some(features_a), Some(features_b)) = (features.get(&ids[0]), features.get(&ids[1]))
+    else {
+        return Err("Spotify has no audio features for one of these tracks.".to_string());
+    };
+    let keys = cached_keys(&ids).await;
+    let key_a = keys.get(&ids[0]).copied().map(key_name);
+    let key_b = keys.get(&ids[1]).copied().map(key_name);
+
+    let mut table = format!("{:<17}{:>9}{:>9}\n", "", "A", "B");
+    table.push_str(&format!(
+        "{:<17}{:>9.0}{:>9.0}\n",
+        "Tempo (BPM)", features_a.tempo, features_b.tempo
+    ));
+    for (label, _, value_a, value_b) in feature_rows(features_a, features_b) {
+        table.push_str(&format!("{:<17}{:>9.2}{:>9.2}\n", label, value_a, value_b));
+    }
+    table.push_str(&format!(
+        "{:<17}{:>9}{:>9}",
+        "Key",
+        key_a.as_deref().map_or("?", short_key),
+        key_b.as_deref().map_or("?", short_key)
+    ));
+
+    let mut response = format!(
+        "<b>⚖️ Track Comparison</b>\n\n\
+         <b>A:</b> {} — <i>{}</i>\n\
+         <b>B:</b> {} — <i>{}</i>\n\n\
+         <pre>{}</pre>\n\n",
+        spotify_link(Entity::Track, a.id.as_deref(), &a.name),
+        html_escape(&a.artists.join(", ")),
+        spotify_link(Entity::Track, b.id.as_deref(), &b.name),
+        html_escape(&b.artists.join(", ")),
+        html_escape(&table)
+    );
+    let lines = verdict("A", "B", features_a, features_b);
+    if lines.is_empty() {
+        response.push_str("🤝 These two sound very much alike.");
+    }
+    for line in lines {
+        response.push_str(&format!("• {}\n", line));
+    }
+
+    Ok(response)
+}
+
+/// Details, sound and detected genre and mood of many tracks, from links or
+/// IDs. Tracks are fetched 50 per call and audio features come from the
+/// shared cache, so a long list costs a handful of requests.
+async fn get_tracks(state: &AppState, input: &str) -> Result<String, String> {
+    const MAX_TRACKS: usize = 500;
+    const USAGE: &str = "Usage: <code>/tracks link1 link2 ...</code> with Spotify track links, \
+                         URIs or IDs";
+
+    let mut ids: Vec<String> = Vec::new();
+    let mut invalid = 0;
+    for word in input.split_whitespace() {
+        match parse_id(Entity::Track, word) {
+            Some(id) if !ids.contains(&id) => ids.push(id),
+            Some(_) => {}
+            None => invalid += 1,
+        }
+    }
+    if ids.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    if ids.len() > MAX_TRACKS {
+        return Err(format!(
+            "At most {} tracks at a time ({} given).",
+            MAX_TRACKS,
+            ids.len()
+        ));
+    }
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    let market = market(state).await;
+    let batches: Vec<Vec<_>> = ids
+        .chunks(50)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .filter_map(|id| convert::track_id(id))
+                .collect()
+        })
+        .collect();
+    // Spotify answers an unknown ID with null, which fails the whole batch, so
+    // a failed batch is retried one track at a time and the unknown ones dropped
+    let pages = fetch_all(batches, SPOTIFY_CONCURRENCY, |batch| async move {
+        if let Ok(tracks) = spotify.tracks(batch.clone(), Some(market)).await {
+            return Ok(tracks);
+        }
+        let singles = fetch_all(batch, SPOTIFY_CONCURRENCY, |id| async move {
+            Ok::<_, ClientError>(spotify.track(id, Some(market)).await.ok())
+        })
+        .await?;
+        Ok::<_, ClientError>(singles.into_iter().flatten().collect())
+    })
+    .await
+    .map_err(|_| "Failed to fetch the tracks. Please try again.".to_string())?;
+    let tracks: Vec<Track> = pages.into_iter().flatten().map(Track::from).collect();
+
+    let track_ids: Vec<String> = tracks.iter().filter_map(|track| track.id.clone()).collect();
+    let features = fetch_audio_features(spotify, &track_ids)
+        .await
+        .map_err(|_| "Failed to fetch audio features. Please try again.".to_string())?;
+    let keys = cached_keys(&track_ids).await;
+    let artist_genres = top_artist_genres(state, spotify, TopRange::Medium).await;
+    drop(guard);
+
+    let mut response = format!("<b>🎼 Tracks</b> <i>({})</i>\n\n", tracks.len());
+    for (idx, track) in tracks.iter().enumerate() {
+        response.push_str(&format!(
+            "<b>{}</b>. {} — <i>{}</i>\n└ {}{} · {}{}\n",
+            idx + 1,
+            spotify_link(Entity::Track, track.id.as_deref(), &track.name),
+            html_escape(&track.artists.join(", ")),
+            html_escape(&track.album),
+            track
+                .release_date
+                .as_deref()
+                .and_then(release_year)
+                .map(|year| format!(" ({})", year))
+                .unwrap_or_default(),
+            format_track_length(track.duration_ms),
+            if track.explicit { " · 🅴" } else { "" }
+        ));
+        let Some(f) = track.id.as_deref().and_then(|id| features.get(id)) else {
+            response.push_str("└ <i>no audio features</i>\n");
+            continue;
+        };
+        let mood = backend().classify_mood(*f).mood;
+        response.push_str(&format!(
+            "└ {:.0} BPM · {} · energy {:.2} · {} · {} {}\n",
+            f.tempo,
+            track
+                .id
+                .as_deref()
+                .and_then(|id| keys.get(id))
+                .map_or("?".to_string(), Camelot::code),
+            f.energy,
+            backend()
+                .classify_genre(*f, &artist_tags(track, &artist_genres), track.popularity)
+                .genre
+                .as_str(),
+            mood.emoji(),
+            mood.as_str().to_lowercase()
+        ));
+    }
+
+    let missing = ids.len() - tracks.len();
+    if missing > 0 || invalid > 0 {
+        response.push_str(&format!(
+            "\n<i>{} not found, {} not a track link or ID</i>",
+            missing, invalid
+        ));
+    }
+
+    Ok(response)
+}
+
+/// "C♯ minor" → "C♯ min", to fit the table column
+fn short_key(key: &str) -> &str {
+    key.strip_suffix("or").unwrap_or(key)
+}
+
+/// The catalog track a typed name most likely refers to
+async fn find_track(
+    spotify: &AuthCodeSpotify,
+    query: &str,
+    market: Market,
+) -> Result<Track, String> {
+    let result = spotify
+        .search(query, SearchType::Track, Some(market), None, Some(5), None)
+        .await
+        .map_err(|_| "Failed to search tracks. Please try again.".to_string())?;
+    let tracks: Vec<Track> = match result {
+        SearchResult::Tracks(page) => page.items.into_iter().map(Track::from).collect(),
+        _ => Vec::new(),
+    };
+
+    let close = tracks
+        .iter()
+        .position(|track| is_close_match(query, &track.name, &track.artists))
+        .unwrap_or(0);
+    tracks
+        .into_iter()
+        .nth(close)
+        .ok_or_else(|| format!("No track found for \"{}\".", html_escape(query)))
+}
+
+async fn compare_tastes(
+    state: &AppState,
+    partner: &AppState,
+    partner_name: &str,
+) -> Result<String, String> {
+    const MAX_SHARED: usize = 5;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let partner_guard = partner.spotify.lock().await;
+    let partner_spotify = partner_guard.as_ref().ok_or_else(|| {
+        format!(
+            "{} needs to connect Spotify with <code>/login</code> in this chat first.",
+            html_escape(partner_name)
+        )
+    })?;
+
+    let (mine, theirs) = futures::try_join!(
+        fetch_taste_profile(state, spotify),
+        fetch_taste_profile(partner, partner_spotify),
+    )?;
+    drop(partner_guard);
+
+    let locale = *state.locale.lock().await;
+    let percent = |value: f32| format_number(value as f64 * 100.0, 0, locale);
+    let similarity = compare(&mine, &theirs);
+
+    let mut response = format!(
+        "<b>💞 Taste Match with {}: {}%</b>\n\n\
+         <b>Artists:</b> {}%\n\
+         <b>Genres:</b> {}%\n",
+        html_escape(partner_name),
+        percent(similarity.overall),
+        percent(similarity.artists),
+        percent(similarity.genres)
+    );
+    match similarity.sound {
+        Some(sound) => response.push_str(&format!("<b>Sound:</b> {}%\n", percent(sound))),
+        None => response.push_str("<i>Sound not compared: audio features unavailable</i>\n"),
+    }
+
+    let shared = shared_artists(&mine, &theirs);
+    if !shared.is_empty() {
+        response.push_str("\n<b>🤝 Shared Favorites</b>\n");
+        for artist in shared.iter().take(MAX_SHARED) {
+            response.push_str(&format!("• {}\n", html_escape(artist)));
+        }
+    }
+
+    Ok(response)
+}
+
+async fn fetch_taste_profile(
+    state: &AppState,
+    spotify: &AuthCodeSpotify,
+) -> Result<TasteProfile, String> {
+    let (artists, tracks) = futures::try_join!(
+        top_artists(state, spotify, TopRange::Medium),
+        top_tracks(state, spotify, TopRange::Medium),
+    )?;
+    let track_ids: Vec<String> = tracks.into_iter().filter_map(|track| track.id).collect();
+
+    // Audio features are optional: some apps no longer get access to them
+    let features: Vec<AudioFeatures> = fetch_audio_features(spotify, &track_ids)
+        .await
+        .map(|features| features.into_values().collect())
+        .unwrap_or_default();
+
+    let mut genres = HashMap::new();
+    for genre in artists.iter().flat_map(|artist| &artist.genres) {
+        *genres.entry(genre.clone()).or_insert(0.0) += 1.0;
+    }
+
+    Ok(TasteProfile {
+        artists: artists.into_iter().map(|artist| artist.name).collect(),
+        genres,
+        centroid: feature_centroid(&features),
+    })
+}
+
+/// Tracks are keyed by canonical ID so relinked copies count as the same
+async fn fetch_taste(
+    state: &AppState,
+    spotify: &AuthCodeSpotify,
+    include_saved: bool,
+    hide_explicit: bool,
+) -> Result<Taste, String> {
+    let keep = |track: Track| {
+        if hide_explicit && track.explicit {
+            None
+        } else {
+            track.canonical_id().map(str::to_string)
+        }
+    };
+    let top = top_tracks(state, spotify, TopRange::Medium).await?;
+    let saved = if include_saved {
+        spotify
+            .current_user_saved_tracks_manual(None, Some(50), None)
+            .await
+            .map_err(|_| "Failed to fetch your Liked Songs. Please try again.".to_string())?
+            .items
+            .into_iter()
+            .filter_map(|saved| keep(Track::from(saved.track)))
+            .collect()
+    } else {
+        HashSet::new()
+    };
+
+    Ok(Taste {
+        top: top.into_iter().filter_map(keep).collect(),
+        saved,
+    })
+}
+
+/// Only the configured admin chat may use admin commands; without a valid
+/// `ADMIN_CHAT_ID` nobody can
+fn require_admin(chat_id: i64) -> Result<(), String> {
+    let admin = std::env::var("ADMIN_CHAT_ID")
+        .ok()
+        .and_then(|admin| admin.trim().parse::<i64>().ok());
+    if admin != Some(chat_id) {
+        return Err("This command is only available to the bot admin.".to_string());
+    }
+    Ok(())
+}
+
+async fn get_jobs(state: &AppState, chat_id: i64) -> Result<String, String> {
+    require_admin(chat_id)?;
+
+    let jobs = jobs::snapshot().await;
+    if jobs.is_empty() {
+        return Ok("No background jobs are running.".to_string());
+    }
+
+    let locale = *state.locale.lock().await;
+    let mut response = "<b>⚙️ Background Jobs</b>\n\n".to_string();
+    for (name, status) in jobs {
+        let icon = match (&status.last_error, status.running) {
+            (_, true) => "🔄",
+            (Some(_), false) => "❌",
+            (None, false) => "✅",
+        };
+        let last_run = status
+            .last_run
+            .map(|at| format_datetime(at, locale))
+            .unwrap_or_else(|| "never".to_string());
+        response.push_str(&format!(
+            "{} <b>{}</b> · every {}\nLast run: {}\nNext run: {}\n",
+            icon,
+            name,
+            format_duration(status.interval, locale),
+            last_run,
+            format_datetime(status.next_run, locale)
+        ));
+        if let Some(err) = &status.last_error {
+            response.push_str(&format!("<i>{}</i>\n", html_escape(err)));
+        }
+        response.push('\n');
+    }
+    response.push_str("Run one now with <code>/run_job name</code>.");
+
+    Ok(response)
+}
+
+async fn run_job(chat_id: i64, name: &str) -> Result<String, String> {
+    require_admin(chat_id)?;
+
+    let name = name.trim();
+    if jobs::trigger(name).await {
+        Ok(format!("▶️ Job <b>{}</b> will run now.", html_escape(name)))
+    } else {
+        Err(format!(
+            "Unknown job \"{}\". See <code>/jobs</code> for the list.",
+            html_escape(name)
+        ))
+    }
+}
+
+async fn get_usage(state: &AppState, chat_id: i64) -> Result<String, String> {
+    if !usage::is_enabled() {
+        return Err(
+            "Usage statistics are disabled. Set <code>USAGE_STATS=true</code> to enable them."
+                .to_string(),
+        );
+    }
+
+    require_admin(chat_id)?;
+
+    let stats = usage::snapshot().await;
+    let ranked = stats.ranked();
+    let total: u64 = ranked.iter().map(|(_, count)| count).sum();
+    let locale = *state.locale.lock().await;
+    let uptime = (chrono::Utc::now() - stats.since)
+        .to_std()
+        .unwrap_or_default();
+
+    let mut response = format!(
+        "<b>📊 Command Usage</b>\n<i>Since {} ({}) · {} commands · stored locally only</i>\n\n",
+        format_datetime(stats.since, locale),
+        format_duration(uptime, locale),
+        format_count(total, locale)
+    );
+    if ranked.is_empty() {
+        response.push_str("No commands recorded yet.");
+    }
+    for (name, count) in ranked {
+        response.push_str(&format!(
+            "<code>/{}</code> — {}\n",
+            html_escape(name),
+            format_count(count, locale)
+        ));
+    }
+
+    Ok(response)
+}
+
+/// Each rejected list argument on its own line, then how to use the command
+fn list_args_error(problems: &str, usage: &str) -> String {
+    format!("{}\n\nUsage: {}", html_escape(problems), usage)
+}
+
+async fn set_top_defaults(state: &AppState, args: &str) -> Result<String, String> {
+    let args = args.trim();
+    let mut defaults = state.list_defaults.lock().await;
+
+    if args.eq_ignore_ascii_case("reset") {
+        *defaults = ListArgs::default();
+    } else if !args.is_empty() {
+        const USAGE: &str = "<code>/top_defaults 25 long</code>, any of a count \
+             (up to 50) and <code>short</code>, <code>medium</code> or <code>long</code>, \
+             or <code>/top_defaults reset</code>";
+        let parsed = ListArgs::parse_without_genre(args)
+            .map_err(|problems| list_args_error(&problems, USAGE))?;
+        if parsed.count.is_none() && parsed.range.is_none() {
+            return Err(format!("Usage: {}", USAGE));
+        }
+        defaults.count = parsed.count.or(defaults.count);
+        defaults.range = parsed.range.or(defaults.range);
+    }
+
+    let (count, range) = ListArgs::default().resolve(*defaults);
+    Ok(format!(
+        "<b>📏 Top List Defaults</b>\n\n\
+         <b>Count:</b> {}\n\
+         <b>Range:</b> {}\n\n\
+         <i>Arguments to <code>/top_tracks</code> and <code>/top_artists</code> still win.</i>",
+        count,
+        range_label(range)
+    ))
+}
+
+async fn set_locale(state: &AppState, arg: &str) -> Result<String, String> {
+    let arg = arg.trim();
+    let mut locale = state.locale.lock().await;
+
+    if !arg.is_empty() {
+        *locale = Locale::parse(arg).ok_or_else(|| {
+            "Usage: <code>/locale</code>, <code>/locale en</code> or <code>/locale vi</code>"
+                .to_string()
+        })?;
+    }
+
+    let available: Vec<&str> = Locale::ALL.iter().map(|l| l.code()).collect();
+    Ok(format!(
+        "<b>🌐 Locale: {}</b>\n\n\
+         <b>Numbers:</b> {}\n\
+         <b>Dates:</b> {}\n\n\
+         <i>Available: {}</i>",
+        locale.code(),
+        format_number(1234.5, 1, *locale),
+        format_date(chrono::Utc::now().date_naive(), *locale),
+        available.join(", ")
+    ))
+}
+
+async fn set_explicit_filter(state: &AppState, arg: &str) -> Result<String, String> {
+    let mut hide_explicit = state.hide_explicit.lock().await;
+    match arg.trim().to_lowercase().as_str() {
+        "" => {}
+        "on" => *hide_explicit = true,
+        "off" => *hide_explicit = false,
+        _ => {
+            return Err(
+                "Usage: <code>/explicit_filter</code>, <code>/explicit_filter on</code> \
+                 or <code>/explicit_filter off</code>"
+                    .to_string(),
+            )
+        }
+    }
+
+    Ok(if *hide_explicit {
+        "🚫 <b>Explicit Filter On</b>\n\nExplicit tracks are left out of search results and blends."
+            .to_string()
+    } else {
+        "🅴 <b>Explicit Filter Off</b>\n\nExplicit tracks are shown everywhere.".to_string()
+    })
+}
+
+async fn set_mqtt(state: &AppState, arg: &str) -> Result<String, String> {
+    #[cfg(feature = "mqtt")]
+    let available = crate::utils::mqtt::enabled();
+    #[cfg(not(feature = "mqtt"))]
+    let available = false;
+    if !available {
+        return Err("MQTT isn't set up on this bot.".to_string());
+    }
+
+    let mut publishing = state.mqtt.lock().await;
+    match arg.trim().to_lowercase().as_str() {
+        "" => {}
+        "on" => *publishing = true,
+        "off" => *publishing = false,
+        _ => {
+            return Err(
+                "Usage: <code>/mqtt</code>, <code>/mqtt on</code> or <code>/mqtt off</code>"
+                    .to_string(),
+            )
+        }
+    }
+
+    Ok(if *publishing {
+        "📡 <b>MQTT On</b>\n\nWhat you're playing is published to the broker.".to_string()
+    } else {
+        "📡 <b>MQTT Off</b>\n\nNothing is published. Turn it on with <code>/mqtt on</code>."
+            .to_string()
+    })
+}
+
+async fn set_market(state: &AppState, arg: &str) -> Result<String, String> {
+    let arg = arg.trim();
+    let mut market = state.market.lock().await;
+
+    match arg.to_lowercase().as_str() {
+        "" => {}
+        "auto" => *market = None,
+        code => {
+            if convert::country(code).is_none() {
+                return Err(
+                    "Usage: <code>/market</code>, <code>/market VN</code> (any two-letter \
+                     country code) or <code>/market auto</code>"
+                        .to_string(),
+                );
+            }
+            *market = Some(code.to_uppercase());
+        }
+    }
+
+    Ok(match market.as_deref() {
+        Some(code) => format!(
+            "<b>🗺️ Market: {}</b>\n\n\
+             Search, podcasts and playlist availability are checked for {}. \
+             Use <code>/market auto</code> to go back to your account's country.",
+            code, code
+        ),
+        None => "<b>🗺️ Market: your account's country</b>\n\n\
+                 Set another one with e.g. <code>/market VN</code> to see what's available there."
+            .to_string(),
+    })
+}
+
+async fn set_webhook(state: &AppState, input: &str) -> Result<String, String> {
+    const USAGE: &str = "Usage: <code>/webhook https://example.com/hook secret</code>, \
+                         <code>/webhook test</code>, <code>/webhook off</code> \
+                         or <code>/webhook</code> for recent deliveries";
+    const MIN_SECRET_LEN: usize = 16;
+
+    let parts: Vec<&str> = input.split_whitespace().collect();
+    match parts.as_slice() {
+        [] => {}
+        ["off"] => {
+            return Ok(match state.webhook.lock().await.take() {
+                Some(_) => "🔌 <b>Webhook Removed</b>\n\nNo more events will be sent.".to_string(),
+                None => "No webhook is set.".to_string(),
+            });
+        }
+        ["test"] => {
+            if state.webhook.lock().await.is_none() {
+                return Err("No webhook is set. Register one first.".to_string());
+            }
+            webhooks::send(
+                state.webhook.clone(),
+                "ping",
+                serde_json::json!({ "message": "Webhook test from Spotify Dashboard Bot" }),
+            );
+            return Ok(
+                "📤 Test event sent. Check <code>/webhook</code> in a few seconds for the result."
+                    .to_string(),
+            );
+        }
+        [url, secret] => {
+            webhooks::check_url(url)
+                .await
+                .map_err(|err| format!("{}\n\n{}", html_escape(&err), USAGE))?;
+            if secret.len() < MIN_SECRET_LEN {
+                return Err(format!(
+                    "The secret should be at least {} characters long.",
+                    MIN_SECRET_LEN
+                ));
+            }
+            *state.webhook.lock().await = Some(Webhook::new(url.to_string(), secret.to_string()));
+            return Ok(format!(
+                "🔗 <b>Webhook Set</b>\n\n\
+                 Plays and new releases will be POSTed as JSON to {}.\n\
+                 Each request carries <code>{}: sha256=&lt;hex&gt;</code>, the HMAC-SHA256 \
+                 of the body with your secret.\n\n\
+                 <i>Playback is checked every 30 seconds, so very short plays may be missed. \
+                 You may want to delete the message containing your secret.</i>",
+                html_escape(url),
+                SIGNATURE_HEADER
+            ));
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+
+    let webhook = state.webhook.lock().await;
+    let Some(webhook) = webhook.as_ref() else {
+        return Ok(format!("No webhook is set.\n\n{}", USAGE));
+    };
+    let locale = *state.locale.lock().await;
+
+    let mut response = format!(
+        "<b>🔗 Webhook</b>\n\n<b>URL:</b> {}\n\n",
+        html_escape(&webhook.url)
+    );
+    if webhook.deliveries.is_empty() {
+        response.push_str("No deliveries yet.");
+    }
+    for delivery in &webhook.deliveries {
+        let outcome = match &delivery.outcome {
+            Ok(status) if (200..300).contains(status) => format!("✅ {}", status),
+            Ok(status) => format!("❌ {}", status),
+            Err(err) => format!("❌ {}", html_escape(err)),
+        };
+        response.push_str(&format!(
+            "{} · <code>{}</code> · {} ({} attempt(s))\n",
+            format_datetime(delivery.at, locale),
+            delivery.event,
+            outcome,
+            delivery.attempts
+        ));
+    }
+
+    Ok(response)
+}
+
+/// Changes the bot made to the member's Spotify account, newest first
+async fn get_history_actions(state: &AppState) -> Result<String, String> {
+    const SHOWN: usize = 20;
+
+    let locale = *state.locale.lock().await;
+    let log = state.audit.lock().await;
+    if log.is_empty() {
+        return Ok("📜 The bot hasn't changed anything on your Spotify account yet.".to_string());
+    }
+
+    let mut response = format!(
+        "📜 <b>Recent Changes</b>\n<i>Latest {} of {}</i>\n\n",
+        log.len().min(SHOWN),
+        log.len()
+    );
+    for entry in log.recent().take(SHOWN) {
+        response.push_str(&format!(
+            "<b>{}</b> · {}\n<i>{}, {}</i>\n\n",
+            html_escape(entry.action),
+            html_escape(&entry.detail),
+            entry.actor.as_str(),
+            format_datetime(entry.at, locale)
+        ));
+    }
+    Ok(response.trim_end().to_string())
+}
+
+/// The member's `/market` override, or the account's own market
+async fn market(state: &AppState) -> Market {
+    convert::market(state.market.lock().await.as_deref())
+}
+
+// Full `YYYY-MM-DD` dates follow the locale; coarser ones are shown as given
+fn display_date(date: &str, locale: Locale) -> String {
+    match chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(date) => format_date(date, locale),
+        Err(_) => date.to_string(),
+    }
+}
+
+/// Save the currently playing track to Liked Songs, or remove it
+async fn set_liked(state: &AppState, liked: bool) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[
+        Feature::CurrentlyPlaying,
+        Feature::ReadLibrary,
+        Feature::ModifyLibrary,
+    ];
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes(spotify, FEATURES).await?;
+
+    let track = spotify
+        .current_playing(None, None::<&[AdditionalType]>)
+        .await
+        .map_err(|_| "Failed to fetch what's playing. Please try again.".to_string())?
+        .and_then(|playing| playing.item)
+        .and_then(convert::playable_item_track)
+        .ok_or_else(|| "Nothing is playing right now (podcasts can't be liked).".to_string())?;
+    let track_id = track
+        .id
+        .as_deref()
+        .and_then(convert::track_id)
+        .ok_or_else(|| "Local files can't be saved to Liked Songs.".to_string())?;
+
+    let saved = spotify
+        .current_user_saved_tracks_contains([track_id.clone()])
+        .await
+        .map_err(|_| "Failed to check your library. Please try again.".to_string())?
+        .first()
+        .copied()
+        .unwrap_or(false);
+    let name = html_escape(&track.name);
+    let artists = html_escape(&track.artists.join(", "));
+
+    if saved == liked {
+        return Ok(if liked {
+            format!(
+                "💚 <b>{}</b> by <i>{}</i> is already in your Liked Songs.",
+                name, artists
+            )
+        } else {
+            format!(
+                "ℹ️ <b>{}</b> by <i>{}</i> isn't in your Liked Songs.",
+                name, artists
+            )
+        });
+    }
+
+    let result = if liked {
+        spotify.current_user_saved_tracks_add([track_id]).await
+    } else {
+        spotify.current_user_saved_tracks_delete([track_id]).await
+    };
+    result.map_err(|err| {
+        scope_failure(
+            &err,
+            &granted,
+            FEATURES,
+            "Failed to update your library. Please try again.",
+        )
+    })?;
+    audit(
+        state,
+        if liked { "like" } else { "unlike" },
+        format!("{} by {}", track.name, track.artists.join(", ")),
+    )
+    .await;
+
+    Ok(if liked {
+        format!("💚 <b>Liked</b>\n\n{}\n<i>{}</i>", name, artists)
+    } else {
+        format!(
+            "💔 <b>Removed from Liked Songs</b>\n\n{}\n<i>{}</i>",
+            name, artists
+        )
+    })
+}
+
+/// Followed shows with their newest episode and where the user left off
+async fn get_podcasts(state: &AppState) -> Result<String, String> {
+    const MAX_SHOWS: u32 = 10;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::ReadLibrary, Feature::PlaybackPosition]).await?;
+
+    let shows: Vec<Show> = spotify
+        .get_saved_show_manual(Some(MAX_SHOWS), None)
+        .await
+        .map_err(|_| "Failed to fetch your shows. Please try again.".to_string())?
+        .items
+        .into_iter()
+        .map(Show::from)
+        .collect();
+    if shows.is_empty() {
+        return Ok("📭 You don't follow any podcasts or audiobooks yet.".to_string());
+    }
+
+    let show_ids: Vec<_> = shows
+        .iter()
+        .filter_map(|show| convert::show_id(&show.id))
+        .collect();
+    let market = market(state).await;
+    let latest = fetch_all(show_ids, SPOTIFY_CONCURRENCY, |id| async move {
+        spotify
+            .get_shows_episodes_manual(id, Some(market), Some(1), None)
+            .await
+            .map(|page| page.items.into_iter().next().map(Episode::from))
+    })
+    .await
+    .map_err(|_| "Failed to fetch episodes. Please try again.".to_string())?;
+
+    let locale = *state.locale.lock().await;
+    let mut response = "<b>🎙️ Your Podcasts</b>\n\n".to_string();
+    for (idx, (show, episode)) in shows.iter().zip(latest).enumerate() {
+        response.push_str(&format!(
+            "<b>{}</b>. {}\n<i>{}</i>\n",
+            idx + 1,
+            spotify_link(Entity::Show, Some(&show.id), &show.name),
+            html_escape(&show.publisher)
+        ));
+        if let Some(episode) = episode {
+            response.push_str(&format!(
+                "└ {} · {} · {}\n",
+                spotify_link(Entity::Episode, Some(&episode.id), &episode.name),
+                html_escape(&display_date(&episode.release_date, locale)),
+                episode_status(&episode)
+            ));
+        }
+        response.push('\n');
+    }
+
+    Ok(response)
+}
+
+// One page is plenty to read in a chat; the total is still shown
+const LIBRARY_PAGE: u32 = 50;
+
+/// Albums in the member's library, most recently saved first
+async fn get_saved_albums(state: &AppState) -> Result<String, String> {
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::ReadLibrary]).await?;
+
+    let page = spotify
+        .current_user_saved_albums_manual(Some(market(state).await), Some(LIBRARY_PAGE), None)
+        .await
+        .map_err(|_| "Failed to fetch your albums. Please try again.".to_string())?;
+    let total = page.total;
+    let albums: Vec<SavedAlbum> = page.items.into_iter().map(SavedAlbum::from).collect();
+    if albums.is_empty() {
+        return Ok("📭 You haven't saved any albums yet.".to_string());
+    }
+
+    let locale = *state.locale.lock().await;
+    let mut response = format!(
+        "<b>💿 Your Albums</b>\n<i>{} saved, newest first</i>\n\n",
+        format_count(total as u64, locale)
+    );
+    for (idx, album) in albums.iter().enumerate() {
+        response.push_str(&format!(
+            "<b>{}</b>. {}{}\n<i>{}</i> · {} tracks · saved {}\n",
+            idx + 1,
+            spotify_link(Entity::Album, Some(&album.id), &album.name),
+            release_year(&album.release_date)
+                .map(|year| format!(" ({})", year))
+                .unwrap_or_default(),
+            html_escape(&album.artists.join(", ")),
+            album.total_tracks,
+            format_date(album.added_at.date_naive(), locale)
+        ));
+    }
+    if total as usize > albums.len() {
+        response.push_str(&format!(
+            "\n…and {} more in the Spotify app",
+            total as usize - albums.len()
+        ));
+    }
+
+    Ok(response)
+}
+
+/// Tracks and albums saved lately, merged newest first, so things saved and
+/// forgotten turn up again
+async fn get_new_in_library(state: &AppState) -> Result<String, String> {
+    const SHOWN: usize = 20;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::ReadLibrary]).await?;
+
+    // Each list is newest first, so its first page covers the merged top
+    let market = market(state).await;
+    let (tracks, albums) = futures::try_join!(
+        async {
+            spotify
+                .current_user_saved_tracks_manual(Some(market), Some(SHOWN as u32), None)
+                .await
+                .map_err(|_| "Failed to fetch your saved tracks. Please try again.".to_string())
+        },
+        async {
+            spotify
+                .current_user_saved_albums_manual(Some(market), Some(SHOWN as u32), None)
+                .await
+                .map_err(|_| "Failed to fetch your albums. Please try again.".to_string())
+        },
+    )?;
+
+    let mut additions: Vec<(chrono::DateTime<chrono::Utc>, String)> = tracks
+        .items
+        .into_iter()
+        .map(|saved| {
+            let track = Track::from(saved.track);
+            let line = format!(
+                "🎵 {} — <i>{}</i>",
+                spotify_link(Entity::Track, track.id.as_deref(), &track.name),
+                html_escape(&track.artists.join(", "))
+            );
+            (saved.added_at, line)
+        })
+        .chain(albums.items.into_iter().map(|saved| {
+            let album = SavedAlbum::from(saved);
+            let line = format!(
+                "💿 {} — <i>{}</i>",
+                spotify_link(Entity::Album, Some(&album.id), &album.name),
+                html_escape(&album.artists.join(", "))
+            );
+            (album.added_at, line)
+        }))
+        .collect();
+    if additions.is_empty() {
+        return Ok("📭 Your library is empty so far.".to_string());
+    }
+    additions.sort_by_key(|(added_at, _)| std::cmp::Reverse(*added_at));
+    additions.truncate(SHOWN);
+
+    let locale = *state.locale.lock().await;
+    let mut response = "<b>🆕 New in Your Library</b>\n".to_string();
+    let mut last_day = None;
+    for (added_at, line) in &additions {
+        let day = added_at.date_naive();
+        if last_day != Some(day) {
+            response.push_str(&format!("\n<b>{}</b>\n", format_date(day, locale)));
+            last_day = Some(day);
+        }
+        response.push_str(line);
+        response.push('\n');
+    }
+
+    Ok(response)
+}
+
+/// Shows in the member's library; `/podcasts` has their latest episodes
+async fn get_saved_shows(state: &AppState) -> Result<String, String> {
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::ReadLibrary]).await?;
+
+    let page = spotify
+        .get_saved_show_manual(Some(LIBRARY_PAGE), None)
+        .await
+        .map_err(|_| "Failed to fetch your shows. Please try again.".to_string())?;
+    let total = page.total;
+    let shows: Vec<Show> = page.items.into_iter().map(Show::from).collect();
+    if shows.is_empty() {
+        return Ok("📭 You don't follow any podcasts or audiobooks yet.".to_string());
+    }
+
+    let locale = *state.locale.lock().await;
+    let mut response = format!(
+        "<b>📻 Your Shows</b>\n<i>{} followed</i>\n\n",
+        format_count(total as u64, locale)
+    );
+    for (idx, show) in shows.iter().enumerate() {
+        response.push_str(&format!(
+            "<b>{}</b>. {}\n<i>{}</i>\n",
+            idx + 1,
+            spotify_link(Entity::Show, Some(&show.id), &show.name),
+            html_escape(&show.publisher)
+        ));
+    }
+    if total as usize > shows.len() {
+        response.push_str(&format!(
+            "\n…and {} more in the Spotify app",
+            total as usize - shows.len()
+        ));
+    }
+    response.push_str("\nLatest episodes: <code>/podcasts</code>");
+
+    Ok(response)
+}
+
+fn episode_status(episode: &Episode) -> String {
+    match episode.progress {
+        EpisodeProgress::New => format!("🆕 {}", format_track_length(episode.duration_ms)),
+        EpisodeProgress::InProgress { position_ms } => format!(
+            "▶ {} left",
+            format_track_length(episode.duration_ms.saturating_sub(position_ms))
+        ),
+        EpisodeProgress::Played => "✓ played".to_string(),
+    }
+}
+
+async fn search_track(state: &AppState, query: &str) -> Result<String, String> {
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Please provide a search query.".to_string());
+    }
+
+    // Search in whole Spotify database; ask for extra results in case
+    // explicit ones get filtered out
+    let hide_explicit = *state.hide_explicit.lock().await;
+    let result = spotify
+        .search(
+            query,
+            SearchType::Track,
+            Some(market(state).await),
+            None,
+            Some(if hide_explicit { 15 } else { 5 }),
+            None,
+        )
+        .await
+        .map_err(|_| "Failed to search tracks. Please try again.".to_string())?;
+
+    let page = match result {
+        SearchResult::Tracks(page) => page,
+        _ => return Err("Failed to search tracks. Please try again.".to_string()),
+    };
+
+    let found = page.items.len();
+    let tracks: Vec<Track> = page
+        .items
+        .into_iter()
+        .map(Track::from)
+        .filter(|track| !(hide_explicit && track.explicit))
+        .take(5)
+        .collect();
+    if tracks.is_empty() {
+        return Ok(format!(
+            "📭 <b>Search Results for \"{}\"</b>\n\nNo tracks found.{}",
+            html_escape(query),
+            if found > 0 {
+                " Explicit results are hidden (<code>/explicit_filter off</code>)."
+            } else {
+                ""
+            }
+        ));
+    }
+
+    let mut response = format!(
+        "<b>📭 Search Results for \"{}\"</b>\n\n",
+        html_escape(query)
+    );
+    for (idx, track) in tracks.iter().enumerate() {
+        response.push_str(&format!(
+            "<b>{}</b>. {}\n<i>{}</i>\n{} · 🔥 {}\n",
+            idx + 1,
+            spotify_link(Entity::Track, track.id.as_deref(), &track.name),
+            html_escape(&track.artists.join(", ")),
+            track_details(track),
+            track.popularity
+        ));
+        // Pasting the URI into the desktop app's search bar jumps straight to it
+        if let Some(id) = &track.id {
+            response.push_str(&format!(
+                "<code>{}</code>\n",
+                spotify_uri(Entity::Track, id)
+            ));
+        }
+        response.push('\n');
+    }
+
+    Ok(response)
+}
+
+async fn list_playlists(state: &AppState) -> Result<String, String> {
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::ReadPlaylists]).await?;
+
+    let stream = spotify.current_user_playlists();
+    let playlists = collect_stream_n(stream, 20, Playlist::from)
+        .await
+        .map_err(|_| "Failed to fetch playlists. Please try again.".to_string())?;
+
+    if playlists.is_empty() {
+        return Ok("📭 <b>Your Playlists</b>\n\nNo playlists found. Create one with <code>/create_playlist</code>".to_string());
+    }
+
+    let locale = *state.locale.lock().await;
+    let mut response = "<b>📋 Your Playlists</b>\n\n".to_string();
+    for (idx, playlist) in playlists.iter().enumerate() {
+        response.push_str(&format!(
+            "<b>{}</b>. {}\n<i>{} tracks</i>\n\n",
+            idx + 1,
+            spotify_link(Entity::Playlist, Some(&playlist.id), &playlist.name),
+            format_count(playlist.total_tracks as u64, locale)
+        ));
+    }
+
+    Ok(response)
+}
+
+async fn get_playlist(
+    state: &AppState,
+    playlist_name: &str,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::ReadPlaylists]).await?;
+
+    if playlist_name.is_empty() {
+        return Err("Please provide a playlist name.".to_string());
+    }
+
+    let playlist = find_playlist(spotify, playlist_name).await?;
+    playlist_page(state, spotify, &playlist, 0).await
+}
+
+/// Another page of a `/playlist` listing, from its ◀/▶ buttons
+async fn get_playlist_page(
+    state: &AppState,
+    data: &str,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    let (playlist_id, offset) = data
+        .rsplit_once(':')
+        .and_then(|(id, offset)| Some((id, offset.parse::<u32>().ok()?)))
+        .ok_or_else(|| "This page is no longer available.".to_string())?;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::ReadPlaylists]).await?;
+
+    let playlist = find_in_stream(spotify.current_user_playlists(), Playlist::from, |p| {
+        p.id == playlist_id
+    })
+    .await
+    .map_err(|_| "Failed to fetch playlists. Please try again.".to_string())?
+    .ok_or_else(|| "That playlist no longer exists.".to_string())?;
+    playlist_page(state, spotify, &playlist, offset).await
+}
+
+async fn playlist_page(
+    state: &AppState,
+    spotify: &AuthCodeSpotify,
+    playlist: &Playlist,
+    offset: u32,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    const PAGE_SIZE: u32 = 15;
+
+    let playlist_id = convert::playlist_id(&playlist.id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    let page = spotify
+        .playlist_items_manual(
+            playlist_id,
+            None,
+            Some(market(state).await),
+            Some(PAGE_SIZE),
+            Some(offset),
+        )
+        .await
+        .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?;
+
+    let locale = *state.locale.lock().await;
+    let mut response = format!(
+        "<b>📋 {}</b>\n\n<b>Tracks:</b> {}\n\n",
+        spotify_link(Entity::Playlist, Some(&playlist.id), &playlist.name),
+        format_count(page.total as u64, locale)
+    );
+    if page.items.is_empty() {
+        response.push_str("📭 No tracks here.");
+    }
+    for (idx, item) in page.items.into_iter().enumerate() {
+        let position = offset as usize + idx + 1;
+        match convert::playlist_item_track(item) {
+            Some(track) => response.push_str(&format!(
+                "<b>{}</b>. {}\n<i>{}</i> · {}\n",
+                position,
+                spotify_link(Entity::Track, track.id.as_deref(), &track.name),
+                html_escape(&track.artists.join(", ")),
+                format_track_length(track.duration_ms)
+            )),
+            None => response.push_str(&format!(
+                "<b>{}</b>. <i>Local file or episode</i>\n",
+                position
+            )),
+        }
+    }
+
+    let mut buttons = Vec::new();
+    if offset > 0 {
+        buttons.push(InlineKeyboardButton::callback(
+            "◀ Previous",
+            format!(
+                "playlist:{}:{}",
+                playlist.id,
+                offset.saturating_sub(PAGE_SIZE)
+            ),
+        ));
+    }
+    if offset + PAGE_SIZE < page.total {
+        buttons.push(InlineKeyboardButton::callback(
+            "Next ▶",
+            format!("playlist:{}:{}", playlist.id, offset + PAGE_SIZE),
+        ));
+    }
+    if page.total > PAGE_SIZE {
+        response.push_str(&format!(
+            "\n<i>Page {} of {}</i>",
+            offset / PAGE_SIZE + 1,
+            page.total.div_ceil(PAGE_SIZE)
+        ));
+    }
+
+    Ok((response, InlineKeyboardMarkup::new(vec![buttons])))
+}
+
+async fn create_playlist(state: &AppState, playlist_name: &str) -> Result<String, String> {
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    if playlist_name.is_empty() {
+        return Err("Please provide a playlist name.".to_string());
+    }
+
+    const FEATURES: &[Feature] = &[Feature::ModifyPlaylists];
+    let granted = require_scopes(spotify, FEATURES).await?;
+
+    let user = spotify
+        .current_user()
+        .await
+        .map_err(|_| "Failed to fetch user info.".to_string())?;
+
+    spotify
+        .user_playlist_create(
+            user.id,
+            playlist_name,
+            Some(false),
+            Some(false),
+            Some("Created with Spotify Dashboard Bot"),
+        )
+        .await
+        .map_err(|err| {
+            scope_failure(
+                &err,
+                &granted,
+                FEATURES,
+                "Failed to create playlist. Please try again.",
+            )
+        })?;
+
+    audit(state, "create playlist", playlist_name.to_string()).await;
+
+    Ok(format!(
+        "✅ <b>Playlist Created</b>\n\n\
+         <b>Name:</b> {}\n\n\
+         You can now add tracks to this playlist.",
+        html_escape(playlist_name)
+    ))
+}
+
+async fn add_to_playlist(
+    state: &AppState,
+    song_name: &str,
+    playlist_name: &str,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+    const MAX_CHOICES: usize = 5;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+
+    if song_name.is_empty() || playlist_name.is_empty() {
+        return Err("Please provide both song name and playlist name.".to_string());
+    }
+
+    let granted = require_scopes(spotify, FEATURES).await?;
+
+    // Search the catalog and look up the playlist concurrently
+    let market = market(state).await;
+    let hide_explicit = *state.hide_explicit.lock().await;
+    let (result, playlist) = futures::try_join!(
+        async {
+            spotify
+                .search(
+                    song_name,
+                    SearchType::Track,
+                    Some(market),
+                    None,
+                    Some(10),
+                    None,
+                )
+                .await
+                .map_err(|_| "Failed to search tracks. Please try again.".to_string())
+        },
+        find_playlist_to_change(spotify, playlist_name),
+    )?;
+    let results: Vec<Track> = match result {
+        SearchResult::Tracks(page) => page
+            .items
+            .into_iter()
+            .map(Track::from)
+            .filter(|track| !(hide_explicit && track.explicit))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let close: Vec<&Track> = results
+        .iter()
+        .filter(|track| is_close_match(song_name, &track.name, &track.artists))
+        .collect();
+    // Versions of one song by the same artists (remasters, live cuts) aren't
+    // worth asking about; search already ranks the usual one first
+    let same_song = close.iter().all(|track| {
+        close
+            .first()
+            .is_some_and(|first| first.artists == track.artists)
+    });
+
+    let track = match close.first() {
+        Some(track) if same_song => (*track).clone(),
+        Some(_) => {
+            let choices: Vec<&Track> = close.into_iter().take(MAX_CHOICES).collect();
+            return Ok(choose_track(&choices, &playlist, song_name));
+        }
+        None => match saved_track_named(spotify, &granted, song_name, market).await? {
+            Some(track) => track,
+            None if results.is_empty() => {
+                return Err(format!(
+                    "No track found for \"{}\".",
+                    html_escape(song_name)
+                ))
+            }
+            None => {
+                let choices: Vec<&Track> = results.iter().take(MAX_CHOICES).collect();
+                return Ok(choose_track(&choices, &playlist, song_name));
+            }
+        },
+    };
+
+    let response = add_track(spotify, &granted, &track, &playlist).await?;
+    audit(
+        state,
+        "add track",
+        format!("{} to {}", track.name, playlist.name),
+    )
+    .await;
+    Ok((response, InlineKeyboardMarkup::default()))
+}
+
+/// Library fallback for names search didn't clearly match, when the member
+/// granted access to their saved tracks
+async fn saved_track_named(
+    spotify: &AuthCodeSpotify,
+    granted: &HashSet<String>,
+    song_name: &str,
+    market: Market,
+) -> Result<Option<Track>, String> {
+    if !scopes::missing_scopes(granted, &[Feature::ReadLibrary]).is_empty() {
+        return Ok(None);
+    }
+
+    let query_lower = song_name.to_lowercase();
+    let stream = spotify.current_user_saved_tracks(Some(market));
+    find_in_stream(
+        stream,
+        |item| Track::from(item.track),
+        |t| t.name.to_lowercase().contains(&query_lower),
+    )
+    .await
+    .map_err(|_| "Failed to fetch your saved tracks.".to_string())
+}
+
+fn choose_track(
+    tracks: &[&Track],
+    playlist: &Playlist,
+    song_name: &str,
+) -> (String, InlineKeyboardMarkup) {
+    const MAX_LABEL: usize = 60;
+
+    let keyboard: Vec<Vec<InlineKeyboardButton>> = tracks
+        .iter()
+        .filter_map(|track| {
+            let label = format!("{} — {}", track.name, track.artists.join(", "));
+            let label = match label.char_indices().nth(MAX_LABEL) {
+                Some((end, _)) => format!("{}…", &label[..end]),
+                None => label,
+            };
+            Some(vec![InlineKeyboardButton::callback(
+                label,
+                format!("add:{}:{}", track.canonical_id()?, playlist.id),
+            )])
+        })
+        .collect();
+
+    (
+        format!(
+            "🎯 <b>Which one?</b>\n\nSeveral tracks match \"{}\". \
+             Pick the one to add to <b>{}</b>:",
+            html_escape(song_name),
+            html_escape(&playlist.name)
+        ),
+        InlineKeyboardMarkup::new(keyboard),
+    )
+}
+
+/// Add the track picked from a "Which one?" prompt
+async fn add_chosen_track(state: &AppState, data: &str) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+
+    let (track_id, playlist_id) = data
+        .split_once(':')
+        .ok_or_else(|| "This choice is no longer valid.".to_string())?;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes(spotify, FEATURES).await?;
+
+    let id = convert::track_id(track_id).ok_or_else(|| "Track ID not available.".to_string())?;
+    let market = market(state).await;
+    let (track, playlist) = futures::try_join!(
+        async {
+            spotify
+                .track(id, Some(market))
+                .await
+                .map(Track::from)
+                .map_err(|_| "Failed to fetch the track. Please try again.".to_string())
+        },
+        playlist_by_id(spotify, playlist_id),
+    )?;
+
+    let response = add_track(spotify, &granted, &track, &playlist).await?;
+    audit(
+        state,
+        "add track",
+        format!("{} to {}", track.name, playlist.name),
+    )
+    .await;
+    Ok(response)
+}
+
+async fn add_track(
+    spotify: &AuthCodeSpotify,
+    granted: &HashSet<String>,
+    track: &Track,
+    playlist: &Playlist,
+) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+
+    // Add the original rather than this market's stand-in; Spotify relinks it
+    // on playback wherever the playlist is opened
+    let playable_id = track
+        .canonical_id()
+        .and_then(convert::playable_track)
+        .ok_or_else(|| "Track ID not available.".to_string())?;
+    let playlist_id = convert::playlist_id(&playlist.id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    spotify
+        .playlist_add_items(playlist_id, vec![playable_id], None)
+        .await
+        .map_err(|err| {
+            scope_failure(&err, granted, FEATURES, "Failed to add track to playlist.")
+        })?;
+
+    Ok(format!(
+        "✅ <b>Track Added</b>\n\n\
+         <b>Song:</b> {}\n\
+         <b>Artist:</b> {}\n\
+         <b>Playlist:</b> {}\n\n\
+         Track successfully added to your playlist!",
+        html_escape(&track.name),
+        html_escape(&track.artists.join(", ")),
+        html_escape(&playlist.name)
+    ))
+}
+
+async fn fill_playlist(state: &AppState, input: &str, dry_run: bool) -> Result<String, String> {
+    let request = parse_fill_args(input).map_err(|problems| {
+        list_args_error(
+            &problems,
+            "<code>/fill_playlist name from top|recent|liked [mood] [genre] [count]</code>\n\
+             e.g. <code>/fill_playlist Evening from liked calm jazz 15</code>",
+        )
+    })?;
+    let source_feature = match request.source {
+        FillSource::TopTracks | FillSource::Recent => Feature::Listening,
+        FillSource::Liked => Feature::ReadLibrary,
+    };
+    let features = [
+        source_feature,
+        Feature::ReadPlaylists,
+        Feature::ModifyPlaylists,
+    ];
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes_for(spotify, &features, dry_run).await?;
+
+    let playlist = find_playlist_to_change(spotify, &request.playlist).await?;
+    let playlist_id = convert::playlist_id(&playlist.id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+
+    let market = market(state).await;
+    let fetch_failed = || {
+        format!(
+            "Failed to fetch {}. Please try again.",
+            request.source.as_str()
+        )
+    };
+    let tracks: Vec<Track> = match request.source {
+        FillSource::TopTracks => top_tracks(state, spotify, TopRange::Medium).await?,
+        FillSource::Recent => spotify
+            .current_user_recently_played(Some(50), None)
+            .await
+            .map(|page| {
+                page.items
+                    .into_iter()
+                    .map(|play| Play::from(play).track)
+                    .collect()
+            })
+            .map_err(|_| fetch_failed())?,
+        FillSource::Liked => spotify
+            .current_user_saved_tracks_manual(Some(market), Some(50), None)
+            .await
+            .map(|page| {
+                page.items
+                    .into_iter()
+                    .map(|saved| Track::from(saved.track))
+                    .collect()
+            })
+            .map_err(|_| fetch_failed())?,
+    };
+
+    let hide_explicit = *state.hide_explicit.lock().await;
+    let mut tracks: Vec<Track> = tracks
+        .into_iter()
+        .filter(|track| !(hide_explicit && track.explicit))
+        .collect();
+    if let Some(mood) = request.mood {
+        let ids: Vec<String> = tracks.iter().filter_map(|track| track.id.clone()).collect();
+        let features = fetch_audio_features(spotify, &ids)
+            .await
+            .map_err(|_| "Failed to fetch audio features. Please try again.".to_string())?;
+        tracks.retain(|track| {
+            track
+                .id
+                .as_deref()
+                .and_then(|id| features.get(id))
+                .is_some_and(|features| backend().classify_mood(*features).mood == mood)
+        });
+    }
+    if let Some(genre) = request.genre {
+        tracks = tracks_of_genre(state, spotify, tracks, TopRange::Medium, genre).await?;
+    }
+    let filters: Vec<String> = request
+        .mood
+        .map(|mood| mood.as_str().to_lowercase())
+        .into_iter()
+        .chain(request.genre.map(|genre| genre.as_str().to_string()))
+        .collect();
+
+    let writer = PlaylistWriter::new(spotify, &granted, &features);
+    let existing = writer.contents(&playlist_id).await?;
+    let candidates: Vec<String> = tracks
+        .iter()
+        .filter_map(|track| track.canonical_id().map(str::to_string))
+        .collect();
+    let plan = plan_add(&candidates, &existing, writer.dedupe, request.limit);
+    if plan.ids.is_empty() {
+        return Ok(format!(
+            "📭 Nothing new to add from {}{} — <b>{}</b> already has them all.",
+            request.source.as_str(),
+            if filters.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", filters.join(", "))
+            },
+            html_escape(&playlist.name)
+        ));
+    }
+
+    if dry_run {
+        return Ok(preview(
+            "Fill Playlist",
+            format!(
+                "Would add {} track(s) from {} to <b>{}</b>",
+                plan.ids.len(),
+                request.source.as_str(),
+                html_escape(&playlist.name)
+            ),
+            &tracks_in_order(&tracks, plan.ids.iter().map(String::as_str)),
+        ));
+    }
+
+    writer.write(&playlist_id, &plan.ids).await?;
+    let count = plan.ids.len();
+
+    audit(
+        state,
+        "add tracks",
+        format!(
+            "{} track(s) to {} from {}",
+            count,
+            playlist.name,
+            request.source.as_str()
+        ),
+    )
+    .await;
+
+    Ok(format!(
+        "✅ <b>Playlist Filled</b>\n\n<b>Playlist:</b> {}\n<b>Added:</b> {} track(s) from {}{}{}{}",
+        html_escape(&playlist.name),
+        count,
+        request.source.as_str(),
+        request
+            .mood
+            .map(|mood| format!(", {} {}", mood.emoji(), mood.as_str().to_lowercase()))
+            .unwrap_or_default(),
+        request
+            .genre
+            .map(|genre| format!(", {}", genre.as_str()))
+            .unwrap_or_default(),
+        skipped_note(plan.skipped)
+    ))
+}
+
+/// How far ahead the running playlist may look for a track in a better key
+const RUN_KEY_LOOKAHEAD: usize = 3;
+
+async fn run_playlist(state: &AppState, input: &str, dry_run: bool) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::Listening, Feature::ModifyPlaylists];
+    // Liked songs are a big pool to draw from, but a few pages are plenty
+    const MAX_LIKED: usize = 300;
+
+    let (bpm, tolerance) = parse_pace(input).ok_or_else(|| {
+        format!(
+            "Usage: <code>/run_playlist bpm [tolerance]</code>, e.g. <code>/run_playlist 170 5</code> \
+             ({}–{} BPM, tolerance up to {})",
+            MIN_BPM, MAX_BPM, MAX_TOLERANCE
+        )
+    })?;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes_for(spotify, FEATURES, dry_run).await?;
+    let use_liked = scopes::missing_scopes(&granted, &[Feature::ReadLibrary]).is_empty();
+
+    let market = market(state).await;
+    let (top, liked) = futures::try_join!(top_tracks(state, spotify, TopRange::Medium), async {
+        if !use_liked {
+            return Ok(Vec::new());
+        }
+        let stream = spotify.current_user_saved_tracks(Some(market));
+        collect_stream_n(stream, MAX_LIKED, |item| Track::from(item.track))
+            .await
+            .map_err(|_| "Failed to fetch your saved tracks. Please try again.".to_string())
+    },)?;
+
+    let hide_explicit = *state.hide_explicit.lock().await;
+    let tracks: Vec<Track> = top
+        .into_iter()
+        .chain(liked)
+        .filter(|track| !(hide_explicit && track.explicit))
+        .collect();
+    let ids: Vec<String> = tracks.iter().filter_map(|track| track.id.clone()).collect();
+    let features = fetch_audio_features(spotify, &ids)
+        .await
+        .map_err(|_| "Failed to fetch audio features. Please try again.".to_string())?;
+    let known_keys = cached_keys(&ids).await;
+    // Keyed by the canonical ID so the playlist works in any market
+    let candidates: Vec<(String, AudioFeatures)> = tracks
+        .iter()
+        .filter_map(|track| {
+            let features = features.get(track.id.as_deref()?)?;
+            Some((track.canonical_id()?.to_string(), *features))
+        })
+        .collect();
+    let keys: HashMap<&str, Camelot> = tracks
+        .iter()
+        .filter_map(|track| {
+            let key = known_keys.get(track.id.as_deref()?)?;
+            Some((track.canonical_id()?, *key))
+        })
+        .collect();
+
+    let picked = pick_for_pace(&candidates, bpm, tolerance, MAX_TRACKS);
+    // Only nearby tracks may swap, so the run still builds up
+    let picked_keys: Vec<Option<Camelot>> = picked
+        .iter()
+        .map(|id| keys.get(id.as_str()).copied())
+        .collect();
+    let picked: Vec<String> = harmonic_order(&picked_keys, RUN_KEY_LOOKAHEAD)
+        .into_iter()
+        .map(|i| picked[i].clone())
+        .collect();
+    if picked.is_empty() {
+        return Ok(format!(
+            "📭 None of your {} tracks run at {:.0} ± {:.0} BPM. Try a wider tolerance, \
+             e.g. <code>/run_playlist {:.0} 10</code>.",
+            if use_liked { "top or liked" } else { "top" },
+            bpm,
+            tolerance,
+            bpm
+        ));
+    }
+
+    let name = format!("Run · {:.0} BPM", bpm);
+    if dry_run {
+        return Ok(preview(
+            "Running Playlist",
+            format!(
+                "Would create <b>{}</b> with {} track(s)",
+                html_escape(&name),
+                picked.len()
+            ),
+            &tracks_in_order(&tracks, picked.iter().map(String::as_str)),
+        ));
+    }
+
+    let plan = PlaylistWriter::new(spotify, &granted, FEATURES)
+        .create(
+            &name,
+            "Tracks at running pace, calmest first. Made with Spotify Dashboard Bot",
+            &picked,
+        )
+        .await?;
+    let count = plan.ids.len();
+
+    audit(
+        state,
+        "create playlist",
+        format!("{} with {} track(s)", name, count),
+    )
+    .await;
+
+    Ok(format!(
+        "🏃 <b>Running Playlist Created</b>\n\n<b>Playlist:</b> {}\n<b>Tracks:</b> {} at {:.0} ± {:.0} BPM\n\n\
+         <i>Half- and double-time tracks count too. They're ordered by energy so the run builds up, \
+         with neighbouring keys kept compatible where possible.</i>{}{}",
+        html_escape(&name),
+        count,
+        bpm,
+        tolerance,
+        if use_liked {
+            ""
+        } else {
+            "\n<i>Only top tracks were used; grant Liked Songs access for more.</i>"
+        },
+        skipped_note(plan.skipped)
+    ))
+}
+
+/// A playlist of the requested length from the user's top and liked tracks,
+/// most danceable first, keeping adjacent tracks close in tempo or key
+async fn party_playlist(state: &AppState, input: &str, dry_run: bool) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::Listening, Feature::ModifyPlaylists];
+    const MAX_LIKED: usize = 300;
+
+    let minutes = parse_minutes(input).map_err(|problems| {
+        list_args_error(
+            &problems,
+            &format!(
+                "<code>/party_playlist [minutes]</code>, {}–{} minutes (default {})",
+                MIN_PARTY_MINUTES, MAX_PARTY_MINUTES, DEFAULT_PARTY_MINUTES
+            ),
+        )
+    })?;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes_for(spotify, FEATURES, dry_run).await?;
+    let use_liked = scopes::missing_scopes(&granted, &[Feature::ReadLibrary]).is_empty();
+
+    let market = market(state).await;
+    let (top, liked) = futures::try_join!(top_tracks(state, spotify, TopRange::Medium), async {
+        if !use_liked {
+            return Ok(Vec::new());
+        }
+        let stream = spotify.current_user_saved_tracks(Some(market));
+        collect_stream_n(stream, MAX_LIKED, |item| Track::from(item.track))
+            .await
+            .map_err(|_| "Failed to fetch your saved tracks. Please try again.".to_string())
+    },)?;
+
+    let hide_explicit = *state.hide_explicit.lock().await;
+    let tracks: Vec<Track> = top
+        .into_iter()
+        .chain(liked)
+        .filter(|track| !(hide_explicit && track.explicit))
+        .collect();
+    let ids: Vec<String> = tracks.iter().filter_map(|track| track.id.clone()).collect();
+    let features = fetch_audio_features(spotify, &ids)
+        .await
+        .map_err(|_| "Failed to fetch audio features. Please try again.".to_string())?;
+    let keys = cached_keys(&ids).await;
+    // Keyed by the canonical ID so the playlist works in any market
+    let candidates: Vec<PartyCandidate> = tracks
+        .iter()
+        .filter_map(|track| {
+            let id = track.id.as_deref()?;
+            let features = features.get(id)?;
+            Some(PartyCandidate {
+                id: track.canonical_id()?.to_string(),
+                duration_ms: track.duration_ms,
+                danceability: features.danceability,
+                tempo: features.tempo,
+                key: keys.get(id).copied(),
+            })
+        })
+        .collect();
+
+    let order = build_party(&candidates, minutes as u64 * 60_000);
+    if order.is_empty() {
+        return Ok("📭 No tracks with audio features to build a party from.".to_string());
+    }
+    let smooth = order
+        .windows(2)
+        .filter(|pair| candidates[pair[0]].flows_into(&candidates[pair[1]]))
+        .count();
+    let total_ms: u64 = order
+        .iter()
+        .map(|i| candidates[*i].duration_ms as u64)
+        .sum();
+
+    let name = format!("Party · {} min", minutes);
+    let locale = *state.locale.lock().await;
+    if dry_run {
+        return Ok(preview(
+            "Party Playlist",
+            format!(
+                "Would create <b>{}</b> with {} track(s), {}",
+                html_escape(&name),
+                order.len(),
+                format_duration(std::time::Duration::from_millis(total_ms), locale)
+            ),
+            &tracks_in_order(&tracks, order.iter().map(|i| candidates[*i].id.as_str())),
+        ));
+    }
+
+    let ids: Vec<String> = order.iter().map(|i| candidates[*i].id.clone()).collect();
+    let plan = PlaylistWriter::new(spotify, &granted, FEATURES)
+        .create(
+            &name,
+            "Most danceable first, smooth tempo and key changes. Made with Spotify Dashboard Bot",
+            &ids,
+        )
+        .await?;
+    let count = plan.ids.len();
+
+    audit(
+        state,
+        "create playlist",
+        format!("{} with {} track(s)", name, count),
+    )
+    .await;
+
+    let mut response = format!(
+        "🪩 <b>Party Playlist Created</b>\n\n<b>Playlist:</b> {}\n<b>Tracks:</b> {} · {}\n\
+         <b>Smooth transitions:</b> {} of {}\n\n\
+         <i>Neighbouring tracks are within {:.0} BPM or in compatible keys where possible.</i>",
+        html_escape(&name),
+        count,
+        format_duration(std::time::Duration::from_millis(total_ms), locale),
+        smooth,
+        order.len().saturating_sub(1),
+        TEMPO_STEP
+    );
+    if total_ms < minutes as u64 * 60_000 {
+        response.push_str("\n<i>Your library ran out before the requested length.</i>");
+    }
+    if !use_liked {
+        response.push_str("\n<i>Only top tracks were used; grant Liked Songs access for more.</i>");
+    }
+    response.push_str(&skipped_note(plan.skipped));
+    Ok(response)
+}
+
+async fn backup_playlist(state: &AppState, playlist_name: &str) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists];
+
+    let playlist_name = playlist_name.trim();
+    if playlist_name.is_empty() {
+        return Err("Usage: <code>/backup_playlist playlist_name</code>".to_string());
+    }
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, FEATURES).await?;
+
+    let playlist = find_playlist(spotify, playlist_name).await?;
+    let playlist_id = convert::playlist_id(&playlist.id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    let items: Vec<Option<Track>> = collect_stream(
+        spotify.playlist_items(playlist_id, None, None),
+        convert::playlist_item_track,
+    )
+    .await
+    .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?;
+    drop(guard);
+
+    let backup = take_backup(state, &playlist, &items, "manual", None).await;
+    let mut response = format!(
+        "💾 <b>Backup Saved</b>\n\n<b>Playlist:</b> {}\n<b>Tracks:</b> {}\n",
+        html_escape(&playlist.name),
+        backup.track_ids.len()
+    );
+    if backup.skipped > 0 {
+        response.push_str(&format!(
+            "<i>{} local file(s) or episode(s) can't be restored and were left out.</i>\n",
+            backup.skipped
+        ));
+    }
+    response.push_str(&format!(
+        "\nRestore with <code>/restore_playlist {} | 1</code>. \
+         Backups are kept until the bot restarts.",
+        html_escape(&playlist.name)
+    ));
+
+    Ok(response)
+}
+
+async fn take_backup(
+    state: &AppState,
+    playlist: &Playlist,
+    items: &[Option<Track>],
+    reason: &str,
+    reorder: Option<&[usize]>,
+) -> PlaylistBackup {
+    let track_ids: Vec<String> = items
+        .iter()
+        .flatten()
+        .filter_map(|track| track.canonical_id().map(str::to_string))
+        .collect();
+    let backup = PlaylistBackup {
+        playlist_id: playlist.id.clone(),
+        playlist_name: playlist.name.clone(),
+        snapshot_id: playlist.snapshot_id.clone(),
+        skipped: items.len() - track_ids.len(),
+        reorder: reorder.map(<[usize]>::to_vec),
+        track_ids,
+        taken_at: chrono::Utc::now(),
+        reason: reason.to_string(),
+    };
+    state.backups.lock().await.push(backup.clone());
+    backup
+}
+
+/// Without a number, list the playlist's backups; with one, put that
+/// backup's tracks back in place of the current ones
+async fn restore_playlist(state: &AppState, input: &str, dry_run: bool) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+    const USAGE: &str = "Usage: <code>/restore_playlist name</code> to list backups, \
+                         <code>/restore_playlist name | 1</code> to restore the latest";
+
+    let (playlist_name, number) = match input.split_once('|') {
+        Some((name, number)) => (
+            name.trim(),
+            Some(
+                number
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| USAGE.to_string())?,
+            ),
+        ),
+        None => (input.trim(), None),
+    };
+    let locale = *state.locale.lock().await;
+    if playlist_name.is_empty() {
+        let backups = state.backups.lock().await;
+        let playlists = backups.playlists();
+        if playlists.is_empty() || number.is_some() {
+            return Err(USAGE.to_string());
+        }
+        let mut response = "<b>💾 Backed Up Playlists</b>\n\n".to_string();
+        for (id, name) in playlists {
+            response.push_str(&format!(
+                "• {} — {} backup(s)\n",
+                html_escape(name),
+                backups.for_playlist(id).len()
+            ));
+        }
+        response.push_str(&format!("\n{}", USAGE));
+        return Ok(response);
+    }
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes_for(spotify, FEATURES, dry_run).await?;
+
+    // Backups are looked up by the name they were taken under, so those of a
+    // deleted playlist can still be restored, into a new playlist
+    let stored_id = state
+        .backups
+        .lock()
+        .await
+        .playlist_named(playlist_name)
+        .map(str::to_string);
+    let (playlist, playlist_id) = match stored_id {
+        Some(id) => (
+            find_in_stream(spotify.current_user_playlists(), Playlist::from, |p| {
+                p.id == id
+            })
+            .await
+            .map_err(|_| "Failed to fetch playlists. Please try again.".to_string())?,
+            id,
+        ),
+        None => {
+            let playlist = find_playlist_to_change(spotify, playlist_name).await?;
+            let id = playlist.id.clone();
+            (Some(playlist), id)
+        }
+    };
+
+    let backups = state.backups.lock().await;
+    let available = backups.for_playlist(&playlist_id);
+    let Some(latest) = available.first() else {
+        let name = playlist.as_ref().map_or(playlist_name, |p| p.name.as_str());
+        return Ok(format!(
+            "📭 No backups of <b>{}</b> yet. Take one with <code>/backup_playlist {}</code>.",
+            html_escape(name),
+            html_escape(name)
+        ));
+    };
+    let name = playlist
+        .as_ref()
+        .map_or(latest.playlist_name.clone(), |p| p.name.clone());
+
+    let Some(number) = number else {
+        let mut response = format!("<b>💾 Backups of {}</b>\n\n", html_escape(&name));
+        if playlist.is_none() {
+            response.push_str("<i>This playlist was deleted; restoring creates a new one.</i>\n\n");
+        }
+        for (idx, backup) in available.iter().enumerate() {
+            response.push_str(&format!(
+                "<b>{}</b>. {} · {} tracks · <i>{}</i>{}\n",
+                idx + 1,
+                format_datetime(backup.taken_at, locale),
+                backup.track_ids.len(),
+                html_escape(&backup.reason),
+                if playlist
+                    .as_ref()
+                    .is_some_and(|p| p.snapshot_id == backup.snapshot_id)
+                {
+                    " · same as now"
+                } else {
+                    ""
+                }
+            ));
+        }
+        response.push_str(&format!(
+            "\nRestore one with <code>/restore_playlist {} | 1</code>.",
+            html_escape(&name)
+        ));
+        return Ok(response);
+    };
+
+    let backup = number
+        .checked_sub(1)
+        .and_then(|idx| available.get(idx))
+        .map(|backup| (*backup).clone())
+        .ok_or_else(|| format!("There is no backup #{}.", number))?;
+    drop(backups);
+
+    let Some(playlist) = playlist else {
+        if dry_run {
+            return Ok(preview(
+                "Restore Playlist",
+                format!(
+                    "Would create <b>{}</b> again with {} track(s) from {} ({})",
+                    html_escape(&name),
+                    backup.track_ids.len(),
+                    format_datetime(backup.taken_at, locale),
+                    html_escape(&backup.reason)
+                ),
+                &[],
+            ));
+        }
+        let description = format!(
+            "Restored from a backup taken {}",
+            format_datetime(backup.taken_at, locale)
+        );
+        let writer = PlaylistWriter::new(spotify, &granted, FEATURES);
+        let new_id = writer.create_empty(&name, &description).await?;
+        state
+            .backups
+            .lock()
+            .await
+            .move_to(&backup.playlist_id, new_id.id());
+        writer.write(&new_id, &backup.track_ids).await?;
+        audit(
+            state,
+            "create playlist",
+            format!(
+                "{} with {} track(s) from the backup of {}",
+                name,
+                backup.track_ids.len(),
+                format_datetime(backup.taken_at, locale)
+            ),
+        )
+        .await;
+        return Ok(format!(
+            "♻️ <b>Playlist Recreated</b>\n\n<b>{}</b> was deleted, so a new playlist \
+             with {} track(s) from {} was created.",
+            html_escape(&name),
+            backup.track_ids.len(),
+            format_datetime(backup.taken_at, locale)
+        ));
+    };
+
+    if backup.snapshot_id == playlist.snapshot_id {
+        return Ok(format!(
+            "✅ <b>{}</b> hasn't changed since that backup.",
+            html_escape(&playlist.name)
+        ));
+    }
+
+    if dry_run {
+        return Ok(preview(
+            "Restore Playlist",
+            format!(
+                "Would replace the {} track(s) of <b>{}</b> with {} track(s) from {} ({})",
+                playlist.total_tracks,
+                html_escape(&playlist.name),
+                backup.track_ids.len(),
+                format_datetime(backup.taken_at, locale),
+                html_escape(&backup.reason)
+            ),
+            &[],
+        ));
+    }
+
+    let count = restore_backup(spotify, &granted, &backup).await?;
+
+    audit(
+        state,
+        "restore playlist",
+        format!(
+            "{} to {} track(s) from the backup of {}",
+            playlist.name,
+            count,
+            format_datetime(backup.taken_at, locale)
+        ),
+    )
+    .await;
+
+    Ok(format!(
+        "♻️ <b>Playlist Restored</b>\n\n<b>{}</b> is back to {} track(s) from {}.",
+        html_escape(&playlist.name),
+        count,
+        format_datetime(backup.taken_at, locale)
+    ))
+}
+
+/// Put the tracks of `backup` back in place of the playlist's current ones.
+/// Returns how many tracks the playlist has now.
+async fn restore_backup(
+    spotify: &AuthCodeSpotify,
+    granted: &HashSet<String>,
+    backup: &PlaylistBackup,
+) -> Result<usize, String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+
+    let playlist_id = convert::playlist_id(&backup.playlist_id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    let ids: Vec<_> = backup
+        .track_ids
+        .iter()
+        .filter_map(|id| convert::playable_track(id))
+        .collect();
+    // Replacing takes at most 100 tracks; the rest are appended in order
+    let mut batches = ids.chunks(100);
+    spotify
+        .playlist_replace_items(
+            playlist_id.clone(),
+            batches
+                .next()
+                .unwrap_or_default()
+                .iter()
+                .map(PlayableId::as_ref),
+        )
+        .await
+        .map_err(|err| scope_failure(&err, granted, FEATURES, "Failed to restore the playlist."))?;
+    for batch in batches {
+        spotify
+            .playlist_add_items(
+                playlist_id.clone(),
+                batch.iter().map(PlayableId::as_ref),
+                None,
+            )
+            .await
+            .map_err(|err| {
+                scope_failure(
+                    &err,
+                    granted,
+                    FEATURES,
+                    "Failed to restore the playlist. It may be partly restored.",
+                )
+            })?;
+    }
+    Ok(ids.len())
+}
+
+/// Restore the backup taken right before a change, from its "↩ Undo" button
+async fn undo_change(state: &AppState, data: &str) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+
+    let (playlist_id, taken_at_ms) = data
+        .split_once(':')
+        .and_then(|(id, ms)| Some((id, ms.parse::<i64>().ok()?)))
+        .ok_or_else(|| "This undo button is no longer valid.".to_string())?;
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes(spotify, FEATURES).await?;
+
+    let backup = state
+        .backups
+        .lock()
+        .await
+        .find(playlist_id, taken_at_ms)
+        .cloned()
+        .ok_or_else(|| "That backup is gone, so this change can't be undone.".to_string())?;
+    if !backup.can_undo(chrono::Utc::now()) {
+        return Err(format!(
+            "Undo only works for {} minutes. Use <code>/restore_playlist {}</code> to go back further.",
+            UNDO_WINDOW_MINUTES,
+            html_escape(&backup.playlist_name)
+        ));
+    }
+
+    let count = match backup.undo_plan() {
+        Some(UndoPlan::MoveBack(moves)) => {
+            let playlist_id = convert::playlist_id(&backup.playlist_id)
+                .ok_or_else(|| "Playlist ID not available.".to_string())?;
+            apply_moves(
+                spotify,
+                &playlist_id,
+                &moves,
+                &granted,
+                FEATURES,
+                "Failed to undo the reorder. It may be partly undone.",
+            )
+            .await?;
+            backup.track_ids.len() + backup.skipped
+        }
+        Some(UndoPlan::Replace) => restore_backup(spotify, &granted, &backup).await?,
+        None => {
+            return Err(format!(
+                "<b>{}</b> has {} local file(s) or episode(s), which undoing would remove, \
+                 so this change can't be undone.",
+                html_escape(&backup.playlist_name),
+                backup.skipped
+            ))
+        }
+    };
+    audit(
+        state,
+        "undo",
+        format!(
+            "{} back to {} track(s), {}",
+            backup.playlist_name, count, backup.reason
+        ),
+    )
+    .await;
+
+    Ok(format!(
+        "↩ <b>Undone</b>\n\n<b>{}</b> is back to how it was {}, with {} track(s).",
+        html_escape(&backup.playlist_name),
+        html_escape(&backup.reason),
+        count
+    ))
+}
+
+/// The "↩ Undo" button, left out when the change can't be undone safely
+fn undo_keyboard(backup: &PlaylistBackup) -> InlineKeyboardMarkup {
+    if backup.undo_plan().is_none() {
+        return InlineKeyboardMarkup::default();
+    }
+    InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+        "↩ Undo",
+        format!(
+            "undo:{}:{}",
+            backup.playlist_id,
+            backup.taken_at.timestamp_millis()
+        ),
+    )]])
+}
+
+fn undo_hint(backup: &PlaylistBackup) -> String {
+    if backup.undo_plan().is_none() {
+        return "<i>No undo: the playlist has local files or episodes, which undoing would remove.</i>"
+            .to_string();
+    }
+    format!(
+        "<i>↩ Undo works for {} minutes, then use <code>/restore_playlist {} | 1</code></i>",
+        UNDO_WINDOW_MINUTES,
+        html_escape(&backup.playlist_name)
+    )
+}
+
+/// Run a playlist command without changing anything, e.g.
+/// `/preview smart_shuffle Gym | rise`
+async fn preview_command(state: &AppState, input: &str) -> Result<String, String> {
+    let input = input.trim().trim_start_matches('/');
+    let (command, args) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+    // Commands may carry the bot's name in groups, e.g. /fill_playlist@bot
+    let command = command.split('@').next().unwrap_or_default();
+    match command.to_lowercase().as_str() {
+        "fill_playlist" => fill_playlist(state, args, true).await,
+        "run_playlist" => run_playlist(state, args, true).await,
+        "party_playlist" => party_playlist(state, args, true).await,
+        "restore_playlist" => restore_playlist(state, args, true).await,
+        "smart_shuffle" => smart_shuffle(state, args, true)
+            .await
+            .map(|(response, _)| response),
+        "harmonic_sort" => harmonic_sort(state, args, true)
+            .await
+            .map(|(response, _)| response),
+        _ => Err("Usage: <code>/preview command arguments</code>, e.g. \
+                  <code>/preview smart_shuffle Gym</code>\n\n\
+                  Works with <code>fill_playlist</code>, <code>run_playlist</code>, \
+                  <code>party_playlist</code>, <code>restore_playlist</code>, \
+                  <code>smart_shuffle</code> and <code>harmonic_sort</code>. \
+                  <code>/clean_playlist</code> and <code>/playlist_outliers</code> \
+                  always ask before removing anything."
+            .to_string()),
+    }
+}
+
+/// What a playlist command would do, with the tracks it would add or the
+/// new order
+fn preview(title: &str, summary: String, tracks: &[&Track]) -> String {
+    const SHOWN: usize = 15;
+
+    let mut response = format!("👀 <b>Preview: {}</b>\n{}\n\n", title, summary);
+    for (idx, track) in tracks.iter().take(SHOWN).enumerate() {
+        response.push_str(&format!(
+            "<b>{}</b>. {} — <i>{}</i>\n",
+            idx + 1,
+            html_escape(&track.name),
+            html_escape(&track.artists.join(", "))
+        ));
+    }
+    if tracks.len() > SHOWN {
+        response.push_str(&format!("…and {} more\n", tracks.len() - SHOWN));
+    }
+    if !tracks.is_empty() {
+        response.push('\n');
+    }
+    response.push_str("<i>Nothing was changed. Run the command without /preview to apply it.</i>");
+    response
+}
+
+/// `ids` (canonical) looked up in `tracks`, in the order of `ids`
+fn tracks_in_order<'a, 'b>(
+    tracks: &'a [Track],
+    ids: impl Iterator<Item = &'b str>,
+) -> Vec<&'a Track> {
+    let by_id: HashMap<&str, &Track> = tracks
+        .iter()
+        .filter_map(|track| Some((track.canonical_id()?, track)))
+        .collect();
+    ids.filter_map(|id| by_id.get(id).copied()).collect()
+}
+
+async fn smart_shuffle(
+    state: &AppState,
+    input: &str,
+    dry_run: bool,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    // Every move is one Spotify call, so very long playlists are refused
+    const MAX_TRACKS: usize = 200;
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+
+    let (playlist_name, shape) = match input.split_once('|') {
+        Some((name, shape)) => (
+            name.trim(),
+            ArcShape::parse(shape).ok_or_else(|| {
+                "Usage: <code>/smart_shuffle playlist | arc</code> (or <code>rise</code>, \
+                 <code>fall</code>)"
+                    .to_string()
+            })?,
+        ),
+        None => (input.trim(), ArcShape::Arc),
+    };
+    if playlist_name.is_empty() {
+        return Err("Please provide a playlist name.".to_string());
+    }
+
+    let guard = state.spotify.lock().await;
     let spotify = guard
         .as_ref()
         .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes_for(spotify, FEATURES, dry_run).await?;
 
-    let stream = spotify.current_user_top_tracks(None);
-    let tracks = collect_stream(stream, |track| crate::models::spotify::Track {
-        name: track.name,
-        artists: track.artists.into_iter().map(|a| a.name).collect(),
-    })
+    let playlist = find_playlist_to_change(spotify, playlist_name).await?;
+    let playlist_id = convert::playlist_id(&playlist.id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    if playlist.total_tracks as usize > MAX_TRACKS {
+        return Err(format!(
+            "Smart shuffle supports playlists of up to {} tracks.",
+            MAX_TRACKS
+        ));
+    }
+
+    // Keep one entry per position so moves line up with the real playlist
+    let items: Vec<Option<Track>> = collect_stream(
+        spotify.playlist_items(playlist_id.clone(), None, None),
+        convert::playlist_item_track,
+    )
     .await
-    .map_err(|_| "Failed to fetch top tracks. Please try again.".to_string())?;
+    .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?;
+    if items.len() < 3 {
+        return Ok((
+            "🔀 This playlist is too short to shuffle.".to_string(),
+            InlineKeyboardMarkup::default(),
+        ));
+    }
+
+    let ids: Vec<String> = items
+        .iter()
+        .flatten()
+        .filter_map(|track| track.id.clone())
+        .collect();
+    let features = fetch_audio_features(spotify, &ids)
+        .await
+        .map_err(|_| "Failed to fetch audio features. Please try again.".to_string())?;
+    if features.is_empty() {
+        return Err("Spotify returned no audio features for this playlist.".to_string());
+    }
+
+    // Tracks without features (episodes, local files) sit in the middle
+    let scores: Vec<f32> = items
+        .iter()
+        .map(|track| {
+            track
+                .as_ref()
+                .and_then(|track| features.get(track.id.as_deref()?))
+                .map(energy_score)
+                .unwrap_or(0.5)
+        })
+        .collect();
+    let order = arc_order(&scores, shape);
+    let moves = reorder_moves(&order);
+    if dry_run {
+        let shuffled: Vec<&Track> = order.iter().filter_map(|i| items[*i].as_ref()).collect();
+        return Ok((
+            preview(
+                "Smart Shuffle",
+                format!(
+                    "Would move {} of {} tracks in <b>{}</b> into a {} order",
+                    moves.len(),
+                    items.len(),
+                    html_escape(&playlist.name),
+                    shape.as_str()
+                ),
+                &shuffled,
+            ),
+            InlineKeyboardMarkup::default(),
+        ));
+    }
+    let backup = if moves.is_empty() {
+        None
+    } else {
+        Some(
+            take_backup(
+                state,
+                &playlist,
+                &items,
+                "before smart shuffle",
+                Some(&order),
+            )
+            .await,
+        )
+    };
+    apply_moves(
+        spotify,
+        &playlist_id,
+        &moves,
+        &granted,
+        FEATURES,
+        "Failed to reorder the playlist. It may be partly shuffled.",
+    )
+    .await?;
+    if !moves.is_empty() {
+        audit(
+            state,
+            "reorder playlist",
+            format!(
+                "{} by smart shuffle ({}), {} move(s)",
+                playlist.name,
+                shape.as_str(),
+                moves.len()
+            ),
+        )
+        .await;
+    }
+
+    let name_at = |position: usize| {
+        items[order[position]]
+            .as_ref()
+            .map(|track| html_escape(&track.name))
+            .unwrap_or_else(|| "—".to_string())
+    };
+    let peak = (0..order.len())
+        .max_by(|a, b| scores[order[*a]].total_cmp(&scores[order[*b]]))
+        .unwrap_or(0);
+    let shaped: Vec<f32> = order.iter().map(|i| scores[*i]).collect();
+    let levels = level_sequence(&shaped);
+
+    let response = format!(
+        "🔀 <b>Smart Shuffle: {}</b>\n<i>{} · {} of {} tracks moved</i>\n\n\
+         <b>Opens with:</b> {} ⚡{}\n\
+         <b>Peaks at:</b> {} ⚡{}\n\
+         <b>Ends with:</b> {} ⚡{}\n\n\
+         {}",
+        html_escape(&playlist.name),
+        shape.as_str(),
+        moves.len(),
+        items.len(),
+        name_at(0),
+        levels[0],
+        name_at(peak),
+        levels[peak],
+        name_at(order.len() - 1),
+        levels[order.len() - 1],
+        backup.as_ref().map(undo_hint).unwrap_or_default()
+    );
+    Ok((
+        response,
+        backup.as_ref().map(undo_keyboard).unwrap_or_default(),
+    ))
+}
+
+// Audio features are fetched in batches, so cap the work per command
+const MAX_ANALYZED_TRACKS: usize = 500;
+
+/// Aggregate sound of a playlist: tempo and energy spread, genre and mood
+/// mix, total length and how energy moves from start to end
+async fn analyze_playlist(state: &AppState, playlist_name: &str) -> Result<String, String> {
+    const CURVE_POINTS: usize = 24;
+    const MAX_GENRES: usize = 5;
+
+    let playlist_name = playlist_name.trim();
+    if playlist_name.is_empty() {
+        return Err("Please provide a playlist name.".to_string());
+    }
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::ReadPlaylists]).await?;
 
+    let playlist = find_playlist(spotify, playlist_name).await?;
+    let (tracks, features) = playlist_sound(spotify, &playlist).await?;
     if tracks.is_empty() {
-        return Ok("📭 No top tracks found. Start listening to see your favorites!".to_string());
+        return Ok(format!(
+            "📭 <b>{}</b> has no tracks to analyze.",
+            html_escape(&playlist.name)
+        ));
     }
 
-    let mut response = "<b>🎵 Your Top Tracks</b>\n\n".to_string();
-    for (idx, track) in tracks.iter().enumerate().take(10) {
-        let artists = track.artists.join(", ");
+    let analyzed: Vec<(&Track, AudioFeatures)> = tracks
+        .iter()
+        .filter_map(|track| Some((track, *features.get(track.id.as_deref()?)?)))
+        .collect();
+
+    let tempos: Vec<f32> = analyzed.iter().map(|(_, f)| f.tempo).collect();
+    let energies: Vec<f32> = analyzed.iter().map(|(_, f)| f.energy).collect();
+    let (Some(tempo), Some(energy)) = (spread(&tempos), spread(&energies)) else {
+        return Err("Spotify returned no audio features for this playlist.".to_string());
+    };
+    let genres = share_breakdown(analyzed.iter().map(|(track, f)| {
+        backend()
+            .classify_genre(*f, &[], track.popularity)
+            .genre
+            .as_str()
+    }));
+    let moods = share_breakdown(
+        analyzed
+            .iter()
+            .map(|(_, f)| backend().classify_mood(*f).mood.as_str()),
+    );
+    let total_ms: u64 = tracks.iter().map(|track| track.duration_ms as u64).sum();
+
+    let locale = *state.locale.lock().await;
+    let percent = |share: f32| format_number(share as f64 * 100.0, 0, locale);
+    let mut response = format!(
+        "<b>🔬 Playlist Analysis: {}</b>\n<i>{} tracks · {}</i>\n\n\
+         <b>Tempo:</b> {} BPM ± {}\n\
+         <b>Energy:</b> {} ± {}\n\n\
+         <b>Energy curve</b> (start → end)\n<code>{}</code>\n",
+        spotify_link(Entity::Playlist, Some(&playlist.id), &playlist.name),
+        format_count(tracks.len() as u64, locale),
+        format_duration(std::time::Duration::from_millis(total_ms), locale),
+        format_number(tempo.mean as f64, 0, locale),
+        format_number(tempo.stddev as f64, 0, locale),
+        format_number(energy.mean as f64, 2, locale),
+        format_number(energy.stddev as f64, 2, locale),
+        sparkline(&energy_curve(&energies, CURVE_POINTS))
+    );
+
+    response.push_str("\n<b>🎼 Genres</b>\n");
+    for (genre, share) in genres.iter().take(MAX_GENRES) {
+        response.push_str(&format!("• {} — {}%\n", genre, percent(*share)));
+    }
+    response.push_str("\n<b>🎭 Moods</b>\n");
+    for (mood, share) in &moods {
+        let emoji = Mood::parse(mood).map_or("", |mood| mood.emoji());
+        response.push_str(&format!("• {} {} — {}%\n", emoji, mood, percent(*share)));
+    }
+
+    if analyzed.len() < tracks.len() {
         response.push_str(&format!(
-            "<b>{}</b>. {}\n<i>{}</i>\n\n",
-            idx + 1,
-            html_escape(&track.name),
-            html_escape(&artists)
+            "\n<i>{} of {} tracks have audio features.</i>",
+            analyzed.len(),
+            tracks.len()
+        ));
+    }
+    if playlist.total_tracks as usize > MAX_ANALYZED_TRACKS {
+        response.push_str(&format!(
+            "\n<i>Only the first {} tracks were analyzed.</i>",
+            MAX_ANALYZED_TRACKS
         ));
     }
 
     Ok(response)
 }
 
-async fn get_top_artists(state: &AppState) -> Result<String, String> {
+/// Tracks of a playlist (up to [`MAX_ANALYZED_TRACKS`]) with the audio
+/// features Spotify has for them
+async fn playlist_sound(
+    spotify: &AuthCodeSpotify,
+    playlist: &Playlist,
+) -> Result<(Vec<Track>, HashMap<String, AudioFeatures>), String> {
+    let playlist_id = convert::playlist_id(&playlist.id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    let tracks: Vec<Track> = collect_stream_n(
+        spotify.playlist_items(playlist_id, None, None),
+        MAX_ANALYZED_TRACKS,
+        convert::playlist_item_track,
+    )
+    .await
+    .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let ids: Vec<String> = tracks.iter().filter_map(|track| track.id.clone()).collect();
+    let features = fetch_audio_features(spotify, &ids)
+        .await
+        .map_err(|_| "Failed to fetch audio features. Please try again.".to_string())?;
+    Ok((tracks, features))
+}
+
+/// Tracks whose sound doesn't fit the rest of a playlist, with a button to
+/// remove each one
+async fn playlist_outliers(
+    state: &AppState,
+    playlist_name: &str,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    const MAX_SHOWN: usize = 5;
+
+    let playlist_name = playlist_name.trim();
+    if playlist_name.is_empty() {
+        return Err("Please provide a playlist name.".to_string());
+    }
+
     let guard = state.spotify.lock().await;
     let spotify = guard
         .as_ref()
         .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::ReadPlaylists]).await?;
 
-    let stream = spotify.current_user_top_artists(None);
-    let artists = collect_stream(stream, |artist| crate::models::spotify::Artist {
-        name: artist.name,
-        genres: artist.genres,
-    })
-    .await
-    .map_err(|_| "Failed to fetch top artists. Please try again.".to_string())?;
+    let playlist = find_playlist(spotify, playlist_name).await?;
+    let (tracks, features) = playlist_sound(spotify, &playlist).await?;
+    let analyzed: Vec<(&Track, AudioFeatures)> = tracks
+        .iter()
+        .filter_map(|track| Some((track, *features.get(track.id.as_deref()?)?)))
+        .collect();
+    if analyzed.len() < MIN_TRACKS_FOR_OUTLIERS {
+        return Err(format!(
+            "Outliers need at least {} tracks with audio features.",
+            MIN_TRACKS_FOR_OUTLIERS
+        ));
+    }
 
-    if artists.is_empty() {
-        return Ok(
-            "📭 No top artists found. Start following artists to see your favorites!".to_string(),
-        );
+    let sounds: Vec<AudioFeatures> = analyzed.iter().map(|(_, f)| *f).collect();
+    let outliers = find_outliers(&sounds);
+    let empty = InlineKeyboardMarkup::new(Vec::<Vec<InlineKeyboardButton>>::new());
+    if outliers.is_empty() {
+        return Ok((
+            format!(
+                "✨ <b>{}</b> sounds consistent: no track stands out.",
+                html_escape(&playlist.name)
+            ),
+            empty,
+        ));
     }
 
-    let mut response = "<b>🎤 Your Top Artists</b>\n\n".to_string();
-    for (idx, artist) in artists.iter().enumerate().take(10) {
-        let genres = if !artist.genres.is_empty() {
-            format!("\n<i>{}</i>", html_escape(&artist.genres.join(", ")))
-        } else {
-            String::new()
-        };
+    let mut response = format!(
+        "<b>🎯 Outliers in {}</b>\n<i>Tracks that sound unlike the rest of the playlist</i>\n\n",
+        html_escape(&playlist.name)
+    );
+    let mut rows = Vec::new();
+    for (idx, outlier) in outliers.iter().take(MAX_SHOWN).enumerate() {
+        let track = analyzed[outlier.index].0;
         response.push_str(&format!(
-            "<b>{}</b>. {}{}\n\n",
+            "<b>{}</b>. {} — <i>{}</i>\nSounds {} than the rest\n\n",
             idx + 1,
-            html_escape(&artist.name),
-            genres
+            spotify_link(Entity::Track, track.id.as_deref(), &track.name),
+            html_escape(&track.artists.join(", ")),
+            outlier.difference
         ));
+        if let Some(track_id) = &track.id {
+            rows.push(vec![InlineKeyboardButton::callback(
+                format!("🗑 Remove {}", track.name),
+                format!("outlier:{}:{}", playlist.id, track_id),
+            )]);
+        }
+    }
+    if outliers.len() > MAX_SHOWN {
+        response.push_str(&format!("…and {} more\n", outliers.len() - MAX_SHOWN));
     }
+    response.push_str("Nothing was removed yet.");
 
-    Ok(response)
+    Ok((response, InlineKeyboardMarkup::new(rows)))
 }
 
-async fn get_recently_played(state: &AppState) -> Result<String, String> {
+/// Callback of the outlier buttons: `<playlist_id>:<track_id>`
+async fn remove_outlier(
+    state: &AppState,
+    choice: &str,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+
+    let (playlist_id, track_id) = choice
+        .split_once(':')
+        .ok_or_else(|| "Invalid selection.".to_string())?;
+    let track =
+        convert::playable_track(track_id).ok_or_else(|| "Track ID not available.".to_string())?;
+
     let guard = state.spotify.lock().await;
     let spotify = guard
         .as_ref()
         .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes(spotify, FEATURES).await?;
 
-    let result = spotify
-        .current_user_recently_played(None, None)
+    let playlist = playlist_by_id(spotify, playlist_id).await?;
+    let items = playlist_tracks(spotify, &playlist.id, None)
         .await
-        .map_err(|_| "Failed to fetch recent tracks. Please try again.".to_string())?;
+        .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?;
+    let playlist_id = convert::playlist_id(&playlist.id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    let backup = take_backup(state, &playlist, &items, "before removing an outlier", None).await;
+    spotify
+        .playlist_remove_all_occurrences_of_items(playlist_id, [track.as_ref()], None)
+        .await
+        .map_err(|err| scope_failure(&err, &granted, FEATURES, "Failed to remove the track."))?;
+    let name = items
+        .iter()
+        .flatten()
+        .find(|item| item.id.as_deref() == Some(track_id))
+        .map_or_else(|| "The outlier".to_string(), |item| item.name.clone());
+    audit(
+        state,
+        "remove track",
+        format!("{} from {}", name, playlist.name),
+    )
+    .await;
 
-    if result.items.is_empty() {
-        return Ok("📭 No recently played tracks found.".to_string());
+    Ok((
+        format!(
+            "🗑 <b>Track Removed</b>\n\n<b>{}</b> is no longer in <b>{}</b>.\n\n{}",
+            html_escape(&name),
+            html_escape(&playlist.name),
+            undo_hint(&backup)
+        ),
+        undo_keyboard(&backup),
+    ))
+}
+
+pub(crate) async fn record_plays(state: &AppState, plays: &[Play]) {
+    let mut log = state.play_log.lock().await;
+    for play in plays {
+        if let Some(id) = play.track.canonical_id() {
+            log.record(id, play.played_at);
+        }
     }
+}
 
-    let mut response = "<b>⏱️ Recently Played</b>\n\n".to_string();
-    for (idx, item) in result.items.iter().enumerate().take(10) {
-        let track = &item.track;
-        let artists: Vec<String> = track.artists.iter().map(|a| a.name.clone()).collect();
+/// Entries of a playlist with when they were added, keyed by canonical ID,
+/// alongside every position's track for backups
+async fn playlist_entries(
+    spotify: &AuthCodeSpotify,
+    playlist_id: &str,
+) -> Result<
+    (
+        Vec<(String, chrono::DateTime<chrono::Utc>)>,
+        Vec<Option<Track>>,
+    ),
+    String,
+> {
+    let playlist_id = convert::playlist_id(playlist_id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    let items = collect_stream(
+        spotify.playlist_items(playlist_id, None, None),
+        convert::playlist_item_added,
+    )
+    .await
+    .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?;
+    let entries = items
+        .iter()
+        .filter_map(|(added_at, track)| {
+            Some((track.as_ref()?.canonical_id()?.to_string(), (*added_at)?))
+        })
+        .collect();
+    Ok((entries, items.into_iter().map(|(_, track)| track).collect()))
+}
+
+/// Tracks added weeks ago that haven't been played since, per the play log
+async fn get_staleness(state: &AppState, input: &str) -> Result<String, String> {
+    const SHOWN: usize = 20;
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::Listening];
+    const USAGE: &str =
+        "Usage: <code>/staleness name</code> or <code>/staleness name | weeks</code>";
+
+    let (playlist_name, weeks) = match input.split_once('|') {
+        Some((name, weeks)) => (
+            name.trim(),
+            parse_weeks(weeks).ok_or_else(|| USAGE.to_string())?,
+        ),
+        None => (input.trim(), DEFAULT_STALE_WEEKS),
+    };
+    if playlist_name.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, FEATURES).await?;
+
+    let playlist = find_playlist(spotify, playlist_name).await?;
+    let ((entries, _), recent) =
+        futures::try_join!(playlist_entries(spotify, &playlist.id), async {
+            spotify
+                .current_user_recently_played(Some(50), None)
+                .await
+                .map(|page| page.items.into_iter().map(Play::from).collect::<Vec<_>>())
+                .map_err(|_| "Failed to fetch your recent plays. Please try again.".to_string())
+        },)?;
+    record_plays(state, &recent).await;
+    let tracks = playlist_tracks(spotify, &playlist.id, None)
+        .await
+        .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?;
+    let by_id: HashMap<&str, &Track> = tracks
+        .iter()
+        .flatten()
+        .filter_map(|track| Some((track.canonical_id()?, track)))
+        .collect();
+
+    let now = chrono::Utc::now();
+    let log = state.play_log.lock().await;
+    let stale = stale_tracks(&entries, &log, now, weeks);
+    let locale = *state.locale.lock().await;
+    if stale.is_empty() {
+        return Ok(format!(
+            "🌱 Everything in <b>{}</b> was added or played in the last {} week(s).",
+            html_escape(&playlist.name),
+            weeks
+        ));
+    }
+
+    let mut response = format!(
+        "<b>🕸 Staleness: {}</b>\n<i>{} of {} tracks unplayed for {}+ week(s), longest first</i>\n\n",
+        html_escape(&playlist.name),
+        stale.len(),
+        entries.len(),
+        weeks
+    );
+    for (idx, entry) in stale.iter().take(SHOWN).enumerate() {
+        let id = entries[entry.index].0.as_str();
+        let (name, artists) = by_id.get(id).map_or_else(
+            || (id.to_string(), String::new()),
+            |track| (track.name.clone(), track.artists.join(", ")),
+        );
         response.push_str(&format!(
-            "<b>{}</b>. {}\n<i>{}</i>\n\n",
+            "<b>{}</b>. {} — <i>{}</i>\n└ added {} · {}\n",
             idx + 1,
-            html_escape(&track.name),
-            html_escape(&artists.join(", "))
+            html_escape(&name),
+            html_escape(&artists),
+            format_date(entry.added_at.date_naive(), locale),
+            entry
+                .last_played
+                .map_or("no play seen".to_string(), |played| {
+                    format!("last played {}", format_date(played.date_naive(), locale))
+                })
+        ));
+    }
+    if stale.len() > SHOWN {
+        response.push_str(&format!("…and {} more\n", stale.len() - SHOWN));
+    }
+    if !log.covers(now, weeks) {
+        response.push_str(&format!(
+            "\n<i>Plays are only known since {}, so some of these may have been played.</i>",
+            log.since().map_or("today".to_string(), |since| format_date(
+                since.date_naive(),
+                locale
+            ))
         ));
     }
+    response.push_str(&format!(
+        "\n\nDrop them automatically with <code>/rotate {} | {}</code>",
+        html_escape(&playlist.name),
+        weeks
+    ));
 
     Ok(response)
 }
 
-async fn search_track(state: &AppState, query: &str) -> Result<String, String> {
+/// Turn daily removal of stale tracks on or off for a playlist, or list the
+/// playlists it's on for
+async fn set_rotation(state: &AppState, input: &str) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+    let usage = format!(
+        "Usage: <code>/rotate name</code>, <code>/rotate name | weeks</code> (1–{}) \
+         or <code>/rotate name | off</code>",
+        MAX_STALE_WEEKS
+    );
+
+    let (playlist_name, setting) = match input.split_once('|') {
+        Some((name, setting)) => (name.trim(), setting.trim()),
+        None => (input.trim(), ""),
+    };
+    if playlist_name.is_empty() {
+        let rotations = state.rotations.lock().await;
+        if rotations.is_empty() {
+            return Ok(format!("No playlists rotate yet.\n\n{}", usage));
+        }
+        let mut response = "<b>🔄 Rotating Playlists</b>\n\n".to_string();
+        for rotation in rotations.iter() {
+            response.push_str(&format!(
+                "• {} — after {} week(s) unplayed\n",
+                html_escape(&rotation.playlist_name),
+                rotation.weeks
+            ));
+        }
+        return Ok(response);
+    }
+    let weeks = match setting {
+        "" => Some(DEFAULT_STALE_WEEKS),
+        "off" => None,
+        weeks => Some(parse_weeks(weeks).ok_or(usage)?),
+    };
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, FEATURES).await?;
+    let playlist = find_playlist_to_change(spotify, playlist_name).await?;
+
+    let mut rotations = state.rotations.lock().await;
+    rotations.retain(|rotation| rotation.playlist_id != playlist.id);
+    let Some(weeks) = weeks else {
+        return Ok(format!(
+            "🔄 <b>{}</b> no longer rotates.",
+            html_escape(&playlist.name)
+        ));
+    };
+    rotations.push(Rotation {
+        playlist_id: playlist.id.clone(),
+        playlist_name: playlist.name.clone(),
+        weeks,
+    });
+
+    Ok(format!(
+        "🔄 <b>Rotation On</b>\n\nOnce a day, tracks in <b>{}</b> unplayed for {} week(s) are removed, \
+         with a backup taken first.\n\n<i>Your plays are followed from now on, so nothing is removed \
+         before the bot has seen {} week(s) of listening.</i>",
+        html_escape(&playlist.name),
+        weeks,
+        weeks
+    ))
+}
+
+/// Remove the stale tracks of a rotating playlist. Returns how many were
+/// removed; none until the play log covers the rotation's weeks.
+pub(crate) async fn rotate_playlist(
+    state: &AppState,
+    spotify: &AuthCodeSpotify,
+    rotation: &Rotation,
+) -> Result<usize, String> {
+    let now = chrono::Utc::now();
+    if !state.play_log.lock().await.covers(now, rotation.weeks) {
+        return Ok(0);
+    }
+
+    let playlist = playlist_by_id(spotify, &rotation.playlist_id).await?;
+    let (entries, items) = playlist_entries(spotify, &playlist.id).await?;
+    let stale = stale_tracks(&entries, &*state.play_log.lock().await, now, rotation.weeks);
+    let ids: Vec<_> = stale
+        .iter()
+        .filter_map(|entry| convert::playable_track(&entries[entry.index].0))
+        .collect();
+    if ids.is_empty() {
+        return Ok(0);
+    }
+
+    take_backup(state, &playlist, &items, "before rotation", None).await;
+    let playlist_id = convert::playlist_id(&playlist.id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    let mut snapshot: Option<String> = None;
+    for batch in ids.chunks(100) {
+        let result = spotify
+            .playlist_remove_all_occurrences_of_items(
+                playlist_id.clone(),
+                batch.iter().map(PlayableId::as_ref),
+                snapshot.as_deref(),
+            )
+            .await
+            .map_err(|_| format!("Failed to remove tracks from {}.", playlist.name))?;
+        snapshot = Some(result.snapshot_id);
+    }
+    state.audit.lock().await.record(
+        Actor::Job,
+        "remove tracks",
+        format!(
+            "{} track(s) unplayed for {} week(s) from {}",
+            ids.len(),
+            rotation.weeks,
+            playlist.name
+        ),
+    );
+
+    Ok(ids.len())
+}
+
+/// Reorder a playlist so adjacent tracks are in compatible keys (Camelot
+/// wheel), keeping the current order where it already flows
+async fn harmonic_sort(
+    state: &AppState,
+    playlist_name: &str,
+    dry_run: bool,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    // Every move is one Spotify call, so very long playlists are refused
+    const MAX_TRACKS: usize = 200;
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+
+    let playlist_name = playlist_name.trim();
+    if playlist_name.is_empty() {
+        return Err("Please provide a playlist name.".to_string());
+    }
+
+    let guard = state.spotify.lock().await;
+    let spotify = guard
+        .as_ref()
+        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes_for(spotify, FEATURES, dry_run).await?;
+
+    let playlist = find_playlist_to_change(spotify, playlist_name).await?;
+    let playlist_id = convert::playlist_id(&playlist.id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    if playlist.total_tracks as usize > MAX_TRACKS {
+        return Err(format!(
+            "Harmonic sort supports playlists of up to {} tracks.",
+            MAX_TRACKS
+        ));
+    }
+
+    // Keep one entry per position so moves line up with the real playlist
+    let items: Vec<Option<Track>> = collect_stream(
+        spotify.playlist_items(playlist_id.clone(), None, None),
+        convert::playlist_item_track,
+    )
+    .await
+    .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?;
+    if items.len() < 3 {
+        return Ok((
+            "🎹 This playlist is too short to sort.".to_string(),
+            InlineKeyboardMarkup::default(),
+        ));
+    }
+
+    let ids: Vec<String> = items
+        .iter()
+        .flatten()
+        .filter_map(|track| track.id.clone())
+        .collect();
+    fetch_audio_features(spotify, &ids)
+        .await
+        .map_err(|_| "Failed to fetch audio features. Please try again.".to_string())?;
+    let known = cached_keys(&ids).await;
+    let keys: Vec<Option<Camelot>> = items
+        .iter()
+        .map(|track| known.get(track.as_ref()?.id.as_deref()?).copied())
+        .collect();
+    if keys.iter().all(Option::is_none) {
+        return Err("Spotify has no key information for this playlist.".to_string());
+    }
+
+    let order = harmonic_order(&keys, items.len());
+    let sorted: Vec<Option<Camelot>> = order.iter().map(|i| keys[*i]).collect();
+    let (before, after) = (clashes(&keys), clashes(&sorted));
+    if after >= before {
+        return Ok((
+            format!(
+                "🎹 <b>{}</b> already flows well: {} key clash(es), none to remove.",
+                html_escape(&playlist.name),
+                before
+            ),
+            InlineKeyboardMarkup::default(),
+        ));
+    }
+
+    let moves = reorder_moves(&order);
+    if dry_run {
+        let sorted_tracks: Vec<&Track> = order.iter().filter_map(|i| items[*i].as_ref()).collect();
+        return Ok((
+            preview(
+                "Harmonic Sort",
+                format!(
+                    "Would move {} of {} tracks in <b>{}</b>, key clashes {} → {}",
+                    moves.len(),
+                    items.len(),
+                    html_escape(&playlist.name),
+                    before,
+                    after
+                ),
+                &sorted_tracks,
+            ),
+            InlineKeyboardMarkup::default(),
+        ));
+    }
+    let backup = take_backup(
+        state,
+        &playlist,
+        &items,
+        "before harmonic sort",
+        Some(&order),
+    )
+    .await;
+    apply_moves(
+        spotify,
+        &playlist_id,
+        &moves,
+        &granted,
+        FEATURES,
+        "Failed to reorder the playlist. It may be partly sorted.",
+    )
+    .await?;
+    audit(
+        state,
+        "reorder playlist",
+        format!("{} by key, {} move(s)", playlist.name, moves.len()),
+    )
+    .await;
+
+    let opening: Vec<String> = sorted
+        .iter()
+        .take(8)
+        .map(|key| key.map_or("?".to_string(), |key| key.code()))
+        .collect();
+    let response = format!(
+        "🎹 <b>Harmonic Sort: {}</b>\n<i>{} of {} tracks moved</i>\n\n\
+         <b>Key clashes:</b> {} → {}\n\
+         <b>Opens:</b> {}{}\n\n\
+         {}",
+        html_escape(&playlist.name),
+        moves.len(),
+        items.len(),
+        before,
+        after,
+        opening.join(" → "),
+        if sorted.len() > opening.len() {
+            " → …"
+        } else {
+            ""
+        },
+        undo_hint(&backup)
+    );
+    Ok((response, undo_keyboard(&backup)))
+}
+
+/// Apply single-item moves from `reorder_moves`, chaining snapshots so each
+/// move sees the previous one
+async fn apply_moves(
+    spotify: &AuthCodeSpotify,
+    playlist_id: &PlaylistId<'static>,
+    moves: &[(usize, usize)],
+    granted: &HashSet<String>,
+    features: &[Feature],
+    failure: &str,
+) -> Result<(), String> {
+    let mut snapshot: Option<String> = None;
+    for (from, to) in moves {
+        let result = spotify
+            .playlist_reorder_items(
+                playlist_id.clone(),
+                Some(*from as i32),
+                Some(*to as i32),
+                Some(1),
+                snapshot.as_deref(),
+            )
+            .await
+            .map_err(|err| scope_failure(&err, granted, features, failure))?;
+        snapshot = Some(result.snapshot_id);
+    }
+    Ok(())
+}
+
+/// Adds tracks to playlists in batches of 100, the most one call takes.
+/// Unless told to keep duplicates, tracks the playlist already has and
+/// repeats are skipped.
+struct PlaylistWriter<'a> {
+    spotify: &'a AuthCodeSpotify,
+    granted: &'a HashSet<String>,
+    features: &'a [Feature],
+    dedupe: bool,
+}
+
+impl<'a> PlaylistWriter<'a> {
+    fn new(
+        spotify: &'a AuthCodeSpotify,
+        granted: &'a HashSet<String>,
+        features: &'a [Feature],
+    ) -> Self {
+        PlaylistWriter {
+            spotify,
+            granted,
+            features,
+            dedupe: true,
+        }
+    }
+
+    fn keep_duplicates(&self) -> Self {
+        PlaylistWriter {
+            dedupe: false,
+            ..*self
+        }
+    }
+
+    /// Canonical IDs of the playlist's tracks, to dedupe against
+    async fn contents(&self, playlist_id: &PlaylistId<'static>) -> Result<HashSet<String>, String> {
+        Ok(collect_stream(
+            self.spotify.playlist_items(playlist_id.clone(), None, None),
+            convert::playlist_item_track,
+        )
+        .await
+        .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?
+        .into_iter()
+        .flatten()
+        .filter_map(|track| track.canonical_id().map(str::to_string))
+        .collect())
+    }
+
+    /// Create a private playlist holding `ids`
+    async fn create(
+        &self,
+        name: &str,
+        description: &str,
+        ids: &[String],
+    ) -> Result<AddPlan, String> {
+        let playlist_id = self.create_empty(name, description).await?;
+        let plan = plan_add(ids, &HashSet::new(), self.dedupe, usize::MAX);
+        self.write(&playlist_id, &plan.ids).await?;
+        Ok(plan)
+    }
+
+    /// Create an empty private playlist
+    async fn create_empty(
+        &self,
+        name: &str,
+        description: &str,
+    ) -> Result<PlaylistId<'static>, String> {
+        let user = self
+            .spotify
+            .current_user()
+            .await
+            .map_err(|_| "Failed to fetch user info.".to_string())?;
+        let playlist = self
+            .spotify
+            .user_playlist_create(user.id, name, Some(false), Some(false), Some(description))
+            .await
+            .map_err(|err| {
+                scope_failure(
+                    &err,
+                    self.granted,
+                    self.features,
+                    "Failed to create playlist.",
+                )
+            })?;
+        Ok(playlist.id)
+    }
+
+    /// Append `ids` as they are; dedupe beforehand with [`plan_add`]
+    async fn write(&self, playlist_id: &PlaylistId<'_>, ids: &[String]) -> Result<(), String> {
+        let ids: Vec<_> = ids
+            .iter()
+            .filter_map(|id| convert::playable_track(id))
+            .collect();
+        for (idx, batch) in ids.chunks(100).enumerate() {
+            self.spotify
+                .playlist_add_items(
+                    playlist_id.as_ref(),
+                    batch.iter().map(PlayableId::as_ref),
+                    None,
+                )
+                .await
+                .map_err(|err| {
+                    scope_failure(
+                        &err,
+                        self.granted,
+                        self.features,
+                        if idx > 0 {
+                            "Failed to add tracks. Some were added before the error."
+                        } else {
+                            "Failed to add tracks."
+                        },
+                    )
+                })?;
+        }
+        Ok(())
+    }
+}
+
+fn skipped_note(skipped: usize) -> String {
+    match skipped {
+        0 => String::new(),
+        n => format!("\n<i>Skipped {} duplicate(s).</i>", n),
+    }
+}
+
+/// Dry run: list tracks that are unavailable in the user's market and offer
+/// a button to remove them
+async fn clean_playlist(
+    state: &AppState,
+    playlist_name: &str,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    let playlist_name = playlist_name.trim();
+    if playlist_name.is_empty() {
+        return Err("Please provide a playlist name.".to_string());
+    }
+
     let guard = state.spotify.lock().await;
     let spotify = guard
         .as_ref()
         .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(spotify, &[Feature::ReadPlaylists]).await?;
 
-    let query = query.trim();
-    if query.is_empty() {
-        return Err("Please provide a search query.".to_string());
-    }
-
-    // Search in whole Spotify database
-    let result = spotify
-        .search(
-            query,
-            SearchType::Track,
-            Some(Market::FromToken),
-            None,
-            Some(5),
-            None,
-        )
+    let playlist = find_playlist(spotify, playlist_name).await?;
+    let unplayable: Vec<Track> = playlist_tracks(spotify, &playlist.id, Some(market(state).await))
         .await
-        .map_err(|_| "Failed to search tracks. Please try again.".to_string())?;
-
-    let page = match result {
-        SearchResult::Tracks(page) => page,
-        _ => return Err("Failed to search tracks. Please try again.".to_string()),
-    };
+        .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?
+        .into_iter()
+        .flatten()
+        .filter(is_unavailable)
+        .collect();
 
-    if page.items.is_empty() {
-        return Ok(format!(
-            "📭 <b>Search Results for \"{}\"</b>\n\nNo tracks found.",
-            html_escape(query)
+    if unplayable.is_empty() {
+        return Ok((
+            format!(
+                "✨ <b>{}</b> is clean: every track is playable.",
+                html_escape(&playlist.name)
+            ),
+            InlineKeyboardMarkup::default(),
         ));
     }
 
     let mut response = format!(
-        "<b>📭 Search Results for \"{}\"</b>\n\n",
-        html_escape(query)
+        "<b>🧹 Unavailable in {}</b>\n<i>{} track(s) can't be played in your market</i>\n\n",
+        html_escape(&playlist.name),
+        unplayable.len()
     );
-    for (idx, track) in page.items.iter().enumerate() {
-        let artists: Vec<String> = track
-            .artists
-            .iter()
-            .map(|a| a.name.clone())
-            .collect::<Vec<_>>();
+    for (idx, track) in unplayable.iter().enumerate().take(20) {
         response.push_str(&format!(
-            "<b>{}</b>. {}\n<i>{}</i>\n\n",
+            "<b>{}</b>. {}\n<i>{}</i>\n",
             idx + 1,
             html_escape(&track.name),
-            html_escape(&artists.join(", "))
+            html_escape(&track.artists.join(", "))
         ));
     }
+    if unplayable.len() > 20 {
+        response.push_str(&format!("…and {} more\n", unplayable.len() - 20));
+    }
+    response.push_str("\nNothing was removed yet.");
 
-    Ok(response)
+    let keyboard = InlineKeyboardMarkup::new(vec![vec![InlineKeyboardButton::callback(
+        format!("🗑 Remove {} track(s)", unplayable.len()),
+        format!("clean:{}", playlist.id),
+    )]]);
+    Ok((response, keyboard))
 }
 
-async fn list_playlists(state: &AppState) -> Result<String, String> {
+async fn remove_unplayable(
+    state: &AppState,
+    playlist_id: &str,
+) -> Result<(String, InlineKeyboardMarkup), String> {
+    const FEATURES: &[Feature] = &[Feature::ReadPlaylists, Feature::ModifyPlaylists];
+
     let guard = state.spotify.lock().await;
     let spotify = guard
         .as_ref()
         .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes(spotify, FEATURES).await?;
 
-    let stream = spotify.current_user_playlists();
-    let playlists = collect_stream(stream, |p| p)
+    let playlist = playlist_by_id(spotify, playlist_id).await?;
+    // Check again: availability may have changed since the dry run
+    let items = playlist_tracks(spotify, playlist_id, Some(market(state).await))
         .await
-        .map_err(|_| "Failed to fetch playlists. Please try again.".to_string())?;
+        .map_err(|_| "Failed to fetch playlist tracks. Please try again.".to_string())?;
+    let ids: Vec<_> = items
+        .iter()
+        .flatten()
+        .filter(|track| is_unavailable(track))
+        .filter_map(|track| convert::playable_track(track.id.as_deref()?))
+        .collect();
+    if ids.is_empty() {
+        return Ok((
+            "✨ Nothing to remove: every track is playable.".to_string(),
+            InlineKeyboardMarkup::default(),
+        ));
+    }
 
-    if playlists.is_empty() {
-        return Ok("📭 <b>Your Playlists</b>\n\nNo playlists found. Create one with <code>/create_playlist</code>".to_string());
+    let backup = take_backup(state, &playlist, &items, "before cleaning", None).await;
+    let playlist_id = convert::playlist_id(playlist_id)
+        .ok_or_else(|| "Playlist ID not available.".to_string())?;
+    let count = ids.len();
+    // The endpoint takes at most 100 tracks per call
+    let mut snapshot: Option<String> = None;
+    for batch in ids.chunks(100) {
+        let result = spotify
+            .playlist_remove_all_occurrences_of_items(
+                playlist_id.clone(),
+                batch.iter().map(PlayableId::as_ref),
+                snapshot.as_deref(),
+            )
+            .await
+            .map_err(|err| scope_failure(&err, &granted, FEATURES, "Failed to remove tracks."))?;
+        snapshot = Some(result.snapshot_id);
     }
+    audit(
+        state,
+        "remove tracks",
+        format!("{} unavailable track(s) from {}", count, playlist.name),
+    )
+    .await;
 
-    let mut response = "<b>📋 Your Playlists</b>\n\n".to_string();
-    for (idx, playlist) in playlists.iter().enumerate().take(20) {
-        let track_count = playlist.tracks.total;
-        response.push_str(&format!(
-            "<b>{}</b>. {}\n<i>{} tracks</i>\n\n",
-            idx + 1,
+    Ok((
+        format!(
+            "🧹 <b>Playlist Cleaned</b>\n\nRemoved {} unavailable track(s) from <b>{}</b>.\n\n{}",
+            count,
             html_escape(&playlist.name),
-            track_count
-        ));
-    }
+            undo_hint(&backup)
+        ),
+        undo_keyboard(&backup),
+    ))
+}
 
-    Ok(response)
+/// Tracks of a playlist in order, `None` for episodes. With a market,
+/// availability and relinking are resolved for it.
+async fn playlist_tracks(
+    spotify: &AuthCodeSpotify,
+    playlist_id: &str,
+    market: Option<Market>,
+) -> Result<Vec<Option<Track>>, ClientError> {
+    let Some(playlist_id) = convert::playlist_id(playlist_id) else {
+        return Ok(Vec::new());
+    };
+    let stream = spotify.playlist_items(playlist_id, None, market);
+    collect_stream(stream, convert::playlist_item_track).await
 }
 
-async fn get_playlist(state: &AppState, playlist_name: &str) -> Result<String, String> {
+// Local files are never "unavailable", so they are left alone
+fn is_unavailable(track: &Track) -> bool {
+    !track.playable && track.id.is_some()
+}
+
+async fn sleep_timer(
+    bot: &Bot,
+    state: &AppState,
+    chat_id: ChatId,
+    input: &str,
+) -> Result<String, String> {
+    const FEATURES: &[Feature] = &[Feature::ControlPlayback];
+
+    let command = parse_sleep(input).ok_or_else(|| {
+        format!(
+            "Usage: <code>/sleep minutes [wind]</code> (1–{}), <code>/sleep off</code> \
+             or <code>/sleep</code> to see the timer",
+            MAX_MINUTES
+        )
+    })?;
+    let (minutes, wind_down) = match command {
+        SleepCommand::Status => {
+            let locale = *state.locale.lock().await;
+            return Ok(match state.sleep_timer.lock().await.as_ref() {
+                Some(timer) => format!(
+                    "😴 Playback pauses in <b>{}</b>.",
+                    format_duration(
+                        (timer.ends_at - chrono::Utc::now())
+                            .to_std()
+                            .unwrap_or_default(),
+                        locale
+                    )
+                ),
+                None => "No sleep timer is set.".to_string(),
+            });
+        }
+        SleepCommand::Cancel => {
+            return Ok(match state.sleep_timer.lock().await.take() {
+                Some(timer) => {
+                    timer.task.abort();
+                    "⏰ <b>Sleep Timer Cancelled</b>\n\nPlayback keeps going.".to_string()
+                }
+                None => "No sleep timer is set.".to_string(),
+            });
+        }
+        SleepCommand::Start { minutes, wind_down } => (minutes, wind_down),
+    };
+
     let guard = state.spotify.lock().await;
     let spotify = guard
         .as_ref()
         .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    let granted = require_scopes(spotify, FEATURES).await?;
 
-    if playlist_name.is_empty() {
-        return Err("Please provide a playlist name.".to_string());
-    }
+    let queued = if wind_down {
+        Some(queue_wind_down(state, spotify, &granted, minutes).await?)
+    } else {
+        None
+    };
+    drop(guard);
 
-    // Get user playlists
-    let stream = spotify.current_user_playlists();
-    let playlists = collect_stream(stream, |p| p)
-        .await
-        .map_err(|_| "Failed to fetch playlists. Please try again.".to_string())?;
+    let duration = std::time::Duration::from_secs(u64::from(minutes) * 60);
+    let task = tokio::spawn({
+        let bot = bot.clone();
+        let state = state.clone();
+        async move {
+            tokio::time::sleep(duration).await;
+            state.sleep_timer.lock().await.take();
 
-    // Find matching playlist
-    let playlist = playlists
-        .iter()
-        .find(|p| p.name.to_lowercase() == playlist_name.to_lowercase())
-        .ok_or_else(|| format!("Playlist \"{}\" not found.", html_escape(playlist_name)))?;
+            let paused = match state.spotify.lock().await.as_ref() {
+                Some(spotify) => spotify.pause_playback(None).await.map_err(|err| {
+                    error!("Sleep timer failed to pause playback in chat {chat_id}: {err:?}");
+                }),
+                None => Err(()),
+            };
+            if paused.is_ok() {
+                state
+                    .audit
+                    .lock()
+                    .await
+                    .record(Actor::Job, "pause playback", "sleep timer ended");
+            }
+            let message = match paused {
+                Ok(()) => "😴 <b>Good Night</b>\n\nPlayback paused by your sleep timer.",
+                Err(()) => "😴 Your sleep timer ended, but I couldn't pause playback.",
+            };
+            if let Err(err) = send_html(&bot, chat_id, message).await {
+                error!("Failed to send sleep timer message to chat {chat_id}: {err}");
+            }
+        }
+    });
+    let replaced = state.sleep_timer.lock().await.replace(SleepTimer {
+        ends_at: chrono::Utc::now() + chrono::Duration::minutes(i64::from(minutes)),
+        task: task.abort_handle(),
+    });
+    if let Some(previous) = replaced {
+        previous.task.abort();
+    }
 
-    let response = format!(
-        "<b>📋 {}</b>\n\n<b>Tracks:</b> {}\n\n",
-        html_escape(&playlist.name),
-        playlist.tracks.total
+    let locale = *state.locale.lock().await;
+    let mut response = format!(
+        "😴 <b>Sleep Timer Set</b>\n\nPlayback pauses in <b>{}</b>.",
+        format_duration(duration, locale)
     );
-
-    // Note: Showing playlist details without full track list due to API complexity
-    // Use /playlist_name command to view details
+    match queued {
+        Some(0) => response.push_str("\n\n<i>No calm tracks found to wind down with.</i>"),
+        Some(count) => response.push_str(&format!(
+            "\n\n🌙 Queued {} calm track(s), getting quieter towards the end.",
+            count
+        )),
+        None => {}
+    }
+    response.push_str("\n\nCancel with <code>/sleep off</code>.");
 
     Ok(response)
 }
 
-async fn create_playlist(state: &AppState, playlist_name: &str) -> Result<String, String> {
-    let guard = state.spotify.lock().await;
-    let spotify = guard
-        .as_ref()
-        .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+/// Queue calm tracks from the member's top and liked tracks to fill the
+/// timer. Returns how many were queued.
+async fn queue_wind_down(
+    state: &AppState,
+    spotify: &AuthCodeSpotify,
+    granted: &HashSet<String>,
+    minutes: u32,
+) -> Result<usize, String> {
+    const FEATURES: &[Feature] = &[Feature::ControlPlayback];
+    const MAX_LIKED: usize = 200;
 
-    if playlist_name.is_empty() {
-        return Err("Please provide a playlist name.".to_string());
-    }
+    let use_liked = scopes::missing_scopes(granted, &[Feature::ReadLibrary]).is_empty();
+    let market = market(state).await;
+    let (top, liked) = futures::try_join!(top_tracks(state, spotify, TopRange::Medium), async {
+        if !use_liked {
+            return Ok(Vec::new());
+        }
+        let stream = spotify.current_user_saved_tracks(Some(market));
+        collect_stream_n(stream, MAX_LIKED, |item| Track::from(item.track))
+            .await
+            .map_err(|_| "Failed to fetch your saved tracks. Please try again.".to_string())
+    },)?;
 
-    let user = spotify
-        .current_user()
+    let tracks: Vec<Track> = top.into_iter().chain(liked).collect();
+    let ids: Vec<String> = tracks.iter().filter_map(|track| track.id.clone()).collect();
+    let features = fetch_audio_features(spotify, &ids)
         .await
-        .map_err(|_| "Failed to fetch user info.".to_string())?;
+        .map_err(|_| "Failed to fetch audio features. Please try again.".to_string())?;
+    let candidates: Vec<WindDownCandidate> = tracks
+        .iter()
+        .filter_map(|track| {
+            let features = *features.get(track.id.as_deref()?)?;
+            Some(WindDownCandidate {
+                id: track.id.clone()?,
+                duration_ms: track.duration_ms,
+                mood: backend().classify_mood(features).mood,
+                energy: features.energy,
+            })
+        })
+        .collect();
 
-    spotify
-        .user_playlist_create(
-            user.id,
-            playlist_name,
-            Some(false),
-            Some(false),
-            Some("Created with Spotify Dashboard Bot"),
+    let picked = pick_wind_down(&candidates, minutes);
+    for id in &picked {
+        let Some(id) = convert::playable_track(id) else {
+            continue;
+        };
+        spotify.add_item_to_queue(id, None).await.map_err(|err| {
+            scope_failure(
+                &err,
+                granted,
+                FEATURES,
+                "Couldn't queue tracks. Start playing on a device first.",
+            )
+        })?;
+    }
+    if !picked.is_empty() {
+        audit(
+            state,
+            "queue tracks",
+            format!("{} calm track(s) for the sleep timer", picked.len()),
         )
-        .await
-        .map_err(|_| "Failed to create playlist. Please try again.".to_string())?;
+        .await;
+    }
 
-    Ok(format!(
-        "✅ <b>Playlist Created</b>\n\n\
-         <b>Name:</b> {}\n\n\
-         You can now add tracks to this playlist.",
-        html_escape(playlist_name)
-    ))
+    Ok(picked.len())
 }
 
-async fn add_to_playlist(
+/// Spotify takes covers as base64 JPEG of at most 256 KB. Telegram stores
+/// photos as JPEG already, so the largest size that fits is used as is.
+async fn set_cover(
+    bot: &Bot,
     state: &AppState,
-    song_name: &str,
+    msg: &Message,
     playlist_name: &str,
 ) -> Result<String, String> {
+    const MAX_ENCODED_BYTES: usize = 256 * 1024;
+
+    let playlist_name = playlist_name.trim();
+    let photo = msg
+        .reply_to_message()
+        .and_then(|reply| reply.photo())
+        .filter(|_| !playlist_name.is_empty())
+        .ok_or_else(|| {
+            "Reply to a photo with <code>/set_cover playlist_name</code>.".to_string()
+        })?;
+    // Sizes come smallest first
+    let size = photo
+        .iter()
+        .rev()
+        .find(|size| (size.file.size as usize).div_ceil(3) * 4 <= MAX_ENCODED_BYTES)
+        .ok_or_else(|| "That photo is too large to use as a cover.".to_string())?;
+
     let guard = state.spotify.lock().await;
     let spotify = guard
         .as_ref()
         .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
+    require_scopes(
+        spotify,
+        &[
+            Feature::ReadPlaylists,
+            Feature::ModifyPlaylists,
+            Feature::UploadImages,
+        ],
+    )
+    .await?;
+    let playlist = find_playlist_to_change(spotify, playlist_name).await?;
 
-    if song_name.is_empty() || playlist_name.is_empty() {
-        return Err("Please provide both song name and playlist name.".to_string());
+    let file = bot
+        .get_file(size.file.id.clone())
+        .await
+        .map_err(|_| "Failed to fetch the photo from Telegram.".to_string())?;
+    let mut jpeg = Vec::new();
+    bot.download_file(&file.path, &mut jpeg)
+        .await
+        .map_err(|_| "Failed to download the photo from Telegram.".to_string())?;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(jpeg);
+    if encoded.len() > MAX_ENCODED_BYTES {
+        return Err("That photo is too large to use as a cover.".to_string());
+    }
+
+    upload_playlist_cover(spotify, &playlist.id, encoded).await?;
+    audit(state, "change cover", playlist.name.clone()).await;
+
+    Ok(format!(
+        "🖼️ <b>Cover Updated</b>\n\n<b>{}</b> has a new cover. It may take a moment to show up in Spotify.",
+        html_escape(&playlist.name)
+    ))
+}
+
+// rspotify 0.12 has no endpoint for this, so call the Web API directly
+async fn upload_playlist_cover(
+    spotify: &AuthCodeSpotify,
+    playlist_id: &str,
+    encoded_jpeg: String,
+) -> Result<(), String> {
+    let access_token = match spotify.get_token().lock().await {
+        Ok(token) => token.as_ref().map(|token| token.access_token.clone()),
+        Err(_) => None,
     }
+    .ok_or_else(|| "Please authenticate first using <code>/login</code>".to_string())?;
 
-    // Search in user's saved tracks
-    let stream = spotify.current_user_saved_tracks(Some(Market::FromToken));
-    let saved_tracks = collect_stream(stream, |item| item.track)
+    let response = reqwest::Client::new()
+        .put(format!(
+            "https://api.spotify.com/v1/playlists/{}/images",
+            playlist_id
+        ))
+        .bearer_auth(access_token)
+        .header(reqwest::header::CONTENT_TYPE, "image/jpeg")
+        .body(encoded_jpeg)
+        .send()
         .await
-        .map_err(|_| "Failed to fetch your saved tracks.".to_string())?;
+        .map_err(|_| "Failed to upload the cover. Please try again.".to_string())?;
 
-    let query_lower = song_name.to_lowercase();
-    let track = saved_tracks
-        .iter()
-        .find(|t| t.name.to_lowercase().contains(&query_lower))
-        .ok_or_else(|| {
-            format!(
-                "Track \"{}\" not found in your library. Please save it first.",
-                html_escape(song_name)
-            )
-        })?;
+    match response.status() {
+        status if status.is_success() => Ok(()),
+        reqwest::StatusCode::FORBIDDEN => {
+            Err("Spotify only lets you change covers of playlists you own.".to_string())
+        }
+        _ => Err("Spotify rejected the cover. Please try a different photo.".to_string()),
+    }
+}
+
+/// Note a change made to the member's Spotify account for `/history_actions`
+async fn audit(state: &AppState, action: &'static str, detail: String) {
+    state
+        .audit
+        .lock()
+        .await
+        .record(Actor::Command, action, detail);
+}
+
+/// One of the member's playlists by ID, e.g. from a button
+async fn playlist_by_id(spotify: &AuthCodeSpotify, playlist_id: &str) -> Result<Playlist, String> {
+    find_in_stream(spotify.current_user_playlists(), Playlist::from, |p| {
+        p.id == playlist_id
+    })
+    .await
+    .map_err(|_| "Failed to fetch playlists. Please try again.".to_string())?
+    .ok_or_else(|| "That playlist no longer exists.".to_string())
+}
+
+/// The member's playlist called `name`, stopping at an exact match and
+/// otherwise settling for a close one, e.g. with a typo. Only for commands
+/// that just read the playlist; see [`find_playlist_to_change`].
+async fn find_playlist(spotify: &AuthCodeSpotify, name: &str) -> Result<Playlist, String> {
+    lookup_playlist(spotify, name, true).await
+}
 
-    // Get user playlists
+/// Like [`find_playlist`], but a close match is only suggested, so a typo
+/// can't reorder or rewrite a different playlist
+async fn find_playlist_to_change(
+    spotify: &AuthCodeSpotify,
+    name: &str,
+) -> Result<Playlist, String> {
+    lookup_playlist(spotify, name, false).await
+}
+
+async fn lookup_playlist(
+    spotify: &AuthCodeSpotify,
+    name: &str,
+    settle_for_close: bool,
+) -> Result<Playlist, String> {
+    let name_lower = name.to_lowercase();
+    let mut others = Vec::new();
     let stream = spotify.current_user_playlists();
-    let playlists = collect_stream(stream, |p| p)
+    let exact = find_in_stream(stream, Playlist::from, |p| {
+        let found = p.name.to_lowercase() == name_lower;
+        if !found {
+            others.push(p.clone());
+        }
+        found
+    })
+    .await
+    .map_err(|_| "Failed to fetch playlists. Please try again.".to_string())?;
+    if let Some(playlist) = exact {
+        return Ok(playlist);
+    }
+
+    let names: Vec<&str> = others.iter().map(|p| p.name.as_str()).collect();
+    match fuzzy_pick(name, &names) {
+        FuzzyMatch::Found(idx) if settle_for_close => Ok(others[idx].clone()),
+        FuzzyMatch::Found(idx) => Err(format!(
+            "Playlist \"{}\" not found. Did you mean <b>{}</b>? \
+             Use the exact name to change a playlist.",
+            html_escape(name),
+            html_escape(names[idx])
+        )),
+        FuzzyMatch::Suggestions(suggestions) if suggestions.is_empty() => {
+            Err(format!("Playlist \"{}\" not found.", html_escape(name)))
+        }
+        FuzzyMatch::Suggestions(suggestions) => Err(format!(
+            "Playlist \"{}\" not found. Did you mean {}?",
+            html_escape(name),
+            suggestions
+                .iter()
+                .map(|idx| format!("<b>{}</b>", html_escape(names[*idx])))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Record label of each album, from the cache or Spotify. Albums without
+/// a label are left out.
+async fn fetch_album_labels(
+    spotify: &AuthCodeSpotify,
+    ids: &[String],
+) -> Result<HashMap<String, String>, ClientError> {
+    let mut found = HashMap::new();
+    let mut missing = Vec::new();
+    {
+        let cache = ALBUM_LABELS.lock().await;
+        for id in ids {
+            match cache.get(id) {
+                Some(label) => {
+                    if let Some(label) = label {
+                        found.insert(id.clone(), label.clone());
+                    }
+                }
+                None => missing.push(id.clone()),
+            }
+        }
+    }
+    missing.sort();
+    missing.dedup();
+
+    // The endpoint takes at most 20 albums per call
+    let batches: Vec<Vec<_>> = missing
+        .chunks(20)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .filter_map(|id| convert::album_id(id))
+                .collect()
+        })
+        .collect();
+    let pages = fetch_all(batches, SPOTIFY_CONCURRENCY, |batch| async move {
+        spotify.albums(batch, None).await
+    })
+    .await?;
+    let fetched: Vec<(String, Option<String>)> = pages
+        .into_iter()
+        .flatten()
+        .map(|album| {
+            let label = album.label.filter(|label| !label.trim().is_empty());
+            (album.id.id().to_string(), label)
+        })
+        .collect();
+
+    let mut cache = ALBUM_LABELS.lock().await;
+    for (id, label) in fetched {
+        if let Some(label) = &label {
+            found.insert(id.clone(), label.clone());
+        }
+        cache.insert(id, label);
+    }
+    Ok(found)
+}
+
+/// The user's top 50 tracks over `range`. Commands and jobs asking at the
+/// same time share one Spotify call.
+async fn top_tracks(
+    state: &AppState,
+    spotify: &AuthCodeSpotify,
+    range: TopRange,
+) -> Result<Vec<Track>, String> {
+    let spotify = spotify.clone();
+    state
+        .top_tracks
+        .run(range, || async move {
+            spotify
+                .current_user_top_tracks_manual(Some(convert::time_range(range)), Some(50), None)
+                .await
+                .map(|page| page.items.into_iter().map(Track::from).collect())
+                .map_err(|_| "Failed to fetch your top tracks. Please try again.".to_string())
+        })
         .await
-        .map_err(|_| "Failed to fetch playlists.".to_string())?;
+}
 
-    // Find matching playlist
-    let playlist = playlists
-        .iter()
-        .find(|p| p.name.to_lowercase() == playlist_name.to_lowercase())
-        .ok_or_else(|| format!("Playlist \"{}\" not found.", html_escape(playlist_name)))?;
+/// The user's top 50 artists over `range`, shared like [`top_tracks`]
+async fn top_artists(
+    state: &AppState,
+    spotify: &AuthCodeSpotify,
+    range: TopRange,
+) -> Result<Vec<Artist>, String> {
+    let spotify = spotify.clone();
+    state
+        .top_artists
+        .run(range, || async move {
+            spotify
+                .current_user_top_artists_manual(Some(convert::time_range(range)), Some(50), None)
+                .await
+                .map(|page| page.items.into_iter().map(Artist::from).collect())
+                .map_err(|_| "Failed to fetch your top artists. Please try again.".to_string())
+        })
+        .await
+}
 
-    // Add track to playlist
-    use rspotify::model::PlayableId;
-    if let Some(track_id) = &track.id {
-        let playable_id = PlayableId::Track(track_id.clone());
-        spotify
-            .playlist_add_items(playlist.id.clone(), vec![playable_id], None)
-            .await
-            .map_err(|_| "Failed to add track to playlist.".to_string())?;
+/// Camelot keys of tracks whose audio features were fetched before; tracks
+/// without a known key are left out
+async fn cached_keys(ids: &[String]) -> HashMap<String, Camelot> {
+    let cache = AUDIO_FEATURES.lock().await;
+    ids.iter()
+        .filter_map(|id| Some((id.clone(), cache.get(id)?.1?)))
+        .collect()
+}
+
+/// Audio features for `ids`, from the cache where possible and otherwise in
+/// batches of 100 (the endpoint's limit)
+async fn fetch_audio_features(
+    spotify: &AuthCodeSpotify,
+    ids: &[String],
+) -> Result<HashMap<String, AudioFeatures>, ClientError> {
+    let mut found = HashMap::new();
+    let mut missing = Vec::new();
+    {
+        let cache = AUDIO_FEATURES.lock().await;
+        for id in ids {
+            match cache.get(id) {
+                Some((features, _)) => {
+                    found.insert(id.clone(), *features);
+                }
+                None => missing.push(id.clone()),
+            }
+        }
+    }
+    missing.sort();
+    missing.dedup();
+
+    let batches: Vec<Vec<_>> = missing
+        .chunks(100)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .filter_map(|id| convert::track_id(id))
+                .collect()
+        })
+        .collect();
+    let pages = fetch_all(batches, SPOTIFY_CONCURRENCY, |batch| async move {
+        spotify.tracks_features(batch).await
+    })
+    .await?;
+    let fetched: Vec<_> = pages
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|raw| {
+            let (id, features, key) = convert::audio_features(raw);
+            (id, (features, key))
+        })
+        .collect();
+
+    let mut cache = AUDIO_FEATURES.lock().await;
+    for (id, (features, key)) in fetched {
+        found.insert(id.clone(), features);
+        cache.insert(id, (features, key));
+    }
+    Ok(found)
+}
+
+/// Features a command needs beyond what `/login` grants, for hiding it from
+/// `/help` when one of them is turned off
+fn command_features(command: &str) -> &'static [Feature] {
+    use Feature::*;
+    match command {
+        "skips" => &[CurrentlyPlaying],
+        "like" | "unlike" => &[ModifyLibrary, CurrentlyPlaying],
+        "sleep" => &[ControlPlayback],
+        "podcasts" => &[ReadLibrary, PlaybackPosition],
+        "albums" | "shows" | "new_in_library" => &[ReadLibrary],
+        "blend" => &[ReadLibrary, ModifyPlaylists],
+        "playlists" | "playlist" | "backup_playlist" | "analyze_playlist" | "playlist_outliers"
+        | "staleness" | "clean_playlist" => &[ReadPlaylists],
+        "create_playlist" | "run_playlist" | "party_playlist" => &[ModifyPlaylists],
+        "archive" | "add_to_playlist" | "fill_playlist" | "restore_playlist" | "smart_shuffle"
+        | "harmonic_sort" | "rotate" => &[ReadPlaylists, ModifyPlaylists],
+        "set_cover" => &[ReadPlaylists, ModifyPlaylists, UploadImages],
+        _ => &[],
+    }
+}
+
+/// Check the feature isn't turned off and the chat's token covers `features`
+/// before calling Spotify. Returns the granted scopes, or a message saying
+/// what is off or with a link to grant the missing scopes.
+async fn require_scopes(
+    spotify: &AuthCodeSpotify,
+    features: &[Feature],
+) -> Result<HashSet<String>, String> {
+    if let Some(blocked) = flags::blocked(features) {
+        return Err(match blocked {
+            Blocked::ReadOnly => "🔒 This bot runs in read-only mode and doesn't change \
+                                  anything on Spotify.\n\n<code>read_only</code>"
+                .to_string(),
+            Blocked::Disabled(feature) => format!(
+                "🚫 This is turned off on this bot.\n\n<code>feature_disabled: {}</code>",
+                feature.name()
+            ),
+        });
+    }
+    let granted = granted_scopes(spotify).await;
+    if scopes::missing_scopes(&granted, features).is_empty() {
+        Ok(granted)
     } else {
-        return Err("Track ID not available.".to_string());
+        Err(reauth_message(&granted, features))
     }
+}
 
-    Ok(format!(
-        "✅ <b>Track Added</b>\n\n\
-         <b>Song:</b> {}\n\
-         <b>Playlist:</b> {}\n\n\
-         Track successfully added to your playlist!",
-        html_escape(&track.name),
-        html_escape(&playlist.name)
-    ))
+/// [`require_scopes`] for a command that can be previewed: a dry run only
+/// reads, so it doesn't need the features that change the account
+async fn require_scopes_for(
+    spotify: &AuthCodeSpotify,
+    features: &[Feature],
+    dry_run: bool,
+) -> Result<HashSet<String>, String> {
+    if dry_run {
+        require_scopes(spotify, &flags::without_writes(features)).await
+    } else {
+        require_scopes(spotify, features).await
+    }
+}
+
+async fn granted_scopes(spotify: &AuthCodeSpotify) -> HashSet<String> {
+    match spotify.get_token().lock().await {
+        Ok(token) => token
+            .as_ref()
+            .map(|token| token.scopes.clone())
+            .unwrap_or_default(),
+        Err(_) => HashSet::new(),
+    }
+}
+
+// Spotify may still refuse a call the token looked fine for, e.g. after the
+// user removed access from their account page
+fn scope_failure(
+    err: &ClientError,
+    granted: &HashSet<String>,
+    features: &[Feature],
+    fallback: &str,
+) -> String {
+    if scopes::is_insufficient_scope(err) {
+        reauth_message(granted, features)
+    } else {
+        fallback.to_string()
+    }
+}
+
+fn reauth_message(granted: &HashSet<String>, features: &[Feature]) -> String {
+    let missing = scopes::missing_scopes(granted, features);
+    let oauth = spotify_oauth_with_scopes(scopes::reauth_scopes(granted, features));
+    let missing = if missing.is_empty() {
+        String::new()
+    } else {
+        format!(" (<code>{}</code>)", missing.join("</code>, <code>"))
+    };
+
+    match AuthCodeSpotify::new(spotify_credentials(), oauth).get_authorize_url(false) {
+        Ok(url) => format!(
+            "🔐 This command needs extra Spotify permissions{}.\n\n\
+             <a href=\"{}\">Grant access</a> and try again.",
+            missing,
+            html_escape(&url)
+        ),
+        Err(err) => {
+            error!("Failed to build re-auth URL: {err}");
+            format!(
+                "🔐 This command needs extra Spotify permissions{}. \
+                 Please <code>/login</code> again.",
+                missing
+            )
+        }
+    }
+}
+
+// "💿 Album · 3:27 · 🅴", with the album linked to its cover art when known
+fn track_details(track: &Track) -> String {
+    let album = match &track.album_art {
+        Some(url) => format!(
+            "<a href=\"{}\">{}</a>",
+            html_escape(url),
+            html_escape(&track.album)
+        ),
+        None => html_escape(&track.album),
+    };
+    let year = track
+        .release_date
+        .as_deref()
+        .and_then(release_year)
+        .map(|year| format!(" ({})", year))
+        .unwrap_or_default();
+    let explicit = if track.explicit { " · 🅴" } else { "" };
+    format!(
+        "💿 {}{} · {}{}",
+        album,
+        year,
+        format_track_length(track.duration_ms),
+        explicit
+    )
+}
+
+/// Send an HTML reply, as several messages if it is over Telegram's limit
+pub(crate) async fn send_html(
+    bot: &Bot,
+    chat_id: ChatId,
+    text: &str,
+) -> Result<(), teloxide::RequestError> {
+    send_html_with_keyboard(bot, chat_id, text, InlineKeyboardMarkup::default()).await
+}
+
+/// Like `send_html`, with the keyboard attached to the last message
+pub(crate) async fn send_html_with_keyboard(
+    bot: &Bot,
+    chat_id: ChatId,
+    text: &str,
+    keyboard: InlineKeyboardMarkup,
+) -> Result<(), teloxide::RequestError> {
+    let chunks = split_message(text, TELEGRAM_LIMIT);
+    let last = chunks.len().saturating_sub(1);
+    for (idx, chunk) in chunks.into_iter().enumerate() {
+        let request = bot
+            .send_message(chat_id, chunk)
+            .parse_mode(teloxide::types::ParseMode::Html);
+        if idx == last && !keyboard.inline_keyboard.is_empty() {
+            request.reply_markup(keyboard.clone()).await?;
+        } else {
+            request.await?;
+        }
+    }
+    Ok(())
 }
 
 // Helper function to escape HTML special characters
-fn html_escape(text: &str) -> String {
+pub(crate) fn html_escape(text: &str) -> String {
     text.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
         .replace('"', "&quot;")
         .replace('\'', "&#39;")
 }
+
+/// Escaped `text`, linked to the entity on open.spotify.com when it has an
+/// id (local files don't)
+pub(crate) fn spotify_link(entity: Entity, id: Option<&str>, text: &str) -> String {
+    match id {
+        Some(id) => format!(
+            "<a href=\"{}\">{}</a>",
+            web_url(entity, id),
+            html_escape(text)
+        ),
+        None => html_escape(text),
+    }
+}
@@ -0,0 +1,380 @@
+use std::time::Duration;
+
+use rspotify::clients::OAuthClient;
+use rspotify::model::AdditionalType;
+use teloxide::prelude::*;
+use tracing::error;
+
+use crate::models::convert;
+use crate::models::spotify::Play;
+use crate::stats::skips::PlaybackSample;
+use crate::utils::audit::Actor;
+use crate::utils::links::Entity;
+use crate::utils::{jobs, webhooks};
+
+use super::handlers::{
+    all_states, archive_week, fetch_recent_releases, html_escape, record_plays, rotate_playlist,
+    send_html, spotify_link,
+};
+
+// Recently-played only reaches back 50 plays, so poll often enough to see every day
+const STREAK_CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const RELEASE_CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+// Checked hourly so a week is archived soon after Monday starts
+const ARCHIVE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const ROTATION_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+// Skip detection needs to see most track changes; see skips::END_MARGIN_MS
+const PLAYBACK_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Periodically refresh streaks for chats that opted in and congratulate them at milestones
+pub async fn run_streak_notifier(bot: Bot) {
+    jobs::run_every("streaks", STREAK_CHECK_INTERVAL, || {
+        check_streaks(bot.clone())
+    })
+    .await
+}
+
+async fn check_streaks(bot: Bot) -> Result<(), String> {
+    let mut failed = 0;
+    for ((chat_id, _), state) in all_states().await {
+        if !state.streak.lock().await.notify {
+            continue;
+        }
+
+        let guard = state.spotify.lock().await;
+        let Some(spotify) = guard.as_ref() else {
+            continue;
+        };
+
+        let plays: Vec<Play> = match spotify.current_user_recently_played(Some(50), None).await {
+            Ok(result) => result.items.into_iter().map(Play::from).collect(),
+            Err(err) => {
+                error!("Streak check failed for chat {chat_id}: {err:?}");
+                failed += 1;
+                continue;
+            }
+        };
+        drop(guard);
+        record_plays(&state, &plays).await;
+
+        let milestone = {
+            let mut tracker = state.streak.lock().await;
+            tracker.record(plays.iter().map(|play| &play.played_at));
+            tracker.take_milestone(chrono::Utc::now().date_naive())
+        };
+
+        if let Some(days) = milestone {
+            let message = format!(
+                "🎉 <b>{} Day Streak!</b>\n\n\
+                     You've listened to music {} days in a row. Keep it going!",
+                days, days
+            );
+            if let Err(err) = bot
+                .send_message(ChatId(chat_id), message)
+                .parse_mode(teloxide::types::ParseMode::Html)
+                .await
+            {
+                error!("Failed to send streak milestone to chat {chat_id}: {err}");
+            }
+        }
+    }
+
+    failed_chats(failed)
+}
+
+/// Periodically check followed artists for chats with the release radar enabled
+pub async fn run_release_notifier(bot: Bot) {
+    jobs::run_every("releases", RELEASE_CHECK_INTERVAL, || {
+        check_releases(bot.clone())
+    })
+    .await
+}
+
+async fn check_releases(bot: Bot) -> Result<(), String> {
+    let mut failed = 0;
+    for ((chat_id, _), state) in all_states().await {
+        let notify = state.releases.lock().await.notify;
+        let has_webhook = state.webhook.lock().await.is_some();
+        if !notify && !has_webhook {
+            continue;
+        }
+
+        let guard = state.spotify.lock().await;
+        let Some(spotify) = guard.as_ref() else {
+            continue;
+        };
+
+        let releases = match fetch_recent_releases(spotify).await {
+            Ok(releases) => releases,
+            Err(err) => {
+                error!("Release check failed for chat {chat_id}: {err:?}");
+                failed += 1;
+                continue;
+            }
+        };
+        drop(guard);
+
+        let new_releases = state.releases.lock().await.take_unseen(&releases);
+        if new_releases.is_empty() {
+            continue;
+        }
+        if has_webhook {
+            let releases: Vec<_> = new_releases
+                .iter()
+                .map(|release| {
+                    serde_json::json!({
+                        "id": release.id,
+                        "name": release.name,
+                        "artist": release.artist,
+                        "album_type": release.album_type,
+                        "release_date": release.release_date,
+                    })
+                })
+                .collect();
+            webhooks::send(
+                state.webhook.clone(),
+                "new_release",
+                serde_json::json!({ "releases": releases }),
+            );
+        }
+        if !notify {
+            continue;
+        }
+
+        let mut message = "📡 <b>New Release Alert</b>\n\n".to_string();
+        for release in &new_releases {
+            message.push_str(&format!(
+                "• {} — <i>{}</i> ({})\n",
+                spotify_link(Entity::Album, Some(&release.id), &release.name),
+                html_escape(&release.artist),
+                html_escape(&release.album_type)
+            ));
+        }
+        if let Err(err) = send_html(&bot, ChatId(chat_id), &message).await {
+            error!("Failed to send release alert to chat {chat_id}: {err}");
+        }
+    }
+
+    failed_chats(failed)
+}
+
+/// Copy Discover Weekly and Release Radar once a week for members who opted in
+pub async fn run_weekly_archiver(bot: Bot) {
+    jobs::run_every("archive", ARCHIVE_CHECK_INTERVAL, || {
+        archive_playlists(bot.clone())
+    })
+    .await
+}
+
+async fn archive_playlists(bot: Bot) -> Result<(), String> {
+    let today = chrono::Utc::now().date_naive();
+    let mut failed = 0;
+    for ((chat_id, _), state) in all_states().await {
+        let Some(week) = state.archive.lock().await.due(today) else {
+            continue;
+        };
+
+        let guard = state.spotify.lock().await;
+        let Some(spotify) = guard.as_ref() else {
+            continue;
+        };
+
+        let archived = match archive_week(spotify, week).await {
+            Ok(archived) => archived,
+            Err(err) => {
+                error!("Weekly archive failed for chat {chat_id}: {err}");
+                failed += 1;
+                continue;
+            }
+        };
+        drop(guard);
+        state.archive.lock().await.mark_done(week);
+        for (name, count) in &archived {
+            state.audit.lock().await.record(
+                Actor::Job,
+                "create playlist",
+                format!("{} with {} track(s)", name, count),
+            );
+        }
+
+        if archived.is_empty() {
+            continue;
+        }
+        let mut message = "🗄️ <b>Weekly Archive</b>\n\n".to_string();
+        for (name, count) in &archived {
+            message.push_str(&format!("• {} ({} tracks)\n", html_escape(name), count));
+        }
+        if let Err(err) = bot
+            .send_message(ChatId(chat_id), message)
+            .parse_mode(teloxide::types::ParseMode::Html)
+            .await
+        {
+            error!("Failed to send archive summary to chat {chat_id}: {err}");
+        }
+    }
+
+    failed_chats(failed)
+}
+
+/// Drop tracks that sat unplayed from playlists members set to rotate
+pub async fn run_playlist_rotation(bot: Bot) {
+    jobs::run_every("rotation", ROTATION_INTERVAL, || {
+        rotate_playlists(bot.clone())
+    })
+    .await
+}
+
+async fn rotate_playlists(bot: Bot) -> Result<(), String> {
+    let mut failed = 0;
+    for ((chat_id, _), state) in all_states().await {
+        let rotations = state.rotations.lock().await.clone();
+        if rotations.is_empty() {
+            continue;
+        }
+
+        let guard = state.spotify.lock().await;
+        let Some(spotify) = guard.as_ref() else {
+            continue;
+        };
+
+        let mut rotated = Vec::new();
+        for rotation in &rotations {
+            match rotate_playlist(&state, spotify, rotation).await {
+                Ok(0) => {}
+                Ok(count) => rotated.push((rotation, count)),
+                Err(err) => {
+                    error!("Rotation failed for chat {chat_id}: {err}");
+                    failed += 1;
+                }
+            }
+        }
+        drop(guard);
+
+        for (rotation, count) in rotated {
+            let message = format!(
+                "🔄 <b>Playlist Rotated</b>\n\nRemoved {} track(s) unplayed for {}+ week(s) from <b>{}</b>.\n\n\
+                 <i>Undo with <code>/restore_playlist {} | 1</code></i>",
+                count,
+                rotation.weeks,
+                html_escape(&rotation.playlist_name),
+                html_escape(&rotation.playlist_name)
+            );
+            if let Err(err) = send_html(&bot, ChatId(chat_id), &message).await {
+                error!("Failed to send rotation summary to chat {chat_id}: {err}");
+            }
+        }
+    }
+
+    failed_chats(failed)
+}
+
+/// Sample what opted-in members are playing so skips can be detected,
+/// plays sent to webhooks and now playing published over MQTT
+pub async fn run_playback_poller() {
+    jobs::run_every("playback", PLAYBACK_POLL_INTERVAL, poll_playback).await
+}
+
+async fn poll_playback() -> Result<(), String> {
+    let mut failed = 0;
+    #[cfg(feature = "mqtt")]
+    let mqtt_enabled = crate::utils::mqtt::enabled();
+    #[cfg(not(feature = "mqtt"))]
+    let mqtt_enabled = false;
+
+    for ((chat_id, _user_id), state) in all_states().await {
+        let tracking_skips = state.skips.lock().await.enabled;
+        let publish_mqtt = mqtt_enabled && *state.mqtt.lock().await;
+        let has_webhook = state.webhook.lock().await.is_some();
+        // Rotation needs to know what gets played
+        let rotating = !state.rotations.lock().await.is_empty();
+        if !tracking_skips && !has_webhook && !publish_mqtt && !rotating {
+            continue;
+        }
+
+        let guard = state.spotify.lock().await;
+        let Some(spotify) = guard.as_ref() else {
+            continue;
+        };
+
+        let playing = match spotify
+            .current_playing(None, None::<&[AdditionalType]>)
+            .await
+        {
+            Ok(playing) => playing,
+            Err(err) => {
+                error!("Playback poll failed for chat {chat_id}: {err:?}");
+                failed += 1;
+                continue;
+            }
+        };
+        drop(guard);
+
+        #[cfg(feature = "mqtt")]
+        if publish_mqtt {
+            let is_playing = playing.as_ref().is_some_and(|playing| playing.is_playing);
+            let track = playing
+                .as_ref()
+                .and_then(|playing| playing.item.clone())
+                .and_then(convert::playable_item_track);
+            crate::utils::mqtt::publish_now_playing(_user_id, is_playing, track.as_ref()).await;
+        }
+
+        let playing = playing
+            .filter(|playing| playing.is_playing)
+            .and_then(|playing| {
+                let progress = playing.progress?;
+                let track = convert::playable_item_track(playing.item?)?;
+                Some((track, progress))
+            });
+
+        let now_playing = playing
+            .as_ref()
+            .and_then(|(track, _)| Some((track, track.canonical_id()?)));
+        if let Some((_, id)) = now_playing {
+            state.play_log.lock().await.record(id, chrono::Utc::now());
+        }
+        if let (true, Some((track, id))) = (has_webhook, now_playing) {
+            let is_new = state
+                .webhook
+                .lock()
+                .await
+                .as_mut()
+                .is_some_and(|hook| hook.observe_track(id));
+            if is_new {
+                webhooks::send(
+                    state.webhook.clone(),
+                    "play",
+                    serde_json::json!({
+                        "id": id,
+                        "name": track.name,
+                        "artists": track.artists,
+                        "album": track.album,
+                        "duration_ms": track.duration_ms,
+                    }),
+                );
+            }
+        }
+
+        if tracking_skips {
+            let sample = playing.and_then(|(track, progress)| {
+                Some(PlaybackSample {
+                    track_id: track.id?,
+                    name: track.name,
+                    progress_ms: progress.num_milliseconds().max(0) as u32,
+                    duration_ms: track.duration_ms,
+                })
+            });
+            state.skips.lock().await.observe(sample);
+        }
+    }
+
+    failed_chats(failed)
+}
+
+fn failed_chats(failed: usize) -> Result<(), String> {
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(format!("{failed} chat(s) could not be checked"))
+    }
+}
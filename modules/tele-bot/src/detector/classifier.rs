@@ -0,0 +1,107 @@
+//! Swappable detector backends.
+//!
+//! Callers go through [`backend`] instead of calling the rule-based
+//! detectors directly, so another implementation can be selected with
+//! `DETECTOR_BACKEND` without touching them. Only `rules` (the default)
+//! ships with the bot.
+
+use tracing::warn;
+
+use super::genre::{detect_genre, AudioFeatures, GenreDetection};
+use super::mood::{detect_mood, MoodDetection};
+
+lazy_static::lazy_static! {
+    static ref BACKEND: Box<dyn Classifier> = {
+        let name = std::env::var("DETECTOR_BACKEND").unwrap_or_default();
+        select(&name).unwrap_or_else(|| {
+            warn!("Unknown DETECTOR_BACKEND {:?}, using the rule-based detectors", name);
+            Box::new(RuleBased)
+        })
+    };
+}
+
+pub trait GenreClassifier: Send + Sync {
+    fn classify_genre(
+        &self,
+        features: AudioFeatures,
+        artist_genres: &[String],
+        popularity: u32,
+    ) -> GenreDetection;
+}
+
+pub trait MoodClassifier: Send + Sync {
+    fn classify_mood(&self, features: AudioFeatures) -> MoodDetection;
+}
+
+/// A backend classifies both genre and mood
+pub trait Classifier: GenreClassifier + MoodClassifier {}
+
+impl<T: GenreClassifier + MoodClassifier> Classifier for T {}
+
+/// The transparent, rule-based detectors in `genre` and `mood`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RuleBased;
+
+impl GenreClassifier for RuleBased {
+    fn classify_genre(
+        &self,
+        features: AudioFeatures,
+        artist_genres: &[String],
+        popularity: u32,
+    ) -> GenreDetection {
+        detect_genre(features, artist_genres, popularity)
+    }
+}
+
+impl MoodClassifier for RuleBased {
+    fn classify_mood(&self, features: AudioFeatures) -> MoodDetection {
+        detect_mood(features)
+    }
+}
+
+/// The backend named by `DETECTOR_BACKEND`; empty means the default
+fn select(name: &str) -> Option<Box<dyn Classifier>> {
+    match name.trim().to_lowercase().as_str() {
+        "" | "rules" | "rule-based" => Some(Box::new(RuleBased)),
+        _ => None,
+    }
+}
+
+/// The configured backend, chosen once at first use
+pub fn backend() -> &'static dyn Classifier {
+    BACKEND.as_ref()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_backend() {
+        assert!(select("").is_some());
+        assert!(select(" Rules ").is_some());
+        assert!(select("onnx").is_none());
+    }
+
+    #[test]
+    fn test_rule_based_matches_detectors() {
+        let features = AudioFeatures {
+            tempo: 128.0,
+            energy: 0.9,
+            valence: 0.7,
+            danceability: 0.85,
+            acousticness: 0.02,
+            instrumentalness: 0.6,
+            loudness: -4.0,
+            speechiness: 0.05,
+        };
+        assert_eq!(
+            RuleBased.classify_genre(features, &[], 60).genre,
+            detect_genre(features, &[], 60).genre
+        );
+        assert_eq!(
+            RuleBased.classify_mood(features).mood,
+            detect_mood(features).mood
+        );
+    }
+}
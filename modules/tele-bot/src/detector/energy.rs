@@ -0,0 +1,113 @@
+//! Rule-based energy level (1–5) of a track
+
+use super::genre::AudioFeatures;
+
+pub const MIN_LEVEL: u8 = 1;
+pub const MAX_LEVEL: u8 = 5;
+
+/// How far past a level's bounds a score must go before a sequence leaves
+/// that level, so tracks near a boundary don't flip-flop between two levels
+const HYSTERESIS: f32 = 0.05;
+
+/// Pure function: 0.0–1.0 energy, mostly Spotify's energy with loudness and
+/// tempo (60–180 BPM) mixed in
+pub fn energy_score(features: &AudioFeatures) -> f32 {
+    let loudness = ((features.loudness + 30.0) / 30.0).clamp(0.0, 1.0);
+    let tempo = ((features.tempo - 60.0) / 120.0).clamp(0.0, 1.0);
+    (0.6 * features.energy + 0.2 * loudness + 0.2 * tempo).clamp(0.0, 1.0)
+}
+
+/// Pure function: level of a single score, each level covering a fifth of
+/// the 0.0–1.0 range
+pub fn energy_level(score: f32) -> u8 {
+    let level = (score.clamp(0.0, 1.0) * MAX_LEVEL as f32).floor() as u8 + 1;
+    level.min(MAX_LEVEL)
+}
+
+/// Pure function: levels of consecutive tracks. A track keeps the previous
+/// track's level while its score stays within that level's bounds widened
+/// by the hysteresis margin.
+pub fn level_sequence(scores: &[f32]) -> Vec<u8> {
+    let width = 1.0 / MAX_LEVEL as f32;
+    let mut levels: Vec<u8> = Vec::with_capacity(scores.len());
+    for &score in scores {
+        let level = match levels.last() {
+            Some(&previous) => {
+                let low = (previous - 1) as f32 * width - HYSTERESIS;
+                let high = previous as f32 * width + HYSTERESIS;
+                if (low..high).contains(&score) {
+                    previous
+                } else {
+                    energy_level(score)
+                }
+            }
+            None => energy_level(score),
+        };
+        levels.push(level);
+    }
+    levels
+}
+
+/// Pure function: a five-segment meter, e.g. "▮▮▮▯▯" for level 3
+pub fn meter(level: u8) -> String {
+    let level = level.clamp(MIN_LEVEL, MAX_LEVEL) as usize;
+    format!(
+        "{}{}",
+        "▮".repeat(level),
+        "▯".repeat(MAX_LEVEL as usize - level)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(energy: f32, loudness: f32, tempo: f32) -> AudioFeatures {
+        AudioFeatures {
+            tempo,
+            energy,
+            valence: 0.5,
+            danceability: 0.5,
+            acousticness: 0.5,
+            instrumentalness: 0.0,
+            loudness,
+            speechiness: 0.05,
+        }
+    }
+
+    #[test]
+    fn test_energy_score_bounds() {
+        assert_eq!(energy_score(&features(0.0, -60.0, 40.0)), 0.0);
+        assert_eq!(energy_score(&features(1.0, 0.0, 200.0)), 1.0);
+        let quiet = energy_score(&features(0.3, -20.0, 80.0));
+        let loud = energy_score(&features(0.3, -4.0, 150.0));
+        assert!(loud > quiet);
+    }
+
+    #[test]
+    fn test_energy_level_scale() {
+        assert_eq!(energy_level(0.0), 1);
+        assert_eq!(energy_level(0.19), 1);
+        assert_eq!(energy_level(0.2), 2);
+        assert_eq!(energy_level(0.55), 3);
+        assert_eq!(energy_level(0.99), 5);
+        assert_eq!(energy_level(1.0), 5);
+        assert_eq!(energy_level(-1.0), 1);
+    }
+
+    #[test]
+    fn test_level_sequence_does_not_flip_flop() {
+        // Hovering around the 2/3 boundary at 0.4 stays on one level
+        assert_eq!(level_sequence(&[0.38, 0.41, 0.39, 0.42]), vec![2, 2, 2, 2]);
+        // A clear move changes level
+        assert_eq!(level_sequence(&[0.38, 0.5, 0.9]), vec![2, 3, 5]);
+        assert!(level_sequence(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_meter() {
+        assert_eq!(meter(3), "▮▮▮▯▯");
+        assert_eq!(meter(0), "▮▯▯▯▯");
+        assert_eq!(meter(9), "▮▮▮▮▮");
+    }
+}
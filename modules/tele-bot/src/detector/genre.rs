@@ -35,6 +35,37 @@ impl Genre {
             Genre::Unknown => "Unknown",
         }
     }
+
+    /// Case-insensitive, ignoring spaces and punctuation so "hiphop", "rnb"
+    /// and "lo-fi" all work; `Unknown` can't be asked for
+    pub fn parse(name: &str) -> Option<Genre> {
+        let simplify = |text: &str| -> String {
+            text.chars()
+                .filter(|c| c.is_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        };
+        let wanted = simplify(name);
+        [
+            Genre::Ballad,
+            Genre::Pop,
+            Genre::Rock,
+            Genre::Edm,
+            Genre::HipHop,
+            Genre::RnB,
+            Genre::Jazz,
+            Genre::Classical,
+            Genre::Acoustic,
+            Genre::LoFi,
+            Genre::Indie,
+            Genre::Metal,
+        ]
+        .into_iter()
+        // The variant name covers "rnb", which `as_str` spells "R&B"
+        .find(|genre| {
+            simplify(genre.as_str()) == wanted || simplify(&format!("{:?}", genre)) == wanted
+        })
+    }
 }
 
 /// Audio features from Spotify API
@@ -76,7 +107,7 @@ pub struct GenreScores {
 }
 
 /// Pure function: detect genre from audio features and artist metadata
-/// 
+///
 /// # Arguments
 /// * `features` - Audio features from Spotify
 /// * `artist_genres` - Genre tags from artist metadata (high weight)
@@ -578,6 +609,17 @@ mod tests {
         assert!(result.confidence < 0.5 || matches!(result.genre, Genre::Unknown | Genre::Pop));
     }
 
+    #[test]
+    fn test_parse_genre_names() {
+        assert_eq!(Genre::parse("rock"), Some(Genre::Rock));
+        assert_eq!(Genre::parse("HipHop"), Some(Genre::HipHop));
+        assert_eq!(Genre::parse("r&b"), Some(Genre::RnB));
+        assert_eq!(Genre::parse("rnb"), Some(Genre::RnB));
+        assert_eq!(Genre::parse("lofi"), Some(Genre::LoFi));
+        assert_eq!(Genre::parse("unknown"), None);
+        assert_eq!(Genre::parse("polka"), None);
+    }
+
     #[test]
     fn test_score_transparency() {
         let features = sample_features();
@@ -84,7 +84,7 @@ fn country_to_language(country_code: &str) -> Language {
         "ES" | "MX" | "AR" | "CO" | "CL" | "PE" | "VE" | "CU" => Language::Spanish,
 
         // French-speaking countries
-        "FR" | "BE" | "CH" | "CA" | "SN" | "CG" | "CD" => Language::French,
+        "FR" | "BE" | "CH" | "SN" | "CG" | "CD" => Language::French,
 
         // Asian countries
         "JP" => Language::Japanese,
@@ -1,3 +1,5 @@
+pub mod classifier;
+pub mod energy;
 pub mod genre;
+pub mod language;
 pub mod mood;
-pub mod language;
\ No newline at end of file
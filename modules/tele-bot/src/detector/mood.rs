@@ -1,4 +1,4 @@
-/// Rule-based music mood detection system
+//! Rule-based music mood detection system
 
 use super::genre::AudioFeatures;
 
@@ -29,6 +29,36 @@ impl Mood {
             Mood::Unknown => "Unknown",
         }
     }
+
+    /// Case-insensitive, from the names `as_str` gives; `Unknown` can't be asked for
+    pub fn parse(name: &str) -> Option<Mood> {
+        [
+            Mood::Happy,
+            Mood::Sad,
+            Mood::Energetic,
+            Mood::Calm,
+            Mood::Angry,
+            Mood::Melancholic,
+            Mood::Peaceful,
+            Mood::Romantic,
+        ]
+        .into_iter()
+        .find(|mood| mood.as_str().eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Mood::Happy => "😊",
+            Mood::Sad => "😢",
+            Mood::Energetic => "⚡",
+            Mood::Calm => "😌",
+            Mood::Angry => "😠",
+            Mood::Melancholic => "🌧️",
+            Mood::Peaceful => "🕊️",
+            Mood::Romantic => "💕",
+            Mood::Unknown => "❔",
+        }
+    }
 }
 
 /// Detection result with mood and confidence (0.0 to 1.0)
@@ -128,7 +158,9 @@ fn score_happy(features: &AudioFeatures) -> f32 {
     let mut score = 0.0;
 
     // High valence is the primary indicator of happiness
-    if features.valence > 0.7 {
+    if features.valence > 0.8 {
+        score += 3.0;
+    } else if features.valence > 0.7 {
         score += 2.0;
     } else if features.valence > 0.5 {
         score += 1.0;
@@ -154,7 +186,7 @@ fn score_sad(features: &AudioFeatures) -> f32 {
     let mut score = 0.0;
 
     // Low valence indicates sadness
-    if features.valence < 0.4 {
+    if features.valence < 0.3 {
         score += 2.0;
     } else if features.valence < 0.6 {
         score += 1.0;
@@ -278,13 +310,13 @@ fn score_angry(features: &AudioFeatures) -> f32 {
 }
 
 fn score_melancholic(features: &AudioFeatures) -> f32 {
-    let mut score = 0.0;
-
-    // Low valence combined with moderate energy
-    if features.valence < 0.5 {
-        score += 1.0;
+    // Melancholy needs a negative emotional tone to begin with
+    if features.valence >= 0.5 {
+        return 0.0;
     }
 
+    let mut score = 1.0;
+
     // Moderate to low energy (contemplative)
     if features.energy < 0.6 && features.energy > 0.3 {
         score += 1.0;
@@ -338,8 +370,11 @@ fn score_peaceful(features: &AudioFeatures) -> f32 {
         score += 1.0;
     }
 
-    // Often acoustic or instrumental
-    if features.acousticness > 0.5 || features.instrumentalness > 0.4 {
+    // Often acoustic and/or instrumental
+    if features.acousticness > 0.5 {
+        score += 1.0;
+    }
+    if features.instrumentalness > 0.4 {
         score += 1.0;
     }
 
@@ -347,6 +382,11 @@ fn score_peaceful(features: &AudioFeatures) -> f32 {
 }
 
 fn score_romantic(features: &AudioFeatures) -> f32 {
+    // Romance needs a positive emotional tone to begin with
+    if features.valence <= 0.5 {
+        return 0.0;
+    }
+
     let mut score = 0.0;
 
     // Moderate to high valence (positive emotion)
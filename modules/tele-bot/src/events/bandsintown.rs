@@ -0,0 +1,137 @@
+use serde::Deserialize;
+use tracing::debug;
+
+use super::{Event, EventProvider};
+
+const API_BASE: &str = "https://rest.bandsintown.com/artists/";
+
+pub struct Bandsintown {
+    app_id: String,
+    client: reqwest::Client,
+}
+
+/// The events endpoint answers with a list, or with an error object such as
+/// `{"errorMessage": "[NotFound] The artist was not found"}`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BitResponse {
+    Events(Vec<BitEvent>),
+    Error {
+        #[serde(rename = "errorMessage")]
+        message: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct BitEvent {
+    datetime: String,
+    url: String,
+    venue: BitVenue,
+}
+
+#[derive(Deserialize)]
+struct BitVenue {
+    name: String,
+    // Sometimes null for online or unannounced venues
+    city: Option<String>,
+    country: Option<String>,
+}
+
+impl Bandsintown {
+    pub fn new(app_id: String) -> Self {
+        Self {
+            app_id,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl EventProvider for Bandsintown {
+    async fn upcoming_events(&self, artist: &str) -> Result<Vec<Event>, String> {
+        let mut url = reqwest::Url::parse(API_BASE).expect("valid base URL");
+        url.path_segments_mut()
+            .expect("base URL has a path")
+            .pop_if_empty()
+            .push(artist)
+            .push("events");
+        url.query_pairs_mut().append_pair("app_id", &self.app_id);
+
+        let body = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| err.to_string())?
+            .text()
+            .await
+            .map_err(|err| err.to_string())?;
+
+        parse_events(artist, &body)
+    }
+}
+
+/// Pure function: the events in a response body. Unknown artists come back
+/// as an error object and have no events; anything else that isn't a list
+/// of events is an error.
+fn parse_events(artist: &str, body: &str) -> Result<Vec<Event>, String> {
+    let events = match serde_json::from_str(body) {
+        Ok(BitResponse::Events(events)) => events,
+        Ok(BitResponse::Error { message }) => {
+            debug!("No Bandsintown events for {artist}: {message}");
+            return Ok(Vec::new());
+        }
+        Err(err) => return Err(format!("unexpected Bandsintown response: {err}")),
+    };
+
+    Ok(events
+        .into_iter()
+        .map(|e| Event {
+            artist: artist.to_string(),
+            venue: e.venue.name,
+            city: e.venue.city.unwrap_or_default(),
+            country: e.venue.country.unwrap_or_default(),
+            datetime: e.datetime,
+            url: e.url,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events() {
+        let body = r#"[
+            {
+                "id": "1",
+                "datetime": "2024-05-01T19:00:00",
+                "url": "https://www.bandsintown.com/e/1",
+                "venue": {"name": "Hanoi Opera House", "city": "Hanoi", "country": "Vietnam"},
+                "lineup": ["Artist"]
+            },
+            {
+                "id": "2",
+                "datetime": "2024-06-01T20:00:00",
+                "url": "https://www.bandsintown.com/e/2",
+                "venue": {"name": "Livestream", "city": null, "country": null}
+            }
+        ]"#;
+        let events = parse_events("Artist", body).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].venue, "Hanoi Opera House");
+        assert_eq!(events[0].country, "Vietnam");
+        assert_eq!(events[1].city, "");
+    }
+
+    #[test]
+    fn test_parse_events_error_object_and_drift() {
+        let not_found = r#"{"errorMessage": "[NotFound] The artist was not found"}"#;
+        assert!(parse_events("Nobody", not_found).unwrap().is_empty());
+
+        assert!(parse_events("Artist", r#"[{"datetime": "2024-05-01"}]"#).is_err());
+        assert!(parse_events("Artist", r#"{"events": []}"#).is_err());
+        assert!(parse_events("Artist", "<html>").is_err());
+    }
+}
@@ -0,0 +1,68 @@
+//! Concert and event lookup behind a provider trait
+
+pub mod bandsintown;
+
+use std::future::Future;
+
+/// An upcoming concert for a single artist
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub artist: String,
+    pub venue: String,
+    pub city: String,
+    pub country: String,
+    pub datetime: String,
+    pub url: String,
+}
+
+pub trait EventProvider {
+    fn upcoming_events(
+        &self,
+        artist: &str,
+    ) -> impl Future<Output = Result<Vec<Event>, String>> + Send;
+}
+
+/// Build the configured provider, if any credentials are set
+pub fn provider_from_env() -> Option<bandsintown::Bandsintown> {
+    std::env::var("BANDSINTOWN_APP_ID")
+        .ok()
+        .filter(|id| !id.is_empty())
+        .map(bandsintown::Bandsintown::new)
+}
+
+/// Location filter from `EVENTS_LOCATION` (city or country, case-insensitive)
+pub fn location_from_env() -> Option<String> {
+    std::env::var("EVENTS_LOCATION")
+        .ok()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+}
+
+/// Pure function: whether an event takes place in the given city or country
+pub fn is_near(event: &Event, location: &str) -> bool {
+    let location = location.to_lowercase();
+    event.city.to_lowercase() == location || event.country.to_lowercase() == location
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(city: &str, country: &str) -> Event {
+        Event {
+            artist: "Artist".to_string(),
+            venue: "Venue".to_string(),
+            city: city.to_string(),
+            country: country.to_string(),
+            datetime: "2024-05-01T19:00:00".to_string(),
+            url: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_is_near_matches_city_or_country() {
+        assert!(is_near(&event("Hanoi", "Vietnam"), "hanoi"));
+        assert!(is_near(&event("Hanoi", "Vietnam"), "Vietnam"));
+        assert!(!is_near(&event("Berlin", "Germany"), "Hanoi"));
+    }
+}
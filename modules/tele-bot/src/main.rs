@@ -1,9 +1,13 @@
 mod auth;
 mod bot;
 mod error;
+mod events;
 mod models;
 mod state;
+mod stats;
 mod utils;
+// Detectors are wired into commands incrementally; not every rule is used yet
+#[allow(dead_code)]
 mod detector;
 
 use dotenvy::dotenv;
@@ -25,6 +29,15 @@ async fn main() {
     let bot = Bot::from_env();
     info!("Spotify Dashboard Telegram Bot started");
 
+    tokio::spawn(bot::notifier::run_streak_notifier(bot.clone()));
+    tokio::spawn(bot::notifier::run_release_notifier(bot.clone()));
+    tokio::spawn(bot::notifier::run_playback_poller());
+    // These jobs write playlists, which read-only mode rules out
+    if !auth::flags::read_only() {
+        tokio::spawn(bot::notifier::run_weekly_archiver(bot.clone()));
+        tokio::spawn(bot::notifier::run_playlist_rotation(bot.clone()));
+    }
+
     Dispatcher::builder(bot, bot::handlers::schema())
         .enable_ctrlc_handler()
         .build()
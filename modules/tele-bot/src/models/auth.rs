@@ -5,7 +5,7 @@ use tokio::sync::RwLock;
 #[derive(Debug)]
 pub struct UserAuth {
     pub telegram_id: i64,
-    pub token: Token
+    pub token: Token,
 }
 
-pub type TokenStore = RwLock<HashMap<i64, Token>>;
\ No newline at end of file
+pub type TokenStore = RwLock<HashMap<i64, Token>>;
@@ -0,0 +1,437 @@
+//! Adapter between rspotify's API types and the bot's own models.
+//!
+//! Handlers and commands only work with `models::spotify` types; anything
+//! that depends on the shape of rspotify models lives here, so upgrading
+//! rspotify only touches this module.
+
+use rspotify::model::{
+    AlbumId, ArtistId, AudioFeatures as SpotifyAudioFeatures, Context, Country, FullArtist,
+    FullTrack, Id, Market, Modality, PlayHistory, PlayableId, PlayableItem, PlaylistId,
+    PlaylistItem, PrivateUser, ResumePoint, SavedAlbum as SpotifySavedAlbum, Show as SavedShow,
+    ShowId, SimplifiedAlbum, SimplifiedEpisode, SimplifiedPlaylist, TimeRange, TrackId, Type,
+};
+
+use super::spotify::{
+    Artist, ContextKind, Episode, EpisodeProgress, Play, PlayContext, Playlist, Release,
+    SavedAlbum, Show, TopRange, Track, UserProfile,
+};
+use crate::detector::genre::AudioFeatures;
+use crate::stats::harmonic::Camelot;
+
+impl From<FullTrack> for Track {
+    fn from(track: FullTrack) -> Self {
+        Track {
+            id: track.id.map(|id| id.id().to_string()),
+            name: track.name,
+            artists: track.artists.into_iter().map(|a| a.name).collect(),
+            album: track.album.name,
+            album_id: track.album.id.map(|id| id.id().to_string()),
+            release_date: track.album.release_date,
+            // Spotify lists album images widest first
+            album_art: track.album.images.into_iter().next().map(|image| image.url),
+            duration_ms: track.duration.num_milliseconds().max(0) as u32,
+            explicit: track.explicit,
+            popularity: track.popularity,
+            // Only reported when a market is given; assume playable otherwise
+            playable: track.is_playable != Some(false),
+            linked_from: track.linked_from.map(|link| link.id.id().to_string()),
+        }
+    }
+}
+
+impl From<FullArtist> for Artist {
+    fn from(artist: FullArtist) -> Self {
+        Artist {
+            id: artist.id.id().to_string(),
+            name: artist.name,
+            genres: artist.genres,
+        }
+    }
+}
+
+impl From<PlayHistory> for Play {
+    fn from(item: PlayHistory) -> Self {
+        Play {
+            track: item.track.into(),
+            played_at: item.played_at,
+            context: item.context.map(PlayContext::from),
+        }
+    }
+}
+
+impl From<Context> for PlayContext {
+    fn from(context: Context) -> Self {
+        let kind = match context._type {
+            Type::Playlist => ContextKind::Playlist,
+            Type::Album => ContextKind::Album,
+            Type::Artist => ContextKind::Artist,
+            Type::Collection => ContextKind::LikedSongs,
+            _ => ContextKind::Other,
+        };
+        // URIs look like `spotify:playlist:<id>`; the ID is the last segment
+        let id = context
+            .uri
+            .rsplit(':')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        PlayContext {
+            kind,
+            id,
+            name: None,
+        }
+    }
+}
+
+impl From<SimplifiedPlaylist> for Playlist {
+    fn from(playlist: SimplifiedPlaylist) -> Self {
+        Playlist {
+            id: playlist.id.id().to_string(),
+            name: playlist.name,
+            total_tracks: playlist.tracks.total,
+            owner_id: playlist.owner.id.id().to_string(),
+            snapshot_id: playlist.snapshot_id,
+        }
+    }
+}
+
+impl From<PrivateUser> for UserProfile {
+    fn from(user: PrivateUser) -> Self {
+        UserProfile {
+            id: user.id.id().to_string(),
+            display_name: user.display_name,
+            email: user.email,
+            country: user
+                .country
+                .map(|country| <&str>::from(country).to_string()),
+            product: user
+                .product
+                .map(|product| <&str>::from(product).to_string()),
+            followers: user.followers.map_or(0, |followers| followers.total),
+            avatar_url: user
+                .images
+                .unwrap_or_default()
+                .into_iter()
+                .next()
+                .map(|image| image.url),
+        }
+    }
+}
+
+impl From<SpotifySavedAlbum> for SavedAlbum {
+    fn from(saved: SpotifySavedAlbum) -> Self {
+        SavedAlbum {
+            id: saved.album.id.id().to_string(),
+            name: saved.album.name,
+            artists: saved.album.artists.into_iter().map(|a| a.name).collect(),
+            release_date: saved.album.release_date,
+            total_tracks: saved.album.tracks.total,
+            added_at: saved.added_at,
+        }
+    }
+}
+
+impl From<SavedShow> for Show {
+    fn from(saved: SavedShow) -> Self {
+        Show {
+            id: saved.show.id.id().to_string(),
+            name: saved.show.name,
+            publisher: saved.show.publisher,
+        }
+    }
+}
+
+impl From<SimplifiedEpisode> for Episode {
+    fn from(episode: SimplifiedEpisode) -> Self {
+        Episode {
+            id: episode.id.id().to_string(),
+            name: episode.name,
+            release_date: episode.release_date,
+            duration_ms: episode.duration.num_milliseconds().max(0) as u32,
+            progress: episode_progress(episode.resume_point),
+        }
+    }
+}
+
+pub fn episode_progress(resume_point: Option<ResumePoint>) -> EpisodeProgress {
+    match resume_point {
+        Some(point) if point.fully_played => EpisodeProgress::Played,
+        Some(point) if point.resume_position.num_milliseconds() > 0 => {
+            EpisodeProgress::InProgress {
+                position_ms: point.resume_position.num_milliseconds() as u32,
+            }
+        }
+        _ => EpisodeProgress::New,
+    }
+}
+
+/// Episodes have no `Track`
+pub fn playable_item_track(item: PlayableItem) -> Option<Track> {
+    match item {
+        PlayableItem::Track(track) => Some(track.into()),
+        PlayableItem::Episode(_) => None,
+    }
+}
+
+/// Tracks of a playlist; episodes and removed tracks have no `Track`
+pub fn playlist_item_track(item: PlaylistItem) -> Option<Track> {
+    playable_item_track(item.track?)
+}
+
+/// A playlist entry's track with when it was added, which Spotify leaves
+/// out for very old playlists
+pub fn playlist_item_added(
+    item: PlaylistItem,
+) -> (Option<chrono::DateTime<chrono::Utc>>, Option<Track>) {
+    (item.added_at, playlist_item_track(item))
+}
+
+pub fn time_range(range: TopRange) -> TimeRange {
+    match range {
+        TopRange::Short => TimeRange::ShortTerm,
+        TopRange::Medium => TimeRange::MediumTerm,
+        TopRange::Long => TimeRange::LongTerm,
+    }
+}
+
+/// Audio features keyed by the bare track ID they belong to, and the
+/// track's key when Spotify knows it
+pub fn audio_features(features: SpotifyAudioFeatures) -> (String, AudioFeatures, Option<Camelot>) {
+    let id = features.id.id().to_string();
+    let key = Camelot::from_key(features.key, features.mode == Modality::Minor);
+    (
+        id,
+        AudioFeatures {
+            tempo: features.tempo,
+            energy: features.energy,
+            valence: features.valence,
+            danceability: features.danceability,
+            acousticness: features.acousticness,
+            instrumentalness: features.instrumentalness,
+            loudness: features.loudness,
+            speechiness: features.speechiness,
+        },
+        key,
+    )
+}
+
+/// Convert an artist's album into a release; albums without an ID or
+/// release date can't be tracked and are skipped
+pub fn release_from_album(album: SimplifiedAlbum, artist: &str) -> Option<Release> {
+    Some(Release {
+        id: album.id?.id().to_string(),
+        name: album.name,
+        artist: artist.to_string(),
+        album_type: album.album_type.unwrap_or_else(|| "album".to_string()),
+        release_date: album.release_date?,
+    })
+}
+
+pub fn album_id(id: &str) -> Option<AlbumId<'static>> {
+    AlbumId::from_id(id.to_string()).ok()
+}
+
+pub fn artist_id(id: &str) -> Option<ArtistId<'static>> {
+    ArtistId::from_id(id.to_string()).ok()
+}
+
+pub fn show_id(id: &str) -> Option<ShowId<'static>> {
+    ShowId::from_id(id.to_string()).ok()
+}
+
+pub fn track_id(id: &str) -> Option<TrackId<'static>> {
+    TrackId::from_id(id.to_string()).ok()
+}
+
+pub fn playlist_id(id: &str) -> Option<PlaylistId<'static>> {
+    PlaylistId::from_id(id.to_string()).ok()
+}
+
+/// ISO 3166-1 alpha-2 code, in any case, to Spotify's `Country`
+pub fn country(code: &str) -> Option<Country> {
+    serde_json::from_value(serde_json::Value::String(code.trim().to_uppercase())).ok()
+}
+
+/// The given country's market, or the one of the token's account
+pub fn market(country_code: Option<&str>) -> Market {
+    country_code
+        .and_then(country)
+        .map_or(Market::FromToken, Market::Country)
+}
+
+pub fn playable_track(id: &str) -> Option<PlayableId<'static>> {
+    track_id(id).map(PlayableId::Track)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn album(id: Option<&str>, release_date: Option<&str>) -> SimplifiedAlbum {
+        serde_json::from_value(json!({
+            "album_type": "single",
+            "artists": [],
+            "external_urls": {},
+            "href": null,
+            "id": id,
+            "images": [],
+            "name": "Fresh Single",
+            "release_date": release_date,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_release_from_album() {
+        let release = release_from_album(
+            album(Some("4aawyAB9vmqN3uQ7FjRGTy"), Some("2024-03-01")),
+            "Artist",
+        )
+        .unwrap();
+
+        assert_eq!(release.id, "4aawyAB9vmqN3uQ7FjRGTy");
+        assert_eq!(release.album_type, "single");
+        assert_eq!(release.artist, "Artist");
+    }
+
+    #[test]
+    fn test_release_without_id_or_date_is_skipped() {
+        assert!(release_from_album(album(None, Some("2024-03-01")), "Artist").is_none());
+        assert!(
+            release_from_album(album(Some("4aawyAB9vmqN3uQ7FjRGTy"), None), "Artist").is_none()
+        );
+    }
+
+    #[test]
+    fn test_track_carries_album_and_duration() {
+        let track: FullTrack = serde_json::from_value(json!({
+            "album": {
+                "artists": [],
+                "external_urls": {},
+                "href": null,
+                "id": "4aawyAB9vmqN3uQ7FjRGTy",
+                "images": [
+                    { "url": "https://i.scdn.co/image/large", "height": 640, "width": 640 },
+                    { "url": "https://i.scdn.co/image/small", "height": 64, "width": 64 }
+                ],
+                "name": "Global Warming",
+            },
+            "artists": [{ "external_urls": {}, "href": null, "id": null, "name": "Pitbull" }],
+            "disc_number": 1,
+            "duration_ms": 207959,
+            "explicit": true,
+            "external_ids": {},
+            "external_urls": {},
+            "href": null,
+            "id": "6rqhFgbbKwnb9MLmUQDhG6",
+            "is_local": false,
+            "linked_from": {
+                "external_urls": {},
+                "href": "https://api.spotify.com/v1/tracks/3n3Ppam7vgaVa1iaRUc9Lp",
+                "id": "3n3Ppam7vgaVa1iaRUc9Lp",
+            },
+            "name": "Feel This Moment",
+            "popularity": 72,
+            "preview_url": null,
+            "track_number": 1,
+        }))
+        .unwrap();
+        let track = Track::from(track);
+
+        assert_eq!(track.album, "Global Warming");
+        assert_eq!(
+            track.album_art.as_deref(),
+            Some("https://i.scdn.co/image/large")
+        );
+        assert_eq!(track.duration_ms, 207959);
+        assert!(track.explicit);
+        assert_eq!(track.popularity, 72);
+        assert!(track.playable);
+        assert_eq!(track.canonical_id(), Some("3n3Ppam7vgaVa1iaRUc9Lp"));
+    }
+
+    #[test]
+    fn test_play_context_from_uri() {
+        let context: Context = serde_json::from_value(json!({
+            "uri": "spotify:playlist:37i9dQZF1DXcBWIGoYBM5M",
+            "href": "https://api.spotify.com/v1/playlists/37i9dQZF1DXcBWIGoYBM5M",
+            "external_urls": {},
+            "type": "playlist",
+        }))
+        .unwrap();
+        let context = PlayContext::from(context);
+
+        assert_eq!(context.kind, ContextKind::Playlist);
+        assert_eq!(context.id, "37i9dQZF1DXcBWIGoYBM5M");
+        assert!(context.name.is_none());
+    }
+
+    #[test]
+    fn test_user_profile_fields() {
+        let user: PrivateUser = serde_json::from_value(json!({
+            "country": "VN",
+            "display_name": "Huy",
+            "email": "huy@example.com",
+            "external_urls": {},
+            "followers": { "href": null, "total": 12 },
+            "href": "https://api.spotify.com/v1/users/quochuy",
+            "id": "quochuy",
+            "images": [{ "url": "https://i.scdn.co/image/avatar", "height": 300, "width": 300 }],
+            "product": "premium",
+            "type": "user",
+            "uri": "spotify:user:quochuy",
+        }))
+        .unwrap();
+        let profile = UserProfile::from(user);
+
+        assert_eq!(profile.id, "quochuy");
+        assert_eq!(profile.country.as_deref(), Some("VN"));
+        assert_eq!(profile.product.as_deref(), Some("premium"));
+        assert_eq!(profile.followers, 12);
+        assert_eq!(
+            profile.avatar_url.as_deref(),
+            Some("https://i.scdn.co/image/avatar")
+        );
+    }
+
+    #[test]
+    fn test_market_from_country_code() {
+        assert_eq!(country("vn"), Some(Country::VietNam));
+        assert_eq!(country("XX"), None);
+        assert_eq!(market(Some("us")), Market::Country(Country::UnitedStates));
+        assert_eq!(market(Some("nowhere")), Market::FromToken);
+        assert_eq!(market(None), Market::FromToken);
+    }
+
+    #[test]
+    fn test_episode_progress_from_resume_point() {
+        let point = |fully_played, seconds| ResumePoint {
+            fully_played,
+            resume_position: chrono::Duration::seconds(seconds),
+        };
+
+        assert_eq!(episode_progress(None), EpisodeProgress::New);
+        assert_eq!(
+            episode_progress(Some(point(false, 0))),
+            EpisodeProgress::New
+        );
+        assert_eq!(
+            episode_progress(Some(point(false, 90))),
+            EpisodeProgress::InProgress {
+                position_ms: 90_000
+            }
+        );
+        assert_eq!(
+            episode_progress(Some(point(true, 90))),
+            EpisodeProgress::Played
+        );
+    }
+
+    #[test]
+    fn test_id_helpers_take_bare_ids() {
+        let id = track_id("6rqhFgbbKwnb9MLmUQDhG6").unwrap();
+        assert_eq!(id.id(), "6rqhFgbbKwnb9MLmUQDhG6");
+        assert!(playlist_id("not a valid id!").is_none());
+    }
+}
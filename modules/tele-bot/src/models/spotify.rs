@@ -1,11 +1,144 @@
-#[derive(Clone)]
+use chrono::{DateTime, Utc};
+
+#[derive(Clone, Debug)]
 pub struct Track {
+    pub id: Option<String>,
     pub name: String,
     pub artists: Vec<String>,
+    pub album: String,
+    pub album_id: Option<String>,
+    /// "2019", "2019-05" or "2019-05-17", depending on what Spotify knows
+    pub release_date: Option<String>,
+    /// URL of the largest album cover, if Spotify has one
+    pub album_art: Option<String>,
+    pub duration_ms: u32,
+    pub explicit: bool,
+    /// 0–100, relative to other tracks right now
+    pub popularity: u32,
+    /// False when Spotify reports the track as unavailable in the user's market
+    pub playable: bool,
+    /// ID that was asked for when Spotify swapped in a version playable in the
+    /// user's market ("track relinking")
+    pub linked_from: Option<String>,
+}
+
+impl Track {
+    /// The ID the track is known by regardless of market, so relinked copies
+    /// of the same track compare equal
+    pub fn canonical_id(&self) -> Option<&str> {
+        self.linked_from.as_deref().or(self.id.as_deref())
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Artist {
+    pub id: String,
     pub name: String,
     pub genres: Vec<String>,
 }
+
+/// A single entry of the listening history
+#[derive(Clone, Debug)]
+pub struct Play {
+    pub track: Track,
+    pub played_at: DateTime<Utc>,
+    pub context: Option<PlayContext>,
+}
+
+/// How far back the top lists look
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TopRange {
+    /// About the last 4 weeks
+    Short,
+    /// About the last 6 months
+    Medium,
+    /// All the listening Spotify has data for
+    Long,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ContextKind {
+    Playlist,
+    Album,
+    Artist,
+    LikedSongs,
+    Other,
+}
+
+/// Where a play was started from. Spotify only reports the URI, so `name`
+/// stays empty until it is looked up separately.
+#[derive(Clone, Debug)]
+pub struct PlayContext {
+    pub kind: ContextKind,
+    pub id: String,
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Playlist {
+    pub id: String,
+    pub name: String,
+    pub total_tracks: u32,
+    pub owner_id: String,
+    /// Version tag that changes with every edit
+    pub snapshot_id: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Release {
+    pub id: String,
+    pub name: String,
+    pub artist: String,
+    pub album_type: String,
+    pub release_date: String,
+}
+
+/// An album in the user's library
+#[derive(Clone, Debug)]
+pub struct SavedAlbum {
+    pub id: String,
+    pub name: String,
+    pub artists: Vec<String>,
+    pub release_date: String,
+    pub total_tracks: u32,
+    pub added_at: DateTime<Utc>,
+}
+
+/// A podcast or audiobook the user follows
+#[derive(Clone, Debug)]
+pub struct Show {
+    pub id: String,
+    pub name: String,
+    pub publisher: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Episode {
+    pub id: String,
+    pub name: String,
+    pub release_date: String,
+    pub duration_ms: u32,
+    pub progress: EpisodeProgress,
+}
+
+/// How far the user got; needs the `user-read-playback-position` scope,
+/// without which every episode looks new
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EpisodeProgress {
+    New,
+    InProgress { position_ms: u32 },
+    Played,
+}
+
+#[derive(Clone, Debug)]
+pub struct UserProfile {
+    pub id: String,
+    pub display_name: Option<String>,
+    pub email: Option<String>,
+    /// ISO 3166-1 alpha-2 code, e.g. "VN"
+    pub country: Option<String>,
+    /// "premium" or "free"
+    pub product: Option<String>,
+    pub followers: u32,
+    pub avatar_url: Option<String>,
+}
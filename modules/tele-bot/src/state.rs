@@ -2,7 +2,50 @@ use rspotify::AuthCodeSpotify;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-#[derive(Clone)]
+use crate::models::spotify::{Artist, TopRange, Track};
+use crate::stats::archive::WeeklyArchive;
+use crate::stats::backups::BackupStore;
+use crate::stats::releases::ReleaseRadar;
+use crate::stats::skips::SkipTracker;
+use crate::stats::sleep::SleepTimer;
+use crate::stats::staleness::{PlayLog, Rotation};
+use crate::stats::streak::StreakTracker;
+use crate::utils::audit::AuditLog;
+use crate::utils::format::Locale;
+use crate::utils::list_args::ListArgs;
+use crate::utils::single_flight::SingleFlight;
+use crate::utils::webhooks::Webhook;
+
+#[derive(Clone, Default)]
 pub struct AppState {
     pub spotify: Arc<Mutex<Option<AuthCodeSpotify>>>,
+    /// Top tracks and artists by time range, fetched once for concurrent
+    /// commands and jobs
+    pub top_tracks: Arc<SingleFlight<TopRange, Result<Vec<Track>, String>>>,
+    pub top_artists: Arc<SingleFlight<TopRange, Result<Vec<Artist>, String>>>,
+    pub streak: Arc<Mutex<StreakTracker>>,
+    pub releases: Arc<Mutex<ReleaseRadar>>,
+    pub skips: Arc<Mutex<SkipTracker>>,
+    pub backups: Arc<Mutex<BackupStore>>,
+    pub sleep_timer: Arc<Mutex<Option<SleepTimer>>>,
+    pub archive: Arc<Mutex<WeeklyArchive>>,
+    /// When tracks were last seen playing, for `/staleness`
+    pub play_log: Arc<Mutex<PlayLog>>,
+    /// Playlists that drop stale tracks daily, set with `/rotate`
+    pub rotations: Arc<Mutex<Vec<Rotation>>>,
+    pub locale: Arc<Mutex<Locale>>,
+    /// What the bot changed on the account, shown by `/history_actions`
+    pub audit: Arc<Mutex<AuditLog>>,
+    /// Count and time range used by the top lists, set with `/top_defaults`
+    pub list_defaults: Arc<Mutex<ListArgs>>,
+    /// Country code overriding the account's market, set with `/market`
+    pub market: Arc<Mutex<Option<String>>>,
+    /// Leave explicit tracks out of search results and generated playlists
+    pub hide_explicit: Arc<Mutex<bool>>,
+    /// Where events are POSTed, set with `/webhook`
+    pub webhook: Arc<Mutex<Option<Webhook>>>,
+    /// Publish now playing to the MQTT broker, set with `/mqtt`
+    pub mqtt: Arc<Mutex<bool>>,
+    /// Telegram username, so other members can refer to this one
+    pub username: Arc<Mutex<Option<String>>>,
 }
@@ -0,0 +1,70 @@
+//! Weekly archive of Spotify's generated playlists, which are replaced every Monday
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+/// Spotify-made playlists worth keeping, by the name they have in the library
+pub const ARCHIVED_PLAYLISTS: &[&str] = &["Discover Weekly", "Release Radar"];
+
+/// User ID that owns the generated playlists
+pub const SPOTIFY_OWNER: &str = "spotify";
+
+/// Per-member archive bookkeeping
+#[derive(Debug, Clone, Default)]
+pub struct WeeklyArchive {
+    pub enabled: bool,
+    /// Monday of the last week that was archived
+    last_week: Option<NaiveDate>,
+}
+
+impl WeeklyArchive {
+    /// Monday of `today`'s week if it hasn't been archived yet. A missed
+    /// Monday is caught up later in the week, as the playlists only change
+    /// on Mondays.
+    pub fn due(&self, today: NaiveDate) -> Option<NaiveDate> {
+        let week = week_start(today);
+        (self.enabled && self.last_week != Some(week)).then_some(week)
+    }
+
+    pub fn mark_done(&mut self, week: NaiveDate) {
+        self.last_week = Some(week);
+    }
+}
+
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Name of the archive copy, e.g. "Discover Weekly · 2024-03-11"
+pub fn archive_name(playlist: &str, week: NaiveDate) -> String {
+    format!("{} · {}", playlist, week.format("%Y-%m-%d"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_due_once_per_week() {
+        let mut archive = WeeklyArchive::default();
+        assert_eq!(archive.due(date(2024, 3, 11)), None);
+
+        archive.enabled = true;
+        // Wednesday catches up the Monday that was missed
+        assert_eq!(archive.due(date(2024, 3, 13)), Some(date(2024, 3, 11)));
+        archive.mark_done(date(2024, 3, 11));
+        assert_eq!(archive.due(date(2024, 3, 17)), None);
+        assert_eq!(archive.due(date(2024, 3, 18)), Some(date(2024, 3, 18)));
+    }
+
+    #[test]
+    fn test_archive_name() {
+        assert_eq!(
+            archive_name("Discover Weekly", date(2024, 3, 11)),
+            "Discover Weekly · 2024-03-11"
+        );
+    }
+}
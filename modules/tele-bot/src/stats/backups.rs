@@ -0,0 +1,238 @@
+//! In-memory playlist backups, taken on demand and before the bot reorders
+//! or removes tracks
+
+use chrono::{DateTime, Duration, Utc};
+
+use super::shuffle::{inverse_order, reorder_moves};
+
+/// Older backups of the same playlist are dropped beyond this
+pub const MAX_BACKUPS_PER_PLAYLIST: usize = 5;
+/// How long the "↩ Undo" button after a change keeps working
+pub const UNDO_WINDOW_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaylistBackup {
+    pub playlist_id: String,
+    pub playlist_name: String,
+    /// Spotify's version tag of the playlist when it was backed up
+    pub snapshot_id: String,
+    /// Track IDs in playlist order
+    pub track_ids: Vec<String>,
+    /// Local files and episodes, which can't be added back by ID
+    pub skipped: usize,
+    /// For a backup taken before a reorder, the order applied (new position
+    /// → old position), so undo can move items back instead of replacing them
+    pub reorder: Option<Vec<usize>>,
+    pub taken_at: DateTime<Utc>,
+    /// Why the backup was taken, e.g. "manual" or "before smart shuffle"
+    pub reason: String,
+}
+
+/// How the change made right after a backup is undone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UndoPlan {
+    /// Single-item moves `(from, to)` that reverse a reorder
+    MoveBack(Vec<(usize, usize)>),
+    /// Replace the playlist's items with the backed up tracks
+    Replace,
+}
+
+impl PlaylistBackup {
+    /// Whether a change made right after this backup can still be undone
+    pub fn can_undo(&self, now: DateTime<Utc>) -> bool {
+        now - self.taken_at < Duration::minutes(UNDO_WINDOW_MINUTES)
+    }
+
+    /// Pure function: how to undo the change made after this backup. `None`
+    /// when only a replace would do and it would drop the local files and
+    /// episodes the backup left out.
+    pub fn undo_plan(&self) -> Option<UndoPlan> {
+        match &self.reorder {
+            Some(order) => Some(UndoPlan::MoveBack(reorder_moves(&inverse_order(order)))),
+            None if self.skipped == 0 => Some(UndoPlan::Replace),
+            None => None,
+        }
+    }
+}
+
+/// Per-member backups, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct BackupStore {
+    backups: Vec<PlaylistBackup>,
+}
+
+impl BackupStore {
+    pub fn push(&mut self, backup: PlaylistBackup) {
+        let playlist_id = backup.playlist_id.clone();
+        self.backups.push(backup);
+
+        let kept = self
+            .backups
+            .iter()
+            .filter(|b| b.playlist_id == playlist_id)
+            .count();
+        let mut to_drop = kept.saturating_sub(MAX_BACKUPS_PER_PLAYLIST);
+        self.backups.retain(|b| {
+            if to_drop > 0 && b.playlist_id == playlist_id {
+                to_drop -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Backups of one playlist, newest first (so #1 is the latest)
+    pub fn for_playlist(&self, playlist_id: &str) -> Vec<&PlaylistBackup> {
+        self.backups
+            .iter()
+            .rev()
+            .filter(|b| b.playlist_id == playlist_id)
+            .collect()
+    }
+
+    /// ID of the backed up playlist last known as `name` (ignoring case), so
+    /// backups stay reachable after the playlist was deleted
+    pub fn playlist_named(&self, name: &str) -> Option<&str> {
+        let name = name.trim().to_lowercase();
+        self.backups
+            .iter()
+            .rev()
+            .find(|b| b.playlist_name.to_lowercase() == name)
+            .map(|b| b.playlist_id.as_str())
+    }
+
+    /// (ID, latest name) of every backed up playlist, most recently backed up
+    /// first
+    pub fn playlists(&self) -> Vec<(&str, &str)> {
+        let mut seen = Vec::new();
+        for b in self.backups.iter().rev() {
+            if !seen.iter().any(|(id, _)| *id == b.playlist_id) {
+                seen.push((b.playlist_id.as_str(), b.playlist_name.as_str()));
+            }
+        }
+        seen
+    }
+
+    /// Point the backups of a deleted playlist at the one it was restored
+    /// into, so later restores and undos go there
+    pub fn move_to(&mut self, old_id: &str, new_id: &str) {
+        for b in self.backups.iter_mut().filter(|b| b.playlist_id == old_id) {
+            b.playlist_id = new_id.to_string();
+        }
+    }
+
+    /// The backup of a playlist taken at `taken_at_ms` (Unix milliseconds),
+    /// which is how undo buttons refer to it
+    pub fn find(&self, playlist_id: &str, taken_at_ms: i64) -> Option<&PlaylistBackup> {
+        self.backups
+            .iter()
+            .find(|b| b.playlist_id == playlist_id && b.taken_at.timestamp_millis() == taken_at_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn backup(playlist_id: &str, n: u32) -> PlaylistBackup {
+        PlaylistBackup {
+            playlist_id: playlist_id.to_string(),
+            playlist_name: playlist_id.to_uppercase(),
+            snapshot_id: format!("snap{n}"),
+            track_ids: vec![format!("t{n}")],
+            skipped: 0,
+            reorder: None,
+            taken_at: Utc.with_ymd_and_hms(2024, 3, 15, n, 0, 0).unwrap(),
+            reason: "manual".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_newest_first_per_playlist() {
+        let mut store = BackupStore::default();
+        store.push(backup("a", 1));
+        store.push(backup("b", 2));
+        store.push(backup("a", 3));
+
+        let snapshots: Vec<&str> = store
+            .for_playlist("a")
+            .iter()
+            .map(|b| b.snapshot_id.as_str())
+            .collect();
+        assert_eq!(snapshots, vec!["snap3", "snap1"]);
+        assert!(store.for_playlist("c").is_empty());
+    }
+
+    #[test]
+    fn test_keeps_a_limited_number_per_playlist() {
+        let mut store = BackupStore::default();
+        store.push(backup("b", 0));
+        for n in 1..=MAX_BACKUPS_PER_PLAYLIST as u32 + 2 {
+            store.push(backup("a", n));
+        }
+
+        let kept = store.for_playlist("a");
+        assert_eq!(kept.len(), MAX_BACKUPS_PER_PLAYLIST);
+        assert_eq!(kept[0].snapshot_id, "snap7");
+        assert_eq!(kept[MAX_BACKUPS_PER_PLAYLIST - 1].snapshot_id, "snap3");
+        assert_eq!(store.for_playlist("b").len(), 1);
+    }
+
+    #[test]
+    fn test_lookup_by_name_and_move() {
+        let mut store = BackupStore::default();
+        store.push(backup("a", 1));
+        store.push(backup("b", 2));
+        store.push(backup("a", 3));
+
+        assert_eq!(store.playlist_named(" a "), Some("a"));
+        assert_eq!(store.playlist_named("c"), None);
+        assert_eq!(store.playlists(), vec![("a", "A"), ("b", "B")]);
+
+        store.move_to("a", "new");
+        assert!(store.for_playlist("a").is_empty());
+        assert_eq!(store.for_playlist("new").len(), 2);
+        assert_eq!(store.playlist_named("A"), Some("new"));
+    }
+
+    #[test]
+    fn test_find_and_undo_window() {
+        let mut store = BackupStore::default();
+        store.push(backup("a", 1));
+        store.push(backup("a", 2));
+
+        let taken_at = Utc.with_ymd_and_hms(2024, 3, 15, 1, 0, 0).unwrap();
+        let found = store.find("a", taken_at.timestamp_millis()).unwrap();
+        assert_eq!(found.snapshot_id, "snap1");
+        assert!(store.find("b", taken_at.timestamp_millis()).is_none());
+
+        assert!(found.can_undo(taken_at + Duration::minutes(9)));
+        assert!(!found.can_undo(taken_at + Duration::minutes(UNDO_WINDOW_MINUTES)));
+    }
+
+    #[test]
+    fn test_undo_plan_keeps_local_files_and_episodes() {
+        let mut removal = backup("a", 1);
+        assert_eq!(removal.undo_plan(), Some(UndoPlan::Replace));
+        // Replacing would delete the items the backup couldn't keep
+        removal.skipped = 2;
+        assert_eq!(removal.undo_plan(), None);
+
+        // A reorder is moved back, whatever the playlist holds
+        let mut reorder = backup("a", 2);
+        reorder.skipped = 2;
+        reorder.reorder = Some(vec![2, 0, 3, 1]);
+        let Some(UndoPlan::MoveBack(moves)) = reorder.undo_plan() else {
+            panic!("expected moves");
+        };
+        let original = ["w", "x", "y", "z"];
+        let mut items: Vec<&str> = [2, 0, 3, 1].map(|old| original[old]).to_vec();
+        for (from, to) in moves {
+            let item = items.remove(from);
+            items.insert(to, item);
+        }
+        assert_eq!(items, original);
+    }
+}
@@ -0,0 +1,102 @@
+//! Two-person blend: a 50/50 mix of both users' favorites
+
+use std::collections::HashSet;
+
+/// One user's taste: top tracks best first, plus saved track IDs
+#[derive(Debug, Clone, Default)]
+pub struct Taste {
+    pub top: Vec<String>,
+    pub saved: HashSet<String>,
+}
+
+impl Taste {
+    fn likes(&self, id: &str) -> bool {
+        self.saved.contains(id) || self.top.iter().any(|top| top == id)
+    }
+}
+
+/// Pure function: `own`'s top tracks ranked by affinity for the pair.
+/// Rank gives up to 1.0, and a track the other user also likes gets +1.0,
+/// so shared favorites come first.
+pub fn affinity_ranking(own: &Taste, other: &Taste) -> Vec<(String, f32)> {
+    let len = own.top.len().max(1) as f32;
+    let mut ranked: Vec<(String, f32)> = own
+        .top
+        .iter()
+        .enumerate()
+        .map(|(rank, id)| {
+            let shared = if other.likes(id) { 1.0 } else { 0.0 };
+            (id.clone(), 1.0 - rank as f32 / len + shared)
+        })
+        .collect();
+    // Stable sort: equal scores keep the user's own order
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+/// Pure function: alternate picks from both rankings, skipping duplicates,
+/// until `size` tracks are chosen or both run out
+pub fn blend(first: &Taste, second: &Taste, size: usize) -> Vec<String> {
+    let mut picks = [
+        affinity_ranking(first, second).into_iter(),
+        affinity_ranking(second, first).into_iter(),
+    ];
+    let mut chosen = Vec::new();
+    let mut seen = HashSet::new();
+    let mut exhausted = [false, false];
+
+    let mut turn = 0;
+    while chosen.len() < size && !(exhausted[0] && exhausted[1]) {
+        if !exhausted[turn] {
+            match picks[turn].find(|(id, _)| !seen.contains(id)) {
+                Some((id, _)) => {
+                    seen.insert(id.clone());
+                    chosen.push(id);
+                }
+                None => exhausted[turn] = true,
+            }
+        }
+        turn = 1 - turn;
+    }
+
+    chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn taste(top: &[&str], saved: &[&str]) -> Taste {
+        Taste {
+            top: top.iter().map(|s| s.to_string()).collect(),
+            saved: saved.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_shared_tracks_rank_first() {
+        let own = taste(&["a", "b", "c"], &[]);
+        let other = taste(&["x"], &["c"]);
+        let ranked = affinity_ranking(&own, &other);
+
+        assert_eq!(ranked[0].0, "c");
+        assert_eq!(ranked[1].0, "a");
+    }
+
+    #[test]
+    fn test_blend_alternates_without_duplicates() {
+        let first = taste(&["a", "b", "c"], &[]);
+        let second = taste(&["x", "y", "a"], &[]);
+        let mixed = blend(&first, &second, 4);
+
+        // "a" is shared so both sides lead with it; the second side moves on
+        assert_eq!(mixed, vec!["a", "x", "b", "y"]);
+    }
+
+    #[test]
+    fn test_blend_fills_from_the_longer_side() {
+        let first = taste(&["a"], &[]);
+        let second = taste(&["x", "y", "z"], &[]);
+        assert_eq!(blend(&first, &second, 10), vec!["a", "x", "y", "z"]);
+    }
+}
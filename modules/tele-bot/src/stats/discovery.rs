@@ -0,0 +1,102 @@
+//! How much of recent listening went to artists outside the user's favorites
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Discovery {
+    pub total_plays: usize,
+    /// Plays of artists not among the favorites
+    pub novel_plays: usize,
+    /// Distinct favorites that were played
+    pub known_artists: usize,
+    /// New artists with their play count, most played first
+    pub new_artists: Vec<(String, usize)>,
+}
+
+impl Discovery {
+    /// Share of plays that went to new artists (0.0 to 1.0)
+    pub fn novelty_ratio(&self) -> f32 {
+        if self.total_plays == 0 {
+            0.0
+        } else {
+            self.novel_plays as f32 / self.total_plays as f32
+        }
+    }
+
+    /// The new artist played the most, if any
+    pub fn top_discovery(&self) -> Option<&(String, usize)> {
+        self.new_artists.first()
+    }
+}
+
+/// Pure function: split plays (by lead artist) into favorites and new
+/// artists. Ties among new artists keep the order they were first heard in.
+pub fn discovery<'a>(
+    lead_artists: impl IntoIterator<Item = &'a str>,
+    favorites: &HashSet<&str>,
+) -> Discovery {
+    let mut result = Discovery::default();
+    let mut known = HashSet::new();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut first_heard = Vec::new();
+
+    for artist in lead_artists {
+        result.total_plays += 1;
+        if favorites.contains(artist) {
+            known.insert(artist);
+            continue;
+        }
+        result.novel_plays += 1;
+        let count = counts.entry(artist).or_insert(0);
+        if *count == 0 {
+            first_heard.push(artist);
+        }
+        *count += 1;
+    }
+
+    result.known_artists = known.len();
+    result.new_artists = first_heard
+        .into_iter()
+        .map(|artist| (artist.to_string(), counts[artist]))
+        .collect();
+    // Stable sort keeps first-heard order among equal counts
+    result.new_artists.sort_by_key(|(_, count)| Reverse(*count));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_splits_new_from_known() {
+        let favorites: HashSet<&str> = ["A", "B"].into();
+        let plays = ["A", "X", "Y", "A", "Y", "B", "Z"];
+        let result = discovery(plays, &favorites);
+
+        assert_eq!(result.total_plays, 7);
+        assert_eq!(result.novel_plays, 4);
+        assert_eq!(result.known_artists, 2);
+        assert_eq!(
+            result.new_artists,
+            vec![
+                ("Y".to_string(), 2),
+                ("X".to_string(), 1),
+                ("Z".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            result.top_discovery().map(|(name, _)| name.as_str()),
+            Some("Y")
+        );
+        assert!((result.novelty_ratio() - 4.0 / 7.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_no_plays_means_no_novelty() {
+        let result = discovery([], &HashSet::new());
+        assert_eq!(result.novelty_ratio(), 0.0);
+        assert_eq!(result.top_discovery(), None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Bulk-filling a playlist from the user's own lists
+
+use std::collections::HashSet;
+
+use crate::detector::genre::Genre;
+use crate::detector::mood::Mood;
+
+pub const DEFAULT_LIMIT: usize = 20;
+pub const MAX_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillSource {
+    TopTracks,
+    Recent,
+    Liked,
+}
+
+impl FillSource {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "top" | "top_tracks" => Some(FillSource::TopTracks),
+            "recent" | "recently_played" => Some(FillSource::Recent),
+            "liked" | "saved" => Some(FillSource::Liked),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FillSource::TopTracks => "your top tracks",
+            FillSource::Recent => "your recently played",
+            FillSource::Liked => "your Liked Songs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillRequest {
+    pub playlist: String,
+    pub source: FillSource,
+    pub mood: Option<Mood>,
+    /// Only keep tracks the detector puts in this genre
+    pub genre: Option<Genre>,
+    pub limit: usize,
+}
+
+/// Pure function: parse `<playlist> from <top|recent|liked> [mood] [genre] [limit]`,
+/// e.g. "Evening Chill from liked calm jazz 15". The last " from " splits, so
+/// playlist names may contain the word. The error has one line per problem.
+pub fn parse_fill_args(input: &str) -> Result<FillRequest, String> {
+    let Some((playlist, rest)) = input.rsplit_once(" from ") else {
+        return Err(
+            "• source: missing, add \"from top\", \"from recent\" or \"from liked\"".to_string(),
+        );
+    };
+    let playlist = playlist.trim();
+    let mut words = rest.split_whitespace();
+    let mut problems = Vec::new();
+    if playlist.is_empty() {
+        problems.push("• playlist: missing name".to_string());
+    }
+    let source = match words.next() {
+        Some(word) => FillSource::parse(word).ok_or_else(|| word.to_string()),
+        None => Err(String::new()),
+    };
+    if let Err(word) = &source {
+        problems.push(format!("• source \"{}\": use top, recent or liked", word));
+    }
+
+    let mut mood = None;
+    let mut genre = None;
+    let mut limit = DEFAULT_LIMIT;
+    for word in words {
+        if let Ok(n) = word.parse::<i64>() {
+            if (1..=MAX_LIMIT as i64).contains(&n) {
+                limit = n as usize;
+            } else {
+                problems.push(format!("• count {}: use 1–{}", n, MAX_LIMIT));
+            }
+        } else if let Some(parsed) = Mood::parse(word) {
+            mood = Some(parsed);
+        } else if let Some(parsed) = Genre::parse(word) {
+            genre = Some(parsed);
+        } else {
+            problems.push(format!("• \"{}\": not a count, mood or genre", word));
+        }
+    }
+
+    match source {
+        Ok(source) if problems.is_empty() => Ok(FillRequest {
+            playlist: playlist.to_string(),
+            source,
+            mood,
+            genre,
+            limit,
+        }),
+        _ => Err(problems.join("\n")),
+    }
+}
+
+/// Tracks to add to a playlist and how many candidates were left out
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddPlan {
+    pub ids: Vec<String>,
+    /// Already in the playlist or repeated in the candidates
+    pub skipped: usize,
+}
+
+/// Pure function: the first `limit` candidates, in candidate order. With
+/// `dedupe`, tracks already in the playlist and repeats are skipped and
+/// counted until the limit is reached.
+pub fn plan_add(
+    candidates: &[String],
+    existing: &HashSet<String>,
+    dedupe: bool,
+    limit: usize,
+) -> AddPlan {
+    let mut seen = HashSet::new();
+    let mut plan = AddPlan::default();
+    for id in candidates {
+        if plan.ids.len() == limit {
+            break;
+        }
+        if dedupe && (existing.contains(id) || !seen.insert(id.as_str())) {
+            plan.skipped += 1;
+        } else {
+            plan.ids.push(id.clone());
+        }
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fill_args() {
+        assert_eq!(
+            parse_fill_args("Songs from Home from liked calm 15"),
+            Ok(FillRequest {
+                playlist: "Songs from Home".to_string(),
+                source: FillSource::Liked,
+                mood: Some(Mood::Calm),
+                genre: None,
+                limit: 15,
+            })
+        );
+        let top = parse_fill_args("Gym from top").unwrap();
+        assert_eq!(top.source, FillSource::TopTracks);
+        assert_eq!(top.mood, None);
+        assert_eq!(top.genre, None);
+        assert_eq!(top.limit, DEFAULT_LIMIT);
+    }
+
+    #[test]
+    fn test_parse_fill_args_genre() {
+        let request = parse_fill_args("Gym from liked Hip-Hop energetic 30").unwrap();
+        assert_eq!(request.genre, Some(Genre::HipHop));
+        assert_eq!(request.mood, Some(Mood::Energetic));
+        assert_eq!(request.limit, 30);
+        assert_eq!(
+            parse_fill_args("Gym from top lofi").unwrap().genre,
+            Some(Genre::LoFi)
+        );
+        // The detector's fallback can't be asked for
+        assert!(parse_fill_args("Gym from top unknown").is_err());
+    }
+
+    #[test]
+    fn test_parse_fill_args_reports_each_problem() {
+        assert!(parse_fill_args("Gym").unwrap_err().contains("source"));
+        assert_eq!(
+            parse_fill_args("Gym from radio"),
+            Err("• source \"radio\": use top, recent or liked".to_string())
+        );
+        assert_eq!(
+            parse_fill_args("Gym from top 500"),
+            Err("• count 500: use 1–100".to_string())
+        );
+        assert_eq!(
+            parse_fill_args("Gym from top grumpy"),
+            Err("• \"grumpy\": not a count, mood or genre".to_string())
+        );
+        assert!(parse_fill_args(" from top")
+            .unwrap_err()
+            .contains("playlist"));
+
+        let problems = parse_fill_args(" from radio grumpy 0").unwrap_err();
+        assert_eq!(problems.lines().count(), 4);
+    }
+
+    #[test]
+    fn test_plan_add_skips_existing_and_repeats() {
+        let candidates: Vec<String> = ["a", "b", "a", "c", "d"].map(String::from).to_vec();
+        let existing: HashSet<String> = ["b".to_string()].into();
+
+        let plan = plan_add(&candidates, &existing, true, 2);
+        assert_eq!(plan.ids, vec!["a", "c"]);
+        assert_eq!(plan.skipped, 2);
+        assert_eq!(
+            plan_add(&candidates, &existing, true, 10).ids,
+            vec!["a", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn test_plan_add_can_keep_duplicates() {
+        let candidates: Vec<String> = ["a", "b", "a"].map(String::from).to_vec();
+        let existing: HashSet<String> = ["b".to_string()].into();
+
+        let plan = plan_add(&candidates, &existing, false, usize::MAX);
+        assert_eq!(plan.ids, candidates);
+        assert_eq!(plan.skipped, 0);
+    }
+}
@@ -0,0 +1,58 @@
+//! Taste overlap between members of a group chat
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// Pure function: items that appear in at least `min_members` of the lists,
+/// with how many lists contain them. Duplicates within one list count once.
+/// Most shared first; ties keep the order items were first seen in.
+pub fn shared_items(lists: &[Vec<String>], min_members: usize) -> Vec<(String, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut first_seen: Vec<&str> = Vec::new();
+
+    for list in lists {
+        let mut in_this_list = HashSet::new();
+        for item in list.iter().map(String::as_str) {
+            if !in_this_list.insert(item) {
+                continue;
+            }
+            let count = counts.entry(item).or_insert(0);
+            if *count == 0 {
+                first_seen.push(item);
+            }
+            *count += 1;
+        }
+    }
+
+    let mut shared: Vec<(String, usize)> = first_seen
+        .into_iter()
+        .map(|item| (item.to_string(), counts[item]))
+        .filter(|(_, count)| *count >= min_members)
+        .collect();
+    // Stable sort keeps first-seen order among equal counts
+    shared.sort_by_key(|(_, count)| Reverse(*count));
+    shared
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_shared_items_counts_members_not_repeats() {
+        let lists = vec![
+            list(&["a", "b", "b", "c"]),
+            list(&["b", "c", "d"]),
+            list(&["c", "e"]),
+        ];
+        assert_eq!(
+            shared_items(&lists, 2),
+            vec![("c".to_string(), 3), ("b".to_string(), 2)]
+        );
+        assert!(shared_items(&lists, 4).is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+//! Camelot wheel notation for harmonic mixing
+
+/// A key on the Camelot wheel: 1–12 around the circle of fifths, "A" for
+/// minor and "B" for major
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Camelot {
+    pub number: u8,
+    pub minor: bool,
+}
+
+impl Camelot {
+    /// From Spotify's pitch class (0 = C, -1 = unknown) and mode
+    pub fn from_key(pitch: i32, minor: bool) -> Option<Self> {
+        if !(0..12).contains(&pitch) {
+            return None;
+        }
+        // A minor key sits on the same number as its relative major
+        let major_pitch = if minor { (pitch + 3) % 12 } else { pitch };
+        let number = ((major_pitch * 7) % 12 + 7) % 12 + 1;
+        Some(Camelot {
+            number: number as u8,
+            minor,
+        })
+    }
+
+    /// Pitch class of the key's tonic (0 = C), the inverse of [`Camelot::from_key`]
+    pub fn pitch(&self) -> i32 {
+        // 7 is its own inverse modulo 12
+        let major_pitch = (7 * (self.number as i32 - 8)).rem_euclid(12);
+        if self.minor {
+            (major_pitch + 9) % 12
+        } else {
+            major_pitch
+        }
+    }
+
+    /// e.g. "8A"
+    pub fn code(&self) -> String {
+        format!("{}{}", self.number, if self.minor { 'A' } else { 'B' })
+    }
+
+    /// Pure function: steps around the wheel, plus one for switching between
+    /// minor and major on different numbers. 0 for the same key or its
+    /// relative, 1 for a compatible neighbour.
+    pub fn distance(&self, other: &Camelot) -> u8 {
+        let step = (self.number as i32 - other.number as i32).rem_euclid(12);
+        let around = step.min(12 - step) as u8;
+        if around > 0 && self.minor != other.minor {
+            around + 1
+        } else {
+            around
+        }
+    }
+
+    /// Pure function: keys that mix without clashing. The same key, its
+    /// relative major/minor, or one step around the wheel in the same mode.
+    pub fn compatible(&self, other: &Camelot) -> bool {
+        if self.number == other.number {
+            return true;
+        }
+        let step = (self.number as i32 - other.number as i32).rem_euclid(12);
+        self.minor == other.minor && (step == 1 || step == 11)
+    }
+}
+
+/// Pure function: number of adjacent pairs whose keys clash. Tracks without
+/// a key don't count either way.
+pub fn clashes(keys: &[Option<Camelot>]) -> usize {
+    keys.windows(2)
+        .filter(|pair| match (pair[0], pair[1]) {
+            (Some(a), Some(b)) => !a.compatible(&b),
+            _ => false,
+        })
+        .count()
+}
+
+/// Pure function: order (as indices into `keys`) that avoids key clashes
+/// while staying close to the given order. Starting from the first track,
+/// each next one is the closest key among the next `lookahead` remaining
+/// tracks, earliest on ties. Tracks without a key keep their relative order
+/// after the rest.
+pub fn harmonic_order(keys: &[Option<Camelot>], lookahead: usize) -> Vec<usize> {
+    let mut remaining: Vec<usize> = (0..keys.len()).filter(|i| keys[*i].is_some()).collect();
+    let unknown = (0..keys.len()).filter(|i| keys[*i].is_none());
+
+    let mut order: Vec<usize> = Vec::with_capacity(keys.len());
+    while !remaining.is_empty() {
+        let pick = match order.last().and_then(|last| keys[*last]) {
+            Some(last) => remaining
+                .iter()
+                .take(lookahead.max(1))
+                .enumerate()
+                .filter_map(|(pos, i)| Some((pos, last.distance(&keys[*i]?))))
+                .min_by_key(|(pos, distance)| (*distance, *pos))
+                .map_or(0, |(pos, _)| pos),
+            None => 0,
+        };
+        order.push(remaining.remove(pick));
+    }
+    order.extend(unknown);
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: &str) -> Camelot {
+        let (number, letter) = code.split_at(code.len() - 1);
+        Camelot {
+            number: number.parse().unwrap(),
+            minor: letter == "A",
+        }
+    }
+
+    #[test]
+    fn test_from_key() {
+        assert_eq!(Camelot::from_key(0, false), Some(key("8B"))); // C
+        assert_eq!(Camelot::from_key(9, true), Some(key("8A"))); // A minor
+        assert_eq!(Camelot::from_key(7, false), Some(key("9B"))); // G
+        assert_eq!(Camelot::from_key(11, false), Some(key("1B"))); // B
+        assert_eq!(Camelot::from_key(0, true), Some(key("5A"))); // C minor
+        assert_eq!(Camelot::from_key(8, true), Some(key("1A"))); // G♯ minor
+        assert_eq!(Camelot::from_key(-1, false), None);
+    }
+
+    #[test]
+    fn test_pitch_round_trips() {
+        for pitch in 0..12 {
+            for minor in [false, true] {
+                assert_eq!(Camelot::from_key(pitch, minor).unwrap().pitch(), pitch);
+            }
+        }
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(key("8A").distance(&key("8A")), 0);
+        assert_eq!(key("8A").distance(&key("8B")), 0);
+        assert_eq!(key("8A").distance(&key("9A")), 1);
+        assert_eq!(key("1A").distance(&key("12A")), 1);
+        assert_eq!(key("8A").distance(&key("9B")), 2);
+        assert_eq!(key("2B").distance(&key("8B")), 6);
+        assert_eq!(key("8A").code(), "8A");
+    }
+
+    #[test]
+    fn test_harmonic_order_removes_clashes() {
+        let keys = vec![
+            Some(key("8A")),
+            Some(key("2B")),
+            Some(key("9A")),
+            None,
+            Some(key("3B")),
+            Some(key("10A")),
+        ];
+        assert_eq!(clashes(&keys), 3);
+
+        let order = harmonic_order(&keys, keys.len());
+        assert_eq!(order, vec![0, 2, 5, 1, 4, 3]);
+        let sorted: Vec<Option<Camelot>> = order.iter().map(|i| keys[*i]).collect();
+        assert_eq!(clashes(&sorted), 1);
+    }
+
+    #[test]
+    fn test_harmonic_order_lookahead_keeps_order_close() {
+        let keys = vec![Some(key("8A")), Some(key("2B")), Some(key("9A"))];
+        // Only the very next track is in reach: the order is kept
+        assert_eq!(harmonic_order(&keys, 1), vec![0, 1, 2]);
+        assert!(harmonic_order(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_compatible_keys() {
+        assert!(key("8A").compatible(&key("8A")));
+        assert!(key("8A").compatible(&key("8B")));
+        assert!(key("8A").compatible(&key("9A")));
+        assert!(key("8A").compatible(&key("7A")));
+        assert!(key("12B").compatible(&key("1B")));
+        assert!(!key("8A").compatible(&key("9B")));
+        assert!(!key("8A").compatible(&key("10A")));
+    }
+}
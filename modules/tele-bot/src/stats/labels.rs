@@ -0,0 +1,48 @@
+//! Record labels and release eras of what the user listens to
+
+use std::collections::BTreeMap;
+
+/// Pure function: year of a Spotify release date, which is "2019",
+/// "2019-05" or "2019-05-17" depending on its precision
+pub fn release_year(date: &str) -> Option<i32> {
+    let year = date.split('-').next()?;
+    if year.len() != 4 {
+        return None;
+    }
+    year.parse().ok()
+}
+
+/// Pure function: share of each decade (0.0 to 1.0), oldest first
+pub fn decade_breakdown(years: &[i32]) -> Vec<(i32, f32)> {
+    let mut counts: BTreeMap<i32, u32> = BTreeMap::new();
+    for year in years {
+        *counts.entry(year - year.rem_euclid(10)).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .map(|(decade, count)| (decade, count as f32 / years.len() as f32))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_year_any_precision() {
+        assert_eq!(release_year("2019"), Some(2019));
+        assert_eq!(release_year("1987-05"), Some(1987));
+        assert_eq!(release_year("2001-09-11"), Some(2001));
+        assert_eq!(release_year("0000"), Some(0));
+        assert_eq!(release_year(""), None);
+        assert_eq!(release_year("19-05-01"), None);
+    }
+
+    #[test]
+    fn test_decade_breakdown() {
+        assert!(decade_breakdown(&[]).is_empty());
+
+        let breakdown = decade_breakdown(&[1999, 2015, 1991, 2010]);
+        assert_eq!(breakdown, vec![(1990, 0.5), (2010, 0.5)]);
+    }
+}
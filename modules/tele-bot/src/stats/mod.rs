@@ -0,0 +1,55 @@
+pub mod archive;
+pub mod backups;
+pub mod blend;
+pub mod discovery;
+pub mod fill;
+pub mod group;
+pub mod harmonic;
+pub mod labels;
+pub mod moods;
+pub mod party;
+pub mod patterns;
+pub mod playlist_analysis;
+pub mod rediscover;
+pub mod releases;
+pub mod shuffle;
+pub mod similarity;
+pub mod skips;
+pub mod sleep;
+pub mod staleness;
+pub mod streak;
+pub mod tempo;
+pub mod track_compare;
+
+use std::collections::HashMap;
+
+/// Pure function: share of each distinct item (0.0 to 1.0), e.g. genres,
+/// moods or record labels, most common first. Ties are broken alphabetically.
+pub fn share_breakdown<'a>(items: impl IntoIterator<Item = &'a str>) -> Vec<(String, f32)> {
+    let mut counts: HashMap<&str, u32> = HashMap::new();
+    let mut total = 0;
+    for item in items {
+        *counts.entry(item).or_insert(0) += 1;
+        total += 1;
+    }
+
+    let mut breakdown: Vec<(String, f32)> = counts
+        .into_iter()
+        .map(|(item, count)| (item.to_string(), count as f32 / total as f32))
+        .collect();
+    breakdown.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    breakdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_breakdown() {
+        let breakdown = share_breakdown(["pop", "rock", "pop", "indie"]);
+        assert_eq!(breakdown[0], ("pop".to_string(), 0.5));
+        assert_eq!(breakdown[1], ("indie".to_string(), 0.25));
+        assert!(share_breakdown([]).is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+//! Mood of each play over a single day
+
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::HashMap;
+
+use crate::detector::classifier::backend;
+use crate::detector::genre::AudioFeatures;
+use crate::detector::mood::Mood;
+use crate::models::spotify::Play;
+
+#[derive(Debug, Clone)]
+pub struct MoodPoint<'a> {
+    pub play: &'a Play,
+    pub mood: Mood,
+    pub energy: f32,
+}
+
+impl MoodPoint<'_> {
+    pub fn played_at(&self) -> DateTime<Utc> {
+        self.play.played_at
+    }
+}
+
+/// Pure function: plays on `day` (UTC) with their detected mood, oldest
+/// first. Plays without audio features are left out.
+pub fn day_timeline<'a>(
+    plays: &'a [Play],
+    features: &HashMap<String, AudioFeatures>,
+    day: NaiveDate,
+) -> Vec<MoodPoint<'a>> {
+    let mut timeline: Vec<MoodPoint> = plays
+        .iter()
+        .filter(|play| play.played_at.date_naive() == day)
+        .filter_map(|play| {
+            let features = features.get(play.track.id.as_deref()?)?;
+            Some(MoodPoint {
+                play,
+                mood: backend().classify_mood(*features).mood,
+                energy: features.energy,
+            })
+        })
+        .collect();
+    timeline.sort_by_key(MoodPoint::played_at);
+    timeline
+}
+
+/// Pure function: the most frequent mood; ties go to the one heard first
+pub fn dominant_mood(timeline: &[MoodPoint]) -> Option<Mood> {
+    let mut counts: Vec<(Mood, usize)> = Vec::new();
+    for point in timeline {
+        match counts.iter_mut().find(|(mood, _)| *mood == point.mood) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((point.mood, 1)),
+        }
+    }
+    // max_by_key keeps the last maximum, so search from the back
+    counts
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(mood, _)| mood)
+}
+
+/// Pure function: one emoji per play, e.g. "😌😌⚡😊"
+pub fn render_strip(timeline: &[MoodPoint]) -> String {
+    timeline.iter().map(|point| point.mood.emoji()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::spotify::Track;
+    use chrono::TimeZone;
+
+    fn play(id: &str, hour: u32, day: u32) -> Play {
+        Play {
+            track: Track {
+                id: Some(id.to_string()),
+                name: id.to_string(),
+                artists: vec![],
+                album: String::new(),
+                album_id: None,
+                release_date: None,
+                album_art: None,
+                duration_ms: 0,
+                explicit: false,
+                popularity: 0,
+                playable: true,
+                linked_from: None,
+            },
+            played_at: Utc.with_ymd_and_hms(2024, 3, day, hour, 0, 0).unwrap(),
+            context: None,
+        }
+    }
+
+    fn features(energy: f32, valence: f32, tempo: f32) -> AudioFeatures {
+        AudioFeatures {
+            tempo,
+            energy,
+            valence,
+            danceability: 0.5,
+            acousticness: 0.2,
+            instrumentalness: 0.0,
+            loudness: -6.0,
+            speechiness: 0.05,
+        }
+    }
+
+    #[test]
+    fn test_timeline_keeps_the_day_in_order() {
+        let plays = vec![
+            play("late", 20, 15),
+            play("early", 8, 15),
+            play("yesterday", 23, 14),
+            play("unknown", 12, 15),
+        ];
+        let features: HashMap<String, AudioFeatures> = [
+            ("late", features(0.9, 0.8, 140.0)),
+            ("early", features(0.2, 0.3, 70.0)),
+            ("yesterday", features(0.5, 0.5, 100.0)),
+        ]
+        .into_iter()
+        .map(|(id, f)| (id.to_string(), f))
+        .collect();
+
+        let day = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let timeline = day_timeline(&plays, &features, day);
+        let names: Vec<&str> = timeline
+            .iter()
+            .map(|p| p.play.track.name.as_str())
+            .collect();
+
+        assert_eq!(names, vec!["early", "late"]);
+        assert_eq!(timeline[1].energy, 0.9);
+        assert_eq!(
+            render_strip(&timeline),
+            format!("{}{}", timeline[0].mood.emoji(), timeline[1].mood.emoji())
+        );
+    }
+
+    #[test]
+    fn test_dominant_mood_prefers_first_heard_on_ties() {
+        let plays = [play("a", 1, 15), play("b", 2, 15), play("c", 3, 15)];
+        let point = |idx: usize, mood| MoodPoint {
+            play: &plays[idx],
+            mood,
+            energy: 0.5,
+        };
+
+        let tied = vec![point(0, Mood::Calm), point(1, Mood::Happy)];
+        assert_eq!(dominant_mood(&tied), Some(Mood::Calm));
+
+        let mostly_happy = vec![
+            point(0, Mood::Calm),
+            point(1, Mood::Happy),
+            point(2, Mood::Happy),
+        ];
+        assert_eq!(dominant_mood(&mostly_happy), Some(Mood::Happy));
+        assert_eq!(dominant_mood(&[]), None);
+    }
+}
@@ -0,0 +1,174 @@
+//! Building a party playlist that keeps people dancing
+
+use std::collections::HashSet;
+
+use crate::stats::harmonic::Camelot;
+
+pub const DEFAULT_PARTY_MINUTES: u32 = 60;
+pub const MIN_PARTY_MINUTES: u32 = 10;
+pub const MAX_PARTY_MINUTES: u32 = 240;
+/// Adjacent tracks this close in tempo blend without a key match
+pub const TEMPO_STEP: f32 = 8.0;
+/// How many of the most danceable remaining tracks are considered for a
+/// smooth transition before settling for the most danceable one
+const LOOKAHEAD: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartyCandidate {
+    pub id: String,
+    pub duration_ms: u32,
+    pub danceability: f32,
+    pub tempo: f32,
+    pub key: Option<Camelot>,
+}
+
+impl PartyCandidate {
+    /// Pure function: whether this track can be followed by `next` without
+    /// a jarring jump in tempo or key
+    pub fn flows_into(&self, next: &PartyCandidate) -> bool {
+        let keys_match = match (self.key, next.key) {
+            (Some(a), Some(b)) => a.compatible(&b),
+            _ => false,
+        };
+        keys_match || (self.tempo - next.tempo).abs() <= TEMPO_STEP
+    }
+}
+
+/// Parse `[minutes]`, defaulting to an hour. The error says what's wrong
+/// with the input.
+pub fn parse_minutes(input: &str) -> Result<u32, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(DEFAULT_PARTY_MINUTES);
+    }
+    let minutes: u32 = input
+        .parse()
+        .map_err(|_| format!("• \"{}\": not a number of minutes", input))?;
+    if (MIN_PARTY_MINUTES..=MAX_PARTY_MINUTES).contains(&minutes) {
+        Ok(minutes)
+    } else {
+        Err(format!(
+            "• {} minutes: use {}–{}",
+            minutes, MIN_PARTY_MINUTES, MAX_PARTY_MINUTES
+        ))
+    }
+}
+
+/// Pure function: indices into `candidates` in play order, adding up to at
+/// least `target_ms` when there are enough tracks. Opens with the most
+/// danceable track; each next one is the most danceable of the top few that
+/// flows from the previous, or simply the most danceable left.
+pub fn build_party(candidates: &[PartyCandidate], target_ms: u64) -> Vec<usize> {
+    let mut seen = HashSet::new();
+    let mut pool: Vec<usize> = (0..candidates.len())
+        .filter(|i| seen.insert(candidates[*i].id.as_str()))
+        .collect();
+    pool.sort_by(|a, b| {
+        candidates[*b]
+            .danceability
+            .total_cmp(&candidates[*a].danceability)
+            .then(a.cmp(b))
+    });
+
+    let mut order: Vec<usize> = Vec::new();
+    let mut total_ms: u64 = 0;
+    while total_ms < target_ms && !pool.is_empty() {
+        let pick = match order.last() {
+            Some(&last) => pool
+                .iter()
+                .take(LOOKAHEAD)
+                .position(|i| candidates[last].flows_into(&candidates[*i]))
+                .or_else(|| closest_key(candidates, &pool, last))
+                .unwrap_or(0),
+            None => 0,
+        };
+        let next = pool.remove(pick);
+        total_ms += candidates[next].duration_ms as u64;
+        order.push(next);
+    }
+    order
+}
+
+/// Position in `pool` (within the lookahead) of the track whose key is
+/// nearest to `last`'s on the Camelot wheel, when keys are known
+fn closest_key(candidates: &[PartyCandidate], pool: &[usize], last: usize) -> Option<usize> {
+    let key = candidates[last].key?;
+    pool.iter()
+        .take(LOOKAHEAD)
+        .enumerate()
+        .filter_map(|(pos, i)| Some((pos, key.distance(&candidates[*i].key?))))
+        .min_by_key(|(pos, distance)| (*distance, *pos))
+        .map(|(pos, _)| pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(id: &str, danceability: f32, tempo: f32, key: Option<&str>) -> PartyCandidate {
+        PartyCandidate {
+            id: id.to_string(),
+            duration_ms: 200_000,
+            danceability,
+            tempo,
+            key: key.map(|code| {
+                let (number, letter) = code.split_at(code.len() - 1);
+                Camelot {
+                    number: number.parse().unwrap(),
+                    minor: letter == "A",
+                }
+            }),
+        }
+    }
+
+    #[test]
+    fn test_parse_minutes() {
+        assert_eq!(parse_minutes(""), Ok(DEFAULT_PARTY_MINUTES));
+        assert_eq!(parse_minutes(" 90 "), Ok(90));
+        assert!(parse_minutes("5").unwrap_err().contains("5 minutes"));
+        assert!(parse_minutes("1000").unwrap_err().contains("1000 minutes"));
+        assert!(parse_minutes("soon").unwrap_err().contains("\"soon\""));
+    }
+
+    #[test]
+    fn test_flows_by_tempo_or_key() {
+        let a = candidate("a", 0.9, 120.0, Some("8A"));
+        assert!(a.flows_into(&candidate("b", 0.9, 127.0, Some("3B"))));
+        assert!(a.flows_into(&candidate("c", 0.9, 150.0, Some("9A"))));
+        assert!(!a.flows_into(&candidate("d", 0.9, 150.0, Some("3B"))));
+        assert!(!a.flows_into(&candidate("e", 0.9, 150.0, None)));
+    }
+
+    #[test]
+    fn test_build_prefers_smooth_transitions() {
+        let candidates = vec![
+            candidate("opener", 0.95, 120.0, Some("8A")),
+            candidate("clash", 0.9, 170.0, Some("2B")),
+            candidate("smooth", 0.8, 124.0, None),
+            candidate("opener", 0.95, 120.0, Some("8A")),
+        ];
+        // Ten minutes is more than the three distinct tracks
+        let order = build_party(&candidates, 600_000);
+        assert_eq!(order, vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn test_build_falls_back_to_the_closest_key() {
+        let candidates = vec![
+            candidate("opener", 0.95, 100.0, Some("8A")),
+            candidate("far", 0.9, 170.0, Some("2B")),
+            candidate("near", 0.85, 60.0, Some("10A")),
+        ];
+        assert_eq!(build_party(&candidates, 600_000), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn test_build_stops_at_target_duration() {
+        let candidates: Vec<PartyCandidate> = (0..10)
+            .map(|i| candidate(&i.to_string(), 0.5 + i as f32 / 100.0, 120.0, None))
+            .collect();
+        let order = build_party(&candidates, 500_000);
+        assert_eq!(order, vec![9, 8, 7]);
+        assert!(build_party(&[], 500_000).is_empty());
+    }
+}
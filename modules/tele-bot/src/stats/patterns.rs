@@ -0,0 +1,179 @@
+//! Time-of-day and day-of-week listening pattern analysis
+
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use std::cmp::Reverse;
+
+/// Play counts bucketed by weekday (Monday first) and hour of day (UTC)
+#[derive(Debug, Clone)]
+pub struct ListeningPatterns {
+    pub matrix: [[u32; 24]; 7],
+    pub total_plays: u32,
+    pub most_active_hour: Option<u32>,
+    pub weekday_plays: u32,
+    pub weekend_plays: u32,
+}
+
+impl ListeningPatterns {
+    /// Share of plays that happened on Saturday or Sunday (0.0 to 1.0)
+    pub fn weekend_ratio(&self) -> f32 {
+        if self.total_plays == 0 {
+            0.0
+        } else {
+            self.weekend_plays as f32 / self.total_plays as f32
+        }
+    }
+
+    /// Total plays per hour of day, summed over all weekdays
+    pub fn hourly_totals(&self) -> [u32; 24] {
+        let mut totals = [0; 24];
+        for row in &self.matrix {
+            for (hour, count) in row.iter().enumerate() {
+                totals[hour] += count;
+            }
+        }
+        totals
+    }
+}
+
+/// Pure function: build the 7×24 play-count matrix from play timestamps
+pub fn analyze_patterns(played_at: &[DateTime<Utc>]) -> ListeningPatterns {
+    let mut matrix = [[0u32; 24]; 7];
+    let mut weekday_plays = 0;
+    let mut weekend_plays = 0;
+
+    for timestamp in played_at {
+        let day = timestamp.weekday().num_days_from_monday() as usize;
+        let hour = timestamp.hour() as usize;
+        matrix[day][hour] += 1;
+
+        if day >= 5 {
+            weekend_plays += 1;
+        } else {
+            weekday_plays += 1;
+        }
+    }
+
+    let mut patterns = ListeningPatterns {
+        matrix,
+        total_plays: played_at.len() as u32,
+        most_active_hour: None,
+        weekday_plays,
+        weekend_plays,
+    };
+
+    // Earliest hour wins ties so the result is deterministic
+    patterns.most_active_hour = patterns
+        .hourly_totals()
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .max_by_key(|(hour, count)| (**count, Reverse(*hour)))
+        .map(|(hour, _)| hour as u32);
+
+    patterns
+}
+
+/// Render a compact heatmap using emoji blocks, one row per weekday and
+/// one column per 3-hour window
+pub fn render_heatmap(patterns: &ListeningPatterns) -> String {
+    const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    const WINDOW: usize = 3;
+
+    let buckets: Vec<Vec<u32>> = patterns
+        .matrix
+        .iter()
+        .map(|row| row.chunks(WINDOW).map(|c| c.iter().sum()).collect())
+        .collect();
+    let max = buckets.iter().flatten().copied().max().unwrap_or(0);
+
+    let mut output = String::from("     00 03 06 09 12 15 18 21\n");
+    for (day, row) in DAYS.iter().zip(&buckets) {
+        output.push_str(day);
+        output.push(' ');
+        for &count in row {
+            output.push_str(heat_block(count, max));
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+fn heat_block(count: u32, max: u32) -> &'static str {
+    if count == 0 || max == 0 {
+        return "⬜";
+    }
+
+    let level = count as f32 / max as f32;
+    if level > 0.75 {
+        "🟥"
+    } else if level > 0.5 {
+        "🟧"
+    } else if level > 0.25 {
+        "🟨"
+    } else {
+        "🟩"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 15, 0).unwrap()
+    }
+
+    #[test]
+    fn test_matrix_buckets_by_weekday_and_hour() {
+        // 2024-01-01 is a Monday, 2024-01-06 a Saturday
+        let plays = vec![at(2024, 1, 1, 8), at(2024, 1, 1, 8), at(2024, 1, 6, 22)];
+        let result = analyze_patterns(&plays);
+
+        assert_eq!(result.matrix[0][8], 2);
+        assert_eq!(result.matrix[5][22], 1);
+        assert_eq!(result.total_plays, 3);
+    }
+
+    #[test]
+    fn test_weekday_weekend_split() {
+        let plays = vec![at(2024, 1, 2, 10), at(2024, 1, 6, 10), at(2024, 1, 7, 10)];
+        let result = analyze_patterns(&plays);
+
+        assert_eq!(result.weekday_plays, 1);
+        assert_eq!(result.weekend_plays, 2);
+        assert!((result.weekend_ratio() - 2.0 / 3.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_most_active_hour() {
+        let plays = vec![at(2024, 1, 1, 21), at(2024, 1, 2, 21), at(2024, 1, 3, 7)];
+        let result = analyze_patterns(&plays);
+        assert_eq!(result.most_active_hour, Some(21));
+    }
+
+    #[test]
+    fn test_most_active_hour_tie_prefers_earliest() {
+        let plays = vec![at(2024, 1, 1, 18), at(2024, 1, 1, 6)];
+        let result = analyze_patterns(&plays);
+        assert_eq!(result.most_active_hour, Some(6));
+    }
+
+    #[test]
+    fn test_empty_history() {
+        let result = analyze_patterns(&[]);
+        assert_eq!(result.total_plays, 0);
+        assert_eq!(result.most_active_hour, None);
+        assert_eq!(result.weekend_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_heatmap_has_one_row_per_day() {
+        let result = analyze_patterns(&[at(2024, 1, 1, 8)]);
+        let heatmap = render_heatmap(&result);
+
+        assert_eq!(heatmap.lines().count(), 8);
+        assert!(heatmap.contains("Mon ⬜⬜🟥"));
+    }
+}
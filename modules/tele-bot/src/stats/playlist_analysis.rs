@@ -0,0 +1,216 @@
+//! Aggregate statistics over the tracks of one playlist
+
+use crate::detector::genre::AudioFeatures;
+use crate::stats::similarity::{feature_centroid, sound_vector};
+
+/// Blocks used for the energy curve, lowest to highest
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Fewer tracks than this have no meaningful "rest of the playlist"
+pub const MIN_TRACKS_FOR_OUTLIERS: usize = 5;
+/// A track is an outlier when its distance from the centroid is this many
+/// standard deviations above the average distance...
+const OUTLIER_DEVIATIONS: f32 = 2.0;
+/// ...and far enough in absolute terms, so a very uniform playlist doesn't
+/// flag tracks that only differ a little
+const MIN_OUTLIER_DISTANCE: f32 = 0.35;
+
+/// How a track differs along each of the [`sound_vector`] dimensions,
+/// as (above the centroid, below the centroid)
+const DIFFERENCES: [(&str, &str); 6] = [
+    ("more energetic", "calmer"),
+    ("happier", "sadder"),
+    ("more danceable", "less danceable"),
+    ("more acoustic", "less acoustic"),
+    ("more instrumental", "more vocal"),
+    ("faster", "slower"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spread {
+    pub mean: f32,
+    /// Population standard deviation
+    pub stddev: f32,
+}
+
+/// Pure function: mean and standard deviation, `None` for no values
+pub fn spread(values: &[f32]) -> Option<Spread> {
+    if values.is_empty() {
+        return None;
+    }
+    let count = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / count;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f32>()
+        / count;
+    Some(Spread {
+        mean,
+        stddev: variance.sqrt(),
+    })
+}
+
+/// Pure function: playlist energy in playlist order, averaged into at most
+/// `points` consecutive buckets so long playlists still fit on one line
+pub fn energy_curve(energies: &[f32], points: usize) -> Vec<f32> {
+    if energies.is_empty() || points == 0 {
+        return Vec::new();
+    }
+    let buckets = points.min(energies.len());
+    (0..buckets)
+        .map(|bucket| {
+            let start = bucket * energies.len() / buckets;
+            let end = (bucket + 1) * energies.len() / buckets;
+            let chunk = &energies[start..end];
+            chunk.iter().sum::<f32>() / chunk.len() as f32
+        })
+        .collect()
+}
+
+/// Pure function: one block per value (0.0 to 1.0), e.g. "▁▃▆█▅"
+pub fn sparkline(values: &[f32]) -> String {
+    values
+        .iter()
+        .map(|value| {
+            let level = (value.clamp(0.0, 1.0) * (LEVELS.len() - 1) as f32).round();
+            LEVELS[level as usize]
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outlier {
+    /// Position in the slice given to [`find_outliers`]
+    pub index: usize,
+    /// Euclidean distance from the playlist centroid
+    pub distance: f32,
+    /// The dimension that differs most, e.g. "calmer"
+    pub difference: &'static str,
+}
+
+/// Pure function: tracks whose sound is far from the rest of the playlist,
+/// furthest first
+pub fn find_outliers(features: &[AudioFeatures]) -> Vec<Outlier> {
+    if features.len() < MIN_TRACKS_FOR_OUTLIERS {
+        return Vec::new();
+    }
+    let Some(centroid) = feature_centroid(features) else {
+        return Vec::new();
+    };
+
+    let offsets: Vec<[f32; 6]> = features
+        .iter()
+        .map(|f| {
+            let vector = sound_vector(f);
+            std::array::from_fn(|i| vector[i] - centroid[i])
+        })
+        .collect();
+    let distances: Vec<f32> = offsets
+        .iter()
+        .map(|offset| offset.iter().map(|d| d * d).sum::<f32>().sqrt())
+        .collect();
+    let Some(Spread { mean, stddev }) = spread(&distances) else {
+        return Vec::new();
+    };
+    let threshold = (mean + OUTLIER_DEVIATIONS * stddev).max(MIN_OUTLIER_DISTANCE);
+
+    let mut outliers: Vec<Outlier> = offsets
+        .iter()
+        .zip(&distances)
+        .enumerate()
+        .filter(|(_, (_, distance))| **distance > threshold)
+        .map(|(index, (offset, distance))| {
+            let (dimension, value) = offset
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+                .unwrap_or((0, &0.0));
+            let (above, below) = DIFFERENCES[dimension];
+            Outlier {
+                index,
+                distance: *distance,
+                difference: if *value >= 0.0 { above } else { below },
+            }
+        })
+        .collect();
+    outliers.sort_by(|a, b| b.distance.total_cmp(&a.distance));
+    outliers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(energy: f32, acousticness: f32, tempo: f32) -> AudioFeatures {
+        AudioFeatures {
+            tempo,
+            energy,
+            valence: 0.5,
+            danceability: 0.7,
+            acousticness,
+            instrumentalness: 0.0,
+            loudness: -5.0,
+            speechiness: 0.05,
+        }
+    }
+
+    #[test]
+    fn test_spread() {
+        assert_eq!(spread(&[]), None);
+
+        let result = spread(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]).unwrap();
+        assert!((result.mean - 5.0).abs() < 1e-6);
+        assert!((result.stddev - 2.0).abs() < 1e-6);
+
+        let single = spread(&[120.0]).unwrap();
+        assert_eq!(single.stddev, 0.0);
+    }
+
+    #[test]
+    fn test_energy_curve_buckets() {
+        assert!(energy_curve(&[], 10).is_empty());
+        // Shorter than the curve: one point per track
+        assert_eq!(energy_curve(&[0.1, 0.5], 10), vec![0.1, 0.5]);
+
+        let curve = energy_curve(&[0.0, 0.2, 0.4, 0.6, 0.8, 1.0], 3);
+        assert_eq!(curve.len(), 3);
+        assert!((curve[0] - 0.1).abs() < 1e-6);
+        assert!((curve[1] - 0.5).abs() < 1e-6);
+        assert!((curve[2] - 0.9).abs() < 1e-6);
+
+        // Uneven split still covers every track
+        assert_eq!(energy_curve(&[1.0; 7], 3), vec![1.0; 3]);
+    }
+
+    #[test]
+    fn test_ballad_stands_out_in_a_workout_mix() {
+        let mut mix: Vec<AudioFeatures> = (0..9)
+            .map(|i| features(0.85 + i as f32 * 0.01, 0.05, 150.0 + i as f32))
+            .collect();
+        mix.insert(4, features(0.2, 0.9, 70.0));
+
+        let outliers = find_outliers(&mix);
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].index, 4);
+        assert!(["calmer", "more acoustic", "slower"].contains(&outliers[0].difference));
+    }
+
+    #[test]
+    fn test_no_outliers_in_uniform_or_short_playlists() {
+        let uniform: Vec<AudioFeatures> = (0..10)
+            .map(|i| features(0.5 + (i % 3) as f32 * 0.05, 0.3, 120.0))
+            .collect();
+        assert!(find_outliers(&uniform).is_empty());
+
+        let short = vec![features(0.9, 0.0, 150.0), features(0.1, 1.0, 60.0)];
+        assert!(find_outliers(&short).is_empty());
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[0.0, 0.5, 1.0]), "▁▅█");
+        assert_eq!(sparkline(&[-1.0, 2.0]), "▁█");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
@@ -0,0 +1,46 @@
+//! All-time favorites that dropped out of the user's listening
+
+use std::collections::HashSet;
+
+/// Longer lists stop feeling like a rediscovery
+pub const MAX_REDISCOVER: usize = 30;
+
+/// Pure function: all-time favorites (best first) that appear in none of
+/// the recent lists, each once, at most `limit`
+pub fn forgotten_favorites<'a>(
+    all_time: &'a [String],
+    recent: &HashSet<&str>,
+    limit: usize,
+) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    all_time
+        .iter()
+        .map(String::as_str)
+        .filter(|id| !recent.contains(id) && seen.insert(*id))
+        .take(limit)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_favorites_missing_from_recent_listening() {
+        let all_time: Vec<String> = ["a", "b", "c", "b", "d"].map(String::from).to_vec();
+        let recent: HashSet<&str> = ["b", "x"].into();
+
+        assert_eq!(
+            forgotten_favorites(&all_time, &recent, 10),
+            vec!["a", "c", "d"]
+        );
+        assert_eq!(forgotten_favorites(&all_time, &recent, 2), vec!["a", "c"]);
+    }
+
+    #[test]
+    fn test_nothing_forgotten() {
+        let all_time: Vec<String> = ["a", "b"].map(String::from).to_vec();
+        let recent: HashSet<&str> = ["a", "b"].into();
+        assert!(forgotten_favorites(&all_time, &recent, 10).is_empty());
+    }
+}
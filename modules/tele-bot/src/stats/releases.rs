@@ -0,0 +1,107 @@
+//! New-release radar for followed artists
+
+use chrono::NaiveDate;
+use std::collections::HashSet;
+
+use crate::models::spotify::Release;
+
+/// How far back a release still counts as "new"
+pub const RELEASE_WINDOW_DAYS: i64 = 14;
+
+/// Per-chat radar bookkeeping: which albums were already announced
+#[derive(Debug, Clone, Default)]
+pub struct ReleaseRadar {
+    pub seen: HashSet<String>,
+    pub notify: bool,
+    primed: bool,
+}
+
+impl ReleaseRadar {
+    /// Returns releases not seen before and marks them as seen. The first
+    /// call only primes the radar, so enabling alerts doesn't replay the
+    /// whole current window.
+    pub fn take_unseen(&mut self, releases: &[Release]) -> Vec<Release> {
+        let unseen: Vec<Release> = releases
+            .iter()
+            .filter(|r| self.seen.insert(r.id.clone()))
+            .cloned()
+            .collect();
+
+        if !self.primed {
+            self.primed = true;
+            return Vec::new();
+        }
+
+        unseen
+    }
+}
+
+/// Parse a Spotify release date, which may be `YYYY-MM-DD`, `YYYY-MM` or `YYYY`.
+/// Coarse dates resolve to the first day of the period.
+pub fn parse_release_date(date: &str) -> Option<NaiveDate> {
+    let mut parts = date.split('-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next().map_or(Some(1), |m| m.parse().ok())?;
+    let day = parts.next().map_or(Some(1), |d| d.parse().ok())?;
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+/// Pure function: whether a release date falls within the last `window_days`
+pub fn is_recent_release(date: &str, today: NaiveDate, window_days: i64) -> bool {
+    match parse_release_date(date) {
+        Some(released) => {
+            let age = (today - released).num_days();
+            (0..=window_days).contains(&age)
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn release(id: &str) -> Release {
+        Release {
+            id: id.to_string(),
+            name: format!("Album {}", id),
+            artist: "Artist".to_string(),
+            album_type: "album".to_string(),
+            release_date: "2024-03-01".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_release_date_precisions() {
+        let expected = NaiveDate::from_ymd_opt(2024, 3, 15);
+        assert_eq!(parse_release_date("2024-03-15"), expected);
+        assert_eq!(
+            parse_release_date("2024-03"),
+            NaiveDate::from_ymd_opt(2024, 3, 1)
+        );
+        assert_eq!(
+            parse_release_date("2024"),
+            NaiveDate::from_ymd_opt(2024, 1, 1)
+        );
+        assert_eq!(parse_release_date("soon"), None);
+    }
+
+    #[test]
+    fn test_recent_release_window() {
+        let today = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert!(is_recent_release("2024-03-10", today, 14));
+        assert!(!is_recent_release("2024-02-01", today, 14));
+        assert!(!is_recent_release("2024-04-01", today, 14));
+    }
+
+    #[test]
+    fn test_radar_primes_then_reports_only_new() {
+        let mut radar = ReleaseRadar::default();
+        assert!(radar.take_unseen(&[release("a")]).is_empty());
+
+        let new = radar.take_unseen(&[release("a"), release("b")]);
+        assert_eq!(new.len(), 1);
+        assert_eq!(new[0].id, "b");
+        assert!(radar.take_unseen(&[release("b")]).is_empty());
+    }
+}
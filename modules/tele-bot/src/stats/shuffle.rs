@@ -0,0 +1,150 @@
+//! Smart shuffle: order a playlist so its energy follows an arc
+
+/// Where the arc peaks, as a fraction of the playlist length
+const PEAK_AT: f32 = 0.7;
+/// Intensity the arc winds down to by the last track
+const COOL_DOWN_TO: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArcShape {
+    /// Warm-up → peak → cool-down
+    Arc,
+    /// Calm to intense
+    Rise,
+    /// Intense to calm
+    Fall,
+}
+
+impl ArcShape {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "arc" => Some(ArcShape::Arc),
+            "rise" => Some(ArcShape::Rise),
+            "fall" => Some(ArcShape::Fall),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArcShape::Arc => "warm-up → peak → cool-down",
+            ArcShape::Rise => "rising",
+            ArcShape::Fall => "falling",
+        }
+    }
+
+    // Desired intensity at `x`, the position as a fraction of the playlist
+    fn target(self, x: f32) -> f32 {
+        match self {
+            ArcShape::Arc if x <= PEAK_AT => x / PEAK_AT,
+            ArcShape::Arc => 1.0 - (x - PEAK_AT) / (1.0 - PEAK_AT) * (1.0 - COOL_DOWN_TO),
+            ArcShape::Rise => x,
+            ArcShape::Fall => 1.0 - x,
+        }
+    }
+}
+
+/// Pure function: new order as indices into `scores`. The n-th calmest track
+/// goes to the position with the n-th lowest target, so the shape is kept
+/// whatever the spread of scores.
+pub fn arc_order(scores: &[f32], shape: ArcShape) -> Vec<usize> {
+    let n = scores.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let mut by_score: Vec<usize> = (0..n).collect();
+    by_score.sort_by(|a, b| scores[*a].total_cmp(&scores[*b]).then(a.cmp(b)));
+
+    let position = |i: usize| {
+        if n == 1 {
+            0.0
+        } else {
+            i as f32 / (n - 1) as f32
+        }
+    };
+    let mut by_target: Vec<usize> = (0..n).collect();
+    by_target.sort_by(|a, b| {
+        shape
+            .target(position(*a))
+            .total_cmp(&shape.target(position(*b)))
+            .then(a.cmp(b))
+    });
+
+    let mut order = vec![0; n];
+    for (slot, track) in by_target.into_iter().zip(by_score) {
+        order[slot] = track;
+    }
+    order
+}
+
+/// Pure function: single-item moves `(from, to)` that turn the current order
+/// into `order`, applied one after another. Items already in place are not moved.
+pub fn reorder_moves(order: &[usize]) -> Vec<(usize, usize)> {
+    let mut current: Vec<usize> = (0..order.len()).collect();
+    let mut moves = Vec::new();
+
+    for (to, wanted) in order.iter().enumerate() {
+        let from = current
+            .iter()
+            .position(|item| item == wanted)
+            .expect("order is a permutation");
+        if from != to {
+            let item = current.remove(from);
+            current.insert(to, item);
+            moves.push((from, to));
+        }
+    }
+
+    moves
+}
+
+/// Pure function: the order that puts back what `order` rearranged
+pub fn inverse_order(order: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; order.len()];
+    for (position, original) in order.iter().enumerate() {
+        inverse[*original] = position;
+    }
+    inverse
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rise_and_fall_sort_by_intensity() {
+        let scores = [0.5, 0.9, 0.1];
+        assert_eq!(arc_order(&scores, ArcShape::Rise), vec![2, 0, 1]);
+        assert_eq!(arc_order(&scores, ArcShape::Fall), vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn test_arc_peaks_late_and_cools_down() {
+        let scores: Vec<f32> = (0..10).map(|i| i as f32 / 10.0).collect();
+        let order = arc_order(&scores, ArcShape::Arc);
+        let shaped: Vec<f32> = order.iter().map(|i| scores[*i]).collect();
+
+        let peak = shaped
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| i)
+            .unwrap();
+        assert_eq!(peak, 6);
+        assert_eq!(shaped[0], 0.0);
+        assert!(shaped[9] < shaped[peak]);
+    }
+
+    #[test]
+    fn test_moves_replay_to_target_order() {
+        let order = vec![2, 0, 3, 1];
+        let mut items = vec![0, 1, 2, 3];
+        for (from, to) in reorder_moves(&order) {
+            let item = items.remove(from);
+            items.insert(to, item);
+        }
+        assert_eq!(items, order);
+        assert!(reorder_moves(&[0, 1, 2]).is_empty());
+    }
+}
@@ -0,0 +1,177 @@
+//! Taste similarity between two users
+
+use std::collections::{HashMap, HashSet};
+
+use crate::detector::genre::AudioFeatures;
+
+// How much each signal counts towards the overall score
+const ARTIST_WEIGHT: f32 = 0.4;
+const GENRE_WEIGHT: f32 = 0.35;
+const SOUND_WEIGHT: f32 = 0.25;
+
+/// What a user's top artists and tracks say about their taste
+#[derive(Debug, Clone, Default)]
+pub struct TasteProfile {
+    /// Top artist names, best first
+    pub artists: Vec<String>,
+    /// Genre → number of top artists tagged with it
+    pub genres: HashMap<String, f32>,
+    /// Average sound of the top tracks, if audio features were available
+    pub centroid: Option<[f32; 6]>,
+}
+
+/// Similarity in 0.0–1.0 per signal, plus the weighted overall score
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Similarity {
+    pub artists: f32,
+    pub genres: f32,
+    pub sound: Option<f32>,
+    pub overall: f32,
+}
+
+/// Pure function: |A ∩ B| / |A ∪ B|, 0.0 when both are empty
+pub fn jaccard(a: &HashSet<&str>, b: &HashSet<&str>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+/// Pure function: cosine similarity of two sparse vectors
+pub fn cosine(a: &HashMap<String, f32>, b: &HashMap<String, f32>) -> f32 {
+    let dot: f32 = a
+        .iter()
+        .filter_map(|(key, x)| b.get(key).map(|y| x * y))
+        .sum();
+    let norm = |v: &HashMap<String, f32>| v.values().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// Pure function: the 0–1 features of one track, tempo scaled from
+/// 60–180 BPM. Order: energy, valence, danceability, acousticness,
+/// instrumentalness, tempo.
+pub fn sound_vector(f: &AudioFeatures) -> [f32; 6] {
+    [
+        f.energy,
+        f.valence,
+        f.danceability,
+        f.acousticness,
+        f.instrumentalness,
+        ((f.tempo - 60.0) / 120.0).clamp(0.0, 1.0),
+    ]
+}
+
+/// Pure function: mean of the 0–1 features, see [`sound_vector`]
+pub fn feature_centroid(features: &[AudioFeatures]) -> Option<[f32; 6]> {
+    if features.is_empty() {
+        return None;
+    }
+
+    let mut sum = [0.0; 6];
+    for f in features {
+        for (total, value) in sum.iter_mut().zip(sound_vector(f)) {
+            *total += value;
+        }
+    }
+    Some(sum.map(|total| total / features.len() as f32))
+}
+
+/// Pure function: compare two profiles. Artist overlap of top lists is
+/// naturally low, so its square root is used to spread the scale. Without
+/// audio features for both users the sound weight is shared out to the rest.
+pub fn compare(a: &TasteProfile, b: &TasteProfile) -> Similarity {
+    let artists_a: HashSet<&str> = a.artists.iter().map(String::as_str).collect();
+    let artists_b: HashSet<&str> = b.artists.iter().map(String::as_str).collect();
+    let artists = jaccard(&artists_a, &artists_b).sqrt();
+    let genres = cosine(&a.genres, &b.genres);
+
+    let sound = match (a.centroid, b.centroid) {
+        (Some(x), Some(y)) => {
+            let distance: f32 = x.iter().zip(y).map(|(p, q)| (p - q).abs()).sum();
+            Some(1.0 - distance / x.len() as f32)
+        }
+        _ => None,
+    };
+
+    let overall = match sound {
+        Some(sound) => ARTIST_WEIGHT * artists + GENRE_WEIGHT * genres + SOUND_WEIGHT * sound,
+        None => (ARTIST_WEIGHT * artists + GENRE_WEIGHT * genres) / (ARTIST_WEIGHT + GENRE_WEIGHT),
+    };
+
+    Similarity {
+        artists,
+        genres,
+        sound,
+        overall,
+    }
+}
+
+/// Pure function: artists both users have in their tops, in `a`'s order
+pub fn shared_artists<'a>(a: &'a TasteProfile, b: &TasteProfile) -> Vec<&'a str> {
+    let theirs: HashSet<&str> = b.artists.iter().map(String::as_str).collect();
+    a.artists
+        .iter()
+        .map(String::as_str)
+        .filter(|artist| theirs.contains(artist))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(artists: &[&str], genres: &[(&str, f32)]) -> TasteProfile {
+        TasteProfile {
+            artists: artists.iter().map(|s| s.to_string()).collect(),
+            genres: genres.iter().map(|(g, n)| (g.to_string(), *n)).collect(),
+            centroid: None,
+        }
+    }
+
+    #[test]
+    fn test_jaccard_and_cosine() {
+        let a: HashSet<&str> = ["x", "y"].into();
+        let b: HashSet<&str> = ["y", "z"].into();
+        assert!((jaccard(&a, &b) - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(jaccard(&HashSet::new(), &HashSet::new()), 0.0);
+
+        let pop = profile(&[], &[("pop", 2.0)]);
+        let rock = profile(&[], &[("rock", 1.0)]);
+        assert!((cosine(&pop.genres, &pop.genres) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine(&pop.genres, &rock.genres), 0.0);
+    }
+
+    #[test]
+    fn test_identical_profiles_match_fully() {
+        let mut a = profile(&["A", "B"], &[("pop", 1.0)]);
+        a.centroid = Some([0.5; 6]);
+        let similarity = compare(&a, &a.clone());
+
+        assert!((similarity.overall - 1.0).abs() < 1e-6);
+        assert_eq!(similarity.sound, Some(1.0));
+    }
+
+    #[test]
+    fn test_missing_features_reweights() {
+        let a = profile(&["A"], &[("pop", 1.0)]);
+        let b = profile(&["B"], &[("pop", 1.0)]);
+        let similarity = compare(&a, &b);
+
+        assert_eq!(similarity.sound, None);
+        assert_eq!(similarity.artists, 0.0);
+        assert!((similarity.overall - GENRE_WEIGHT / (ARTIST_WEIGHT + GENRE_WEIGHT)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_shared_artists_keep_order() {
+        let a = profile(&["C", "A", "B"], &[]);
+        let b = profile(&["A", "C"], &[]);
+        assert_eq!(shared_artists(&a, &b), vec!["C", "A"]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Skip detection from periodic "currently playing" samples
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// A track that changes with more than this left was skipped. Playback is
+/// sampled every 30 seconds, so the margin also covers the time between the
+/// last sample and the actual track change.
+pub const END_MARGIN_MS: u32 = 45_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaybackSample {
+    pub track_id: String,
+    pub name: String,
+    pub progress_ms: u32,
+    pub duration_ms: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SkipStats {
+    pub name: String,
+    pub count: u32,
+    /// Sum of skip positions as a fraction of the track, for the average
+    position_sum: f32,
+}
+
+impl SkipStats {
+    pub fn average_position(&self) -> f32 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.position_sum / self.count as f32
+        }
+    }
+}
+
+/// Per-member skip bookkeeping, fed by the playback poller
+#[derive(Debug, Clone, Default)]
+pub struct SkipTracker {
+    pub enabled: bool,
+    current: Option<PlaybackSample>,
+    skips: HashMap<String, SkipStats>,
+}
+
+impl SkipTracker {
+    /// Record the latest sample (`None` when nothing plays). Returns the
+    /// track that was just skipped, if any. Stopping playback is not a skip.
+    pub fn observe(&mut self, sample: Option<PlaybackSample>) -> Option<String> {
+        let Some(sample) = sample else {
+            self.current = None;
+            return None;
+        };
+
+        let previous = self.current.replace(sample);
+        let previous = previous.filter(|previous| {
+            Some(&previous.track_id) != self.current.as_ref().map(|now| &now.track_id)
+        })?;
+        if previous.duration_ms.saturating_sub(previous.progress_ms) <= END_MARGIN_MS {
+            return None;
+        }
+
+        let stats = self
+            .skips
+            .entry(previous.track_id.clone())
+            .or_insert_with(|| SkipStats {
+                name: previous.name.clone(),
+                ..SkipStats::default()
+            });
+        stats.count += 1;
+        stats.position_sum += previous.progress_ms as f32 / previous.duration_ms.max(1) as f32;
+        Some(previous.track_id)
+    }
+
+    /// Most skipped tracks first, ties by name
+    pub fn most_skipped(&self) -> Vec<&SkipStats> {
+        let mut ranked: Vec<&SkipStats> = self.skips.values().collect();
+        ranked.sort_by_key(|stats| (Reverse(stats.count), stats.name.as_str()));
+        ranked
+    }
+
+    pub fn total_skips(&self) -> u32 {
+        self.skips.values().map(|stats| stats.count).sum()
+    }
+
+    /// Average position across all skips (0.0 to 1.0)
+    pub fn average_position(&self) -> f32 {
+        let total = self.total_skips();
+        if total == 0 {
+            return 0.0;
+        }
+        self.skips
+            .values()
+            .map(|stats| stats.position_sum)
+            .sum::<f32>()
+            / total as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(id: &str, progress_s: u32, duration_s: u32) -> Option<PlaybackSample> {
+        Some(PlaybackSample {
+            track_id: id.to_string(),
+            name: id.to_uppercase(),
+            progress_ms: progress_s * 1000,
+            duration_ms: duration_s * 1000,
+        })
+    }
+
+    #[test]
+    fn test_change_before_the_end_is_a_skip() {
+        let mut tracker = SkipTracker::default();
+        assert_eq!(tracker.observe(sample("a", 10, 200)), None);
+        assert_eq!(tracker.observe(sample("a", 50, 200)), None);
+        assert_eq!(tracker.observe(sample("b", 5, 180)), Some("a".to_string()));
+
+        // Last sample close to the end: played through
+        assert_eq!(tracker.observe(sample("b", 150, 180)), None);
+        assert_eq!(tracker.observe(sample("c", 3, 240)), None);
+
+        let ranked = tracker.most_skipped();
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].name, "A");
+        assert!((ranked[0].average_position() - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_stopping_playback_is_not_a_skip() {
+        let mut tracker = SkipTracker::default();
+        tracker.observe(sample("a", 10, 200));
+        assert_eq!(tracker.observe(None), None);
+        assert_eq!(tracker.observe(sample("b", 1, 200)), None);
+        assert_eq!(tracker.total_skips(), 0);
+        assert_eq!(tracker.average_position(), 0.0);
+    }
+
+    #[test]
+    fn test_most_skipped_orders_by_count() {
+        let mut tracker = SkipTracker::default();
+        for id in ["a", "c", "a", "b", "a"] {
+            tracker.observe(sample(id, 20, 200));
+        }
+
+        let names: Vec<&str> = tracker
+            .most_skipped()
+            .iter()
+            .map(|stats| stats.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["A", "B", "C"]);
+        assert_eq!(tracker.total_skips(), 4);
+        assert!((tracker.average_position() - 0.1).abs() < 1e-6);
+    }
+}
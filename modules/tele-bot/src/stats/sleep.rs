@@ -0,0 +1,130 @@
+//! Sleep timer: pause playback after a while, optionally winding down first
+
+use chrono::{DateTime, Utc};
+use tokio::task::AbortHandle;
+
+use crate::detector::mood::Mood;
+
+pub const MAX_MINUTES: u32 = 240;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SleepCommand {
+    Status,
+    Cancel,
+    Start { minutes: u32, wind_down: bool },
+}
+
+/// A pending pause; aborting the task cancels it
+#[derive(Debug)]
+pub struct SleepTimer {
+    pub ends_at: DateTime<Utc>,
+    pub task: AbortHandle,
+}
+
+/// A track that could be queued to wind down
+#[derive(Debug, Clone)]
+pub struct WindDownCandidate {
+    pub id: String,
+    pub duration_ms: u32,
+    pub mood: Mood,
+    pub energy: f32,
+}
+
+/// Parse `[minutes [wind]]` or `off`
+pub fn parse_sleep(input: &str) -> Option<SleepCommand> {
+    let parts: Vec<String> = input.split_whitespace().map(str::to_lowercase).collect();
+    let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+    match parts.as_slice() {
+        [] => Some(SleepCommand::Status),
+        ["off"] | ["cancel"] => Some(SleepCommand::Cancel),
+        [minutes, rest @ ..] => {
+            let minutes: u32 = minutes.parse().ok()?;
+            let wind_down = match rest {
+                [] => false,
+                ["wind"] | ["winddown"] | ["wind-down"] => true,
+                _ => return None,
+            };
+            (1..=MAX_MINUTES)
+                .contains(&minutes)
+                .then_some(SleepCommand::Start { minutes, wind_down })
+        }
+    }
+}
+
+/// Calm and peaceful tracks filling `minutes`, from the most to the least
+/// energetic so playback gradually quiets down
+pub fn pick_wind_down(candidates: &[WindDownCandidate], minutes: u32) -> Vec<String> {
+    let mut calm: Vec<&WindDownCandidate> = candidates
+        .iter()
+        .filter(|candidate| matches!(candidate.mood, Mood::Calm | Mood::Peaceful))
+        .collect();
+    calm.sort_by(|a, b| b.energy.total_cmp(&a.energy));
+
+    let budget_ms = u64::from(minutes) * 60_000;
+    let mut total_ms = 0u64;
+    let mut picked = Vec::new();
+    for candidate in calm {
+        if total_ms >= budget_ms {
+            break;
+        }
+        if picked.contains(&candidate.id) {
+            continue;
+        }
+        total_ms += u64::from(candidate.duration_ms);
+        picked.push(candidate.id.clone());
+    }
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(id: &str, minutes: u32, mood: Mood, energy: f32) -> WindDownCandidate {
+        WindDownCandidate {
+            id: id.to_string(),
+            duration_ms: minutes * 60_000,
+            mood,
+            energy,
+        }
+    }
+
+    #[test]
+    fn test_parse_sleep() {
+        assert_eq!(parse_sleep(""), Some(SleepCommand::Status));
+        assert_eq!(parse_sleep("off"), Some(SleepCommand::Cancel));
+        assert_eq!(
+            parse_sleep("30"),
+            Some(SleepCommand::Start {
+                minutes: 30,
+                wind_down: false
+            })
+        );
+        assert_eq!(
+            parse_sleep("45 Wind"),
+            Some(SleepCommand::Start {
+                minutes: 45,
+                wind_down: true
+            })
+        );
+        assert_eq!(parse_sleep("0"), None);
+        assert_eq!(parse_sleep("500"), None);
+        assert_eq!(parse_sleep("30 loud"), None);
+    }
+
+    #[test]
+    fn test_wind_down_fills_time_getting_calmer() {
+        let candidates = vec![
+            candidate("quiet", 4, Mood::Peaceful, 0.1),
+            candidate("party", 3, Mood::Energetic, 0.9),
+            candidate("soft", 4, Mood::Calm, 0.4),
+            candidate("softer", 4, Mood::Calm, 0.25),
+            candidate("soft", 4, Mood::Calm, 0.4),
+        ];
+        assert_eq!(pick_wind_down(&candidates, 6), vec!["soft", "softer"]);
+        assert_eq!(
+            pick_wind_down(&candidates, 60),
+            vec!["soft", "softer", "quiet"]
+        );
+    }
+}
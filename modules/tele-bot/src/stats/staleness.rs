@@ -0,0 +1,145 @@
+//! How long tracks have sat in a playlist without being played, and the
+//! play log that tells
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+pub const DEFAULT_STALE_WEEKS: u32 = 4;
+pub const MAX_STALE_WEEKS: u32 = 52;
+
+/// When each track was last seen playing, per member. Fed by recent plays
+/// and the playback poller; kept in memory, so it starts empty on restart.
+#[derive(Debug, Clone, Default)]
+pub struct PlayLog {
+    last_played: HashMap<String, DateTime<Utc>>,
+    /// Earliest play seen; before it nothing is known
+    since: Option<DateTime<Utc>>,
+}
+
+impl PlayLog {
+    pub fn record(&mut self, track_id: &str, at: DateTime<Utc>) {
+        let last = self.last_played.entry(track_id.to_string()).or_insert(at);
+        *last = (*last).max(at);
+        self.since = Some(self.since.map_or(at, |since| since.min(at)));
+    }
+
+    pub fn last_played(&self, track_id: &str) -> Option<DateTime<Utc>> {
+        self.last_played.get(track_id).copied()
+    }
+
+    pub fn since(&self) -> Option<DateTime<Utc>> {
+        self.since
+    }
+
+    /// Whether the log reaches back `weeks`, so a track missing from it
+    /// really wasn't played in that time
+    pub fn covers(&self, now: DateTime<Utc>, weeks: u32) -> bool {
+        self.since
+            .is_some_and(|since| now - since >= Duration::weeks(weeks as i64))
+    }
+}
+
+/// A playlist the member wants stale tracks removed from automatically
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rotation {
+    pub playlist_id: String,
+    pub playlist_name: String,
+    pub weeks: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaleTrack {
+    /// Position in the slice given to [`stale_tracks`]
+    pub index: usize,
+    pub added_at: DateTime<Utc>,
+    pub last_played: Option<DateTime<Utc>>,
+}
+
+impl StaleTrack {
+    /// Since when the track has sat unplayed
+    pub fn idle_since(&self) -> DateTime<Utc> {
+        self.last_played
+            .map_or(self.added_at, |played| played.max(self.added_at))
+    }
+}
+
+/// Pure function: entries (track ID and when it was added) that were added
+/// over `weeks` ago and not played in the last `weeks`, idle longest first
+pub fn stale_tracks(
+    items: &[(String, DateTime<Utc>)],
+    log: &PlayLog,
+    now: DateTime<Utc>,
+    weeks: u32,
+) -> Vec<StaleTrack> {
+    let cutoff = now - Duration::weeks(weeks as i64);
+    let mut stale: Vec<StaleTrack> = items
+        .iter()
+        .enumerate()
+        .map(|(index, (id, added_at))| StaleTrack {
+            index,
+            added_at: *added_at,
+            last_played: log.last_played(id),
+        })
+        .filter(|track| track.idle_since() < cutoff)
+        .collect();
+    stale.sort_by_key(|track| (track.idle_since(), track.index));
+    stale
+}
+
+/// Pure function: a week count for the report or rotation, clamped to
+/// 1..=MAX_STALE_WEEKS
+pub fn parse_weeks(input: &str) -> Option<u32> {
+    let weeks = input.trim().trim_end_matches('w').parse::<u32>().ok()?;
+    Some(weeks.clamp(1, MAX_STALE_WEEKS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn day(d: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, d, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_play_log_keeps_latest_play_and_earliest_coverage() {
+        let mut log = PlayLog::default();
+        assert!(!log.covers(day(31), 1));
+
+        log.record("a", day(10));
+        log.record("a", day(5));
+        log.record("b", day(3));
+        assert_eq!(log.last_played("a"), Some(day(10)));
+        assert_eq!(log.since(), Some(day(3)));
+        assert!(log.covers(day(10), 1));
+        assert!(!log.covers(day(10), 2));
+    }
+
+    #[test]
+    fn test_stale_tracks_idle_longest_first() {
+        let mut log = PlayLog::default();
+        log.record("played", day(25));
+        log.record("old_play", day(4));
+        let items = vec![
+            ("played".to_string(), day(1)),
+            ("never".to_string(), day(2)),
+            ("new".to_string(), day(28)),
+            ("old_play".to_string(), day(1)),
+        ];
+
+        let stale = stale_tracks(&items, &log, day(30), 2);
+        let indices: Vec<usize> = stale.iter().map(|track| track.index).collect();
+        assert_eq!(indices, vec![1, 3]);
+        assert_eq!(stale[1].idle_since(), day(4));
+    }
+
+    #[test]
+    fn test_parse_weeks() {
+        assert_eq!(parse_weeks("6"), Some(6));
+        assert_eq!(parse_weeks(" 8w "), Some(8));
+        assert_eq!(parse_weeks("0"), Some(1));
+        assert_eq!(parse_weeks("500"), Some(MAX_STALE_WEEKS));
+        assert_eq!(parse_weeks("soon"), None);
+    }
+}
@@ -0,0 +1,144 @@
+//! Listening streak tracking (consecutive days with at least one play)
+
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::BTreeSet;
+
+/// Streak lengths (in days) that deserve a congratulation message
+pub const MILESTONES: [u32; 3] = [7, 30, 100];
+
+/// Per-chat streak bookkeeping, built up from every batch of plays seen
+#[derive(Debug, Clone, Default)]
+pub struct StreakTracker {
+    pub days: BTreeSet<NaiveDate>,
+    pub notify: bool,
+    pub celebrated: u32,
+}
+
+impl StreakTracker {
+    /// Remember the (UTC) calendar days of the given plays
+    pub fn record<'a>(&mut self, played_at: impl IntoIterator<Item = &'a DateTime<Utc>>) {
+        self.days
+            .extend(played_at.into_iter().map(|t| t.date_naive()));
+    }
+
+    pub fn current(&self, today: NaiveDate) -> u32 {
+        current_streak(&self.days, today)
+    }
+
+    /// Returns the milestone just reached, if it has not been celebrated yet.
+    /// Breaking the streak resets the celebration marker.
+    pub fn take_milestone(&mut self, today: NaiveDate) -> Option<u32> {
+        let streak = self.current(today);
+        if streak < self.celebrated {
+            self.celebrated = 0;
+        }
+
+        let milestone = MILESTONES
+            .iter()
+            .copied()
+            .filter(|m| streak >= *m && *m > self.celebrated)
+            .max()?;
+        self.celebrated = milestone;
+        Some(milestone)
+    }
+}
+
+/// Pure function: number of consecutive days ending today (or yesterday,
+/// since today's first play may still be ahead) that contain a play
+pub fn current_streak(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> u32 {
+    let mut day = if days.contains(&today) {
+        today
+    } else {
+        match today.pred_opt() {
+            Some(yesterday) if days.contains(&yesterday) => yesterday,
+            _ => return 0,
+        }
+    };
+
+    let mut streak = 0;
+    while days.contains(&day) {
+        streak += 1;
+        day = match day.pred_opt() {
+            Some(previous) => previous,
+            None => break,
+        };
+    }
+
+    streak
+}
+
+/// Pure function: longest run of consecutive days ever observed
+pub fn longest_streak(days: &BTreeSet<NaiveDate>) -> u32 {
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+
+    for day in days {
+        run = match previous {
+            Some(prev) if prev.succ_opt() == Some(*day) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, d).unwrap()
+    }
+
+    fn days(list: &[u32]) -> BTreeSet<NaiveDate> {
+        list.iter().map(|d| date(*d)).collect()
+    }
+
+    #[test]
+    fn test_streak_ending_today() {
+        assert_eq!(current_streak(&days(&[8, 9, 10]), date(10)), 3);
+    }
+
+    #[test]
+    fn test_streak_ending_yesterday_still_counts() {
+        assert_eq!(current_streak(&days(&[8, 9]), date(10)), 2);
+    }
+
+    #[test]
+    fn test_gap_breaks_streak() {
+        assert_eq!(current_streak(&days(&[5, 6, 9, 10]), date(10)), 2);
+        assert_eq!(current_streak(&days(&[5, 6]), date(10)), 0);
+    }
+
+    #[test]
+    fn test_longest_streak() {
+        assert_eq!(longest_streak(&days(&[1, 2, 3, 7, 8])), 3);
+        assert_eq!(longest_streak(&BTreeSet::new()), 0);
+    }
+
+    #[test]
+    fn test_milestone_celebrated_once() {
+        let mut tracker = StreakTracker {
+            days: days(&[1, 2, 3, 4, 5, 6, 7]),
+            ..Default::default()
+        };
+
+        assert_eq!(tracker.take_milestone(date(7)), Some(7));
+        assert_eq!(tracker.take_milestone(date(7)), None);
+    }
+
+    #[test]
+    fn test_broken_streak_resets_milestones() {
+        let mut tracker = StreakTracker {
+            days: days(&[1, 2, 3, 4, 5, 6, 7]),
+            celebrated: 7,
+            ..Default::default()
+        };
+
+        assert_eq!(tracker.take_milestone(date(20)), None);
+        assert_eq!(tracker.celebrated, 0);
+    }
+}
@@ -0,0 +1,108 @@
+//! Picking tracks that match a running cadence
+
+use std::collections::HashSet;
+
+use crate::detector::genre::AudioFeatures;
+
+pub const DEFAULT_TOLERANCE: f32 = 5.0;
+pub const MAX_TOLERANCE: f32 = 20.0;
+/// Cadences outside this range aren't runnable (or walkable)
+pub const MIN_BPM: f32 = 60.0;
+pub const MAX_BPM: f32 = 220.0;
+pub const MAX_TRACKS: usize = 50;
+
+/// Parse `<bpm> [tolerance]`
+pub fn parse_pace(input: &str) -> Option<(f32, f32)> {
+    let mut parts = input.split_whitespace();
+    let bpm: f32 = parts.next()?.parse().ok()?;
+    let tolerance: f32 = match parts.next() {
+        Some(tolerance) => tolerance.parse().ok()?,
+        None => DEFAULT_TOLERANCE,
+    };
+    let valid = parts.next().is_none()
+        && (MIN_BPM..=MAX_BPM).contains(&bpm)
+        && (0.0..=MAX_TOLERANCE).contains(&tolerance);
+    valid.then_some((bpm, tolerance))
+}
+
+/// The tempo a runner would step to: the track's own, or half or double
+/// time when that is what lands within `tolerance` of `target`
+pub fn matching_tempo(tempo: f32, target: f32, tolerance: f32) -> Option<f32> {
+    [tempo, tempo * 2.0, tempo / 2.0]
+        .into_iter()
+        .find(|candidate| (candidate - target).abs() <= tolerance)
+}
+
+/// IDs of tracks matching the pace, calmest first so the run builds up
+pub fn pick_for_pace(
+    candidates: &[(String, AudioFeatures)],
+    target: f32,
+    tolerance: f32,
+    limit: usize,
+) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut picked: Vec<&(String, AudioFeatures)> = candidates
+        .iter()
+        .filter(|(id, features)| {
+            matching_tempo(features.tempo, target, tolerance).is_some() && seen.insert(id)
+        })
+        .collect();
+    picked.sort_by(|a, b| a.1.energy.total_cmp(&b.1.energy));
+    picked
+        .into_iter()
+        .take(limit)
+        .map(|(id, _)| id.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(tempo: f32, energy: f32) -> AudioFeatures {
+        AudioFeatures {
+            tempo,
+            energy,
+            valence: 0.5,
+            danceability: 0.5,
+            acousticness: 0.1,
+            instrumentalness: 0.0,
+            loudness: -6.0,
+            speechiness: 0.05,
+        }
+    }
+
+    #[test]
+    fn test_parse_pace() {
+        assert_eq!(parse_pace("170"), Some((170.0, DEFAULT_TOLERANCE)));
+        assert_eq!(parse_pace(" 165 3 "), Some((165.0, 3.0)));
+        assert_eq!(parse_pace("fast"), None);
+        assert_eq!(parse_pace("300"), None);
+        assert_eq!(parse_pace("170 50"), None);
+        assert_eq!(parse_pace("170 5 extra"), None);
+    }
+
+    #[test]
+    fn test_matching_tempo_accepts_half_and_double_time() {
+        assert_eq!(matching_tempo(168.0, 170.0, 5.0), Some(168.0));
+        assert_eq!(matching_tempo(86.0, 170.0, 5.0), Some(172.0));
+        assert_eq!(matching_tempo(120.0, 170.0, 5.0), None);
+        assert_eq!(matching_tempo(200.0, 100.0, 2.0), Some(100.0));
+    }
+
+    #[test]
+    fn test_pick_for_pace_sorts_by_energy_and_dedups() {
+        let candidates = vec![
+            ("loud".to_string(), features(171.0, 0.9)),
+            ("slow".to_string(), features(100.0, 0.2)),
+            ("half".to_string(), features(85.0, 0.4)),
+            ("loud".to_string(), features(171.0, 0.9)),
+            ("calm".to_string(), features(168.0, 0.3)),
+        ];
+        assert_eq!(
+            pick_for_pace(&candidates, 170.0, 5.0, 10),
+            vec!["calm", "half", "loud"]
+        );
+        assert_eq!(pick_for_pace(&candidates, 170.0, 5.0, 1), vec!["calm"]);
+    }
+}
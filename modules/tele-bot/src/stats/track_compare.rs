@@ -0,0 +1,125 @@
+//! Side-by-side audio features of two tracks
+
+use crate::detector::genre::AudioFeatures;
+use crate::stats::harmonic::Camelot;
+
+/// Smallest difference in a 0–1 feature worth calling out
+const NOTABLE: f32 = 0.1;
+/// Smallest tempo difference worth calling out, in BPM
+const NOTABLE_BPM: f32 = 5.0;
+
+const PITCH_CLASSES: [&str; 12] = [
+    "C", "C♯", "D", "D♯", "E", "F", "F♯", "G", "G♯", "A", "A♯", "B",
+];
+
+/// 0–1 features shown in the comparison, with the adjective for "more of it"
+pub fn feature_rows(
+    a: &AudioFeatures,
+    b: &AudioFeatures,
+) -> [(&'static str, &'static str, f32, f32); 6] {
+    [
+        ("Energy", "energetic", a.energy, b.energy),
+        ("Danceability", "danceable", a.danceability, b.danceability),
+        ("Valence", "upbeat", a.valence, b.valence),
+        ("Acousticness", "acoustic", a.acousticness, b.acousticness),
+        (
+            "Instrumentalness",
+            "instrumental",
+            a.instrumentalness,
+            b.instrumentalness,
+        ),
+        ("Speechiness", "wordy", a.speechiness, b.speechiness),
+    ]
+}
+
+/// One line per track saying what it has more of, e.g.
+/// "A is faster, more danceable and more energetic". Empty when the two
+/// are alike in every feature.
+pub fn verdict(a_name: &str, b_name: &str, a: &AudioFeatures, b: &AudioFeatures) -> Vec<String> {
+    let mut more_a = Vec::new();
+    let mut more_b = Vec::new();
+
+    if a.tempo - b.tempo >= NOTABLE_BPM {
+        more_a.push("faster".to_string());
+    } else if b.tempo - a.tempo >= NOTABLE_BPM {
+        more_b.push("faster".to_string());
+    }
+
+    let mut rows = feature_rows(a, b);
+    // Biggest differences first
+    rows.sort_by(|x, y| (y.3 - y.2).abs().total_cmp(&(x.3 - x.2).abs()));
+    for (_, adjective, value_a, value_b) in rows {
+        if value_a - value_b >= NOTABLE {
+            more_a.push(format!("more {adjective}"));
+        } else if value_b - value_a >= NOTABLE {
+            more_b.push(format!("more {adjective}"));
+        }
+    }
+
+    [(a_name, more_a), (b_name, more_b)]
+        .into_iter()
+        .filter(|(_, traits)| !traits.is_empty())
+        .map(|(name, traits)| format!("{} is {}", name, join_and(&traits)))
+        .collect()
+}
+
+/// The key's name, e.g. "F♯ minor"
+pub fn key_name(key: Camelot) -> String {
+    format!(
+        "{} {}",
+        PITCH_CLASSES[key.pitch() as usize],
+        if key.minor { "minor" } else { "major" }
+    )
+}
+
+fn join_and(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.clone(),
+        [init @ .., last] => format!("{} and {}", init.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(tempo: f32, energy: f32, danceability: f32, acousticness: f32) -> AudioFeatures {
+        AudioFeatures {
+            tempo,
+            energy,
+            valence: 0.5,
+            danceability,
+            acousticness,
+            instrumentalness: 0.0,
+            loudness: -6.0,
+            speechiness: 0.05,
+        }
+    }
+
+    #[test]
+    fn test_verdict_names_what_each_has_more_of() {
+        let a = features(128.0, 0.9, 0.8, 0.1);
+        let b = features(90.0, 0.4, 0.75, 0.7);
+        assert_eq!(
+            verdict("A", "B", &a, &b),
+            vec![
+                "A is faster and more energetic".to_string(),
+                "B is more acoustic".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verdict_is_empty_for_similar_tracks() {
+        let a = features(120.0, 0.6, 0.6, 0.2);
+        let b = features(122.0, 0.65, 0.55, 0.25);
+        assert!(verdict("A", "B", &a, &b).is_empty());
+    }
+
+    #[test]
+    fn test_key_name() {
+        assert_eq!(key_name(Camelot::from_key(0, false).unwrap()), "C major");
+        assert_eq!(key_name(Camelot::from_key(6, true).unwrap()), "F♯ minor");
+    }
+}
@@ -0,0 +1,107 @@
+//! What the bot changed on a member's Spotify account, so they can check
+//! what was done on their behalf with `/history_actions`.
+//!
+//! Kept in memory per member, like backups; the oldest entries are dropped.
+
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+
+pub const MAX_ENTRIES: usize = 100;
+
+/// Who triggered the change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Actor {
+    /// A command or button in the chat
+    Command,
+    /// A background job or timer the member turned on
+    Job,
+}
+
+impl Actor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Actor::Command => "command",
+            Actor::Job => "automatic",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    pub actor: Actor,
+    /// Short verb phrase, e.g. "create playlist"
+    pub action: &'static str,
+    /// The parameters, e.g. the playlist name and track count
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    entries: VecDeque<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn record(&mut self, actor: Actor, action: &'static str, detail: impl Into<String>) {
+        self.record_at(Utc::now(), actor, action, detail);
+    }
+
+    pub fn record_at(
+        &mut self,
+        at: DateTime<Utc>,
+        actor: Actor,
+        action: &'static str,
+        detail: impl Into<String>,
+    ) {
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(AuditEntry {
+            at,
+            actor,
+            action,
+            detail: detail.into(),
+        });
+    }
+
+    /// Newest first
+    pub fn recent(&self) -> impl Iterator<Item = &AuditEntry> {
+        self.entries.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_recent_is_newest_first() {
+        let mut log = AuditLog::default();
+        let at = |minute| Utc.with_ymd_and_hms(2024, 3, 1, 12, minute, 0).unwrap();
+        log.record_at(at(0), Actor::Command, "like", "A");
+        log.record_at(at(5), Actor::Job, "pause playback", "sleep timer");
+
+        let actions: Vec<&str> = log.recent().map(|entry| entry.action).collect();
+        assert_eq!(actions, vec!["pause playback", "like"]);
+        assert_eq!(log.recent().next().unwrap().actor, Actor::Job);
+    }
+
+    #[test]
+    fn test_oldest_entries_are_dropped() {
+        let mut log = AuditLog::default();
+        for i in 0..MAX_ENTRIES + 5 {
+            log.record(Actor::Command, "like", i.to_string());
+        }
+        assert_eq!(log.len(), MAX_ENTRIES);
+        assert_eq!(log.recent().last().unwrap().detail, "5");
+    }
+}
@@ -0,0 +1,205 @@
+//! Locale-aware formatting of numbers, dates and durations for bot messages.
+//!
+//! The deployment default comes from `BOT_LOCALE` (`en` or `vi`, English if
+//! unset); each chat can override it with `/locale`.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use std::time::Duration;
+
+lazy_static::lazy_static! {
+    static ref DEFAULT_LOCALE: Locale = std::env::var("BOT_LOCALE")
+        .ok()
+        .and_then(|code| Locale::parse(&code))
+        .unwrap_or(Locale::En);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    /// `1,234.5`, `03/15/2024`
+    En,
+    /// `1.234,5`, `15/03/2024`
+    Vi,
+}
+
+impl Default for Locale {
+    /// The deployment-wide locale from `BOT_LOCALE`
+    fn default() -> Self {
+        *DEFAULT_LOCALE
+    }
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::Vi];
+
+    /// Accepts a language code with an optional region, e.g. `vi` or `en-US`
+    pub fn parse(code: &str) -> Option<Self> {
+        let language = code.trim().split(['-', '_']).next()?.to_lowercase();
+        match language.as_str() {
+            "en" => Some(Locale::En),
+            "vi" => Some(Locale::Vi),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Vi => "vi",
+        }
+    }
+
+    // (thousands separator, decimal separator)
+    fn separators(self) -> (char, char) {
+        match self {
+            Locale::En => (',', '.'),
+            Locale::Vi => ('.', ','),
+        }
+    }
+}
+
+/// Pure function: format a number with grouped thousands and `decimals` places
+pub fn format_number(value: f64, decimals: usize, locale: Locale) -> String {
+    let (thousands, decimal) = locale.separators();
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = match formatted.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (formatted.as_str(), None),
+    };
+
+    let mut output = String::new();
+    // Rounding may turn a tiny negative value into zero, which gets no sign
+    if value < 0.0 && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        output.push('-');
+    }
+    output.push_str(&group_digits(integer, thousands));
+    if let Some(fraction) = fraction {
+        output.push(decimal);
+        output.push_str(fraction);
+    }
+    output
+}
+
+/// Pure function: format a whole count with grouped thousands
+pub fn format_count(value: u64, locale: Locale) -> String {
+    group_digits(&value.to_string(), locale.separators().0)
+}
+
+fn group_digits(digits: &str, separator: char) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+pub fn format_date(date: NaiveDate, locale: Locale) -> String {
+    match locale {
+        Locale::En => date.format("%m/%d/%Y").to_string(),
+        Locale::Vi => date.format("%d/%m/%Y").to_string(),
+    }
+}
+
+pub fn format_datetime(datetime: DateTime<Utc>, locale: Locale) -> String {
+    format!(
+        "{} {} UTC",
+        format_date(datetime.date_naive(), locale),
+        datetime.format("%H:%M")
+    )
+}
+
+/// Pure function: a coarse human-readable duration such as `2d 3h` or `5 phút`,
+/// keeping the two most significant units
+pub fn format_duration(duration: Duration, locale: Locale) -> String {
+    let units: [(u64, &str, &str); 4] = [
+        (86_400, "d", " ngày"),
+        (3_600, "h", " giờ"),
+        (60, "m", " phút"),
+        (1, "s", " giây"),
+    ];
+
+    let mut remaining = duration.as_secs();
+    let mut parts = Vec::new();
+    for (size, en, vi) in units {
+        let amount = remaining / size;
+        remaining %= size;
+        // Seconds are always shown when nothing larger is, so zero reads "0s"
+        if amount > 0 || (size == 1 && parts.is_empty()) {
+            let suffix = if locale == Locale::Vi { vi } else { en };
+            parts.push(format!("{}{}", amount, suffix));
+        }
+        if parts.len() == 2 {
+            break;
+        }
+    }
+
+    parts.join(" ")
+}
+
+/// Pure function: a track length as `m:ss`, or `h:mm:ss` for long tracks
+pub fn format_track_length(duration_ms: u32) -> String {
+    let seconds = duration_ms / 1000;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_locale_codes() {
+        assert_eq!(Locale::parse("vi"), Some(Locale::Vi));
+        assert_eq!(Locale::parse("en-US"), Some(Locale::En));
+        assert_eq!(Locale::parse("VI_vn"), Some(Locale::Vi));
+        assert_eq!(Locale::parse("fr"), None);
+    }
+
+    #[test]
+    fn test_number_separators_per_locale() {
+        assert_eq!(format_number(1234.5, 1, Locale::En), "1,234.5");
+        assert_eq!(format_number(1234.5, 1, Locale::Vi), "1.234,5");
+        assert_eq!(format_number(-1234567.0, 0, Locale::En), "-1,234,567");
+        assert_eq!(format_number(-0.001, 1, Locale::En), "0.0");
+        assert_eq!(format_count(999, Locale::Vi), "999");
+        assert_eq!(format_count(1_000_000, Locale::Vi), "1.000.000");
+    }
+
+    #[test]
+    fn test_date_order_per_locale() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert_eq!(format_date(date, Locale::En), "03/15/2024");
+        assert_eq!(format_date(date, Locale::Vi), "15/03/2024");
+
+        let datetime = Utc.with_ymd_and_hms(2024, 3, 15, 21, 5, 0).unwrap();
+        assert_eq!(
+            format_datetime(datetime, Locale::Vi),
+            "15/03/2024 21:05 UTC"
+        );
+    }
+
+    #[test]
+    fn test_duration_keeps_two_largest_units() {
+        let duration = Duration::from_secs(2 * 86_400 + 3 * 3_600 + 59);
+        assert_eq!(format_duration(duration, Locale::En), "2d 3h");
+        assert_eq!(
+            format_duration(Duration::from_secs(65), Locale::Vi),
+            "1 phút 5 giây"
+        );
+        assert_eq!(format_duration(Duration::ZERO, Locale::En), "0s");
+    }
+
+    #[test]
+    fn test_track_length() {
+        assert_eq!(format_track_length(207_959), "3:27");
+        assert_eq!(format_track_length(5_000), "0:05");
+        assert_eq!(format_track_length(3_725_000), "1:02:05");
+    }
+}
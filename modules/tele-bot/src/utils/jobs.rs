@@ -0,0 +1,126 @@
+//! Background jobs: fixed-interval loops that record their status and can be
+//! triggered early with `/run_job`.
+
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+use tracing::{error, info};
+
+lazy_static::lazy_static! {
+    static ref JOBS: Mutex<BTreeMap<&'static str, Job>> = Mutex::new(BTreeMap::new());
+}
+
+struct Job {
+    status: JobStatus,
+    trigger: Arc<Notify>,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobStatus {
+    pub interval: Duration,
+    pub running: bool,
+    pub last_run: Option<DateTime<Utc>>,
+    pub next_run: DateTime<Utc>,
+    /// Error of the last run, cleared by a successful one
+    pub last_error: Option<String>,
+}
+
+impl JobStatus {
+    pub fn new(interval: Duration, now: DateTime<Utc>) -> Self {
+        Self {
+            interval,
+            running: false,
+            last_run: None,
+            next_run: now,
+            last_error: None,
+        }
+    }
+
+    pub fn finish(&mut self, now: DateTime<Utc>, result: Result<(), String>) {
+        self.running = false;
+        self.last_run = Some(now);
+        self.next_run = now + chrono::Duration::from_std(self.interval).unwrap_or_default();
+        self.last_error = result.err();
+    }
+}
+
+/// Run `job` every `every` (first run immediately) for the life of the process
+pub async fn run_every<F, Fut>(name: &'static str, every: Duration, mut job: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let trigger = Arc::new(Notify::new());
+    JOBS.lock().await.insert(
+        name,
+        Job {
+            status: JobStatus::new(every, Utc::now()),
+            trigger: trigger.clone(),
+        },
+    );
+    info!("Job {name} started");
+
+    let mut interval = tokio::time::interval(every);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = trigger.notified() => interval.reset(),
+        }
+
+        if let Some(job) = JOBS.lock().await.get_mut(name) {
+            job.status.running = true;
+        }
+        let result = job().await;
+        if let Err(err) = &result {
+            error!("Job {name} failed: {err}");
+        }
+        if let Some(job) = JOBS.lock().await.get_mut(name) {
+            job.status.finish(Utc::now(), result);
+        }
+    }
+}
+
+/// Ask a job to run now. Returns false for an unknown name.
+pub async fn trigger(name: &str) -> bool {
+    match JOBS.lock().await.get(name) {
+        Some(job) => {
+            job.trigger.notify_one();
+            true
+        }
+        None => false,
+    }
+}
+
+/// All registered jobs by name
+pub async fn snapshot() -> Vec<(&'static str, JobStatus)> {
+    JOBS.lock()
+        .await
+        .iter()
+        .map(|(name, job)| (*name, job.status.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_finish_schedules_next_run_and_keeps_last_error() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap();
+        let mut status = JobStatus::new(Duration::from_secs(3600), start);
+        status.running = true;
+
+        status.finish(start, Err("2 chats failed".to_string()));
+        assert!(!status.running);
+        assert_eq!(status.last_run, Some(start));
+        assert_eq!(status.next_run, start + chrono::Duration::hours(1));
+        assert_eq!(status.last_error.as_deref(), Some("2 chats failed"));
+
+        status.finish(start, Ok(()));
+        assert_eq!(status.last_error, None);
+    }
+}
@@ -0,0 +1,103 @@
+//! Links to Spotify entities, as `spotify:` URIs and open.spotify.com URLs
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Entity {
+    Track,
+    Album,
+    Artist,
+    Playlist,
+    Show,
+    Episode,
+    User,
+}
+
+impl Entity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Entity::Track => "track",
+            Entity::Album => "album",
+            Entity::Artist => "artist",
+            Entity::Playlist => "playlist",
+            Entity::Show => "show",
+            Entity::Episode => "episode",
+            Entity::User => "user",
+        }
+    }
+}
+
+/// Opens the desktop or mobile app directly, e.g. `spotify:track:<id>`
+pub fn spotify_uri(entity: Entity, id: &str) -> String {
+    format!("spotify:{}:{}", entity.as_str(), id)
+}
+
+/// Works everywhere, and hands over to the app when it is installed
+pub fn web_url(entity: Entity, id: &str) -> String {
+    format!("https://open.spotify.com/{}/{}", entity.as_str(), id)
+}
+
+/// Pure function: the ID in a `spotify:` URI, an open.spotify.com URL
+/// (with or without a locale segment and query) or a bare ID
+pub fn parse_id(entity: Entity, input: &str) -> Option<String> {
+    let input = input.trim();
+    let id = if let Some(rest) = input.strip_prefix("spotify:") {
+        rest.strip_prefix(entity.as_str())?.strip_prefix(':')?
+    } else if let Some((_, path)) = input.split_once("open.spotify.com/") {
+        let mut segments = path.split('/').skip_while(|s| s.starts_with("intl-"));
+        if segments.next()? != entity.as_str() {
+            return None;
+        }
+        segments.next()?.split(['?', '#']).next()?
+    } else {
+        input
+    };
+    (id.len() == 22 && id.chars().all(|c| c.is_ascii_alphanumeric())).then(|| id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links_for_each_entity() {
+        assert_eq!(
+            spotify_uri(Entity::Track, "6rqhFgbbKwnb9MLmUQDhG6"),
+            "spotify:track:6rqhFgbbKwnb9MLmUQDhG6"
+        );
+        assert_eq!(
+            web_url(Entity::Playlist, "37i9dQZF1DXcBWIGoYBM5M"),
+            "https://open.spotify.com/playlist/37i9dQZF1DXcBWIGoYBM5M"
+        );
+        assert_eq!(
+            web_url(Entity::Show, "x"),
+            "https://open.spotify.com/show/x"
+        );
+    }
+
+    #[test]
+    fn test_parse_id_from_links_and_ids() {
+        let id = Some("6rqhFgbbKwnb9MLmUQDhG6".to_string());
+        assert_eq!(
+            parse_id(Entity::Track, "spotify:track:6rqhFgbbKwnb9MLmUQDhG6"),
+            id
+        );
+        assert_eq!(
+            parse_id(
+                Entity::Track,
+                "https://open.spotify.com/intl-vi/track/6rqhFgbbKwnb9MLmUQDhG6?si=abc"
+            ),
+            id
+        );
+        assert_eq!(parse_id(Entity::Track, " 6rqhFgbbKwnb9MLmUQDhG6 "), id);
+
+        // Other entities and malformed IDs are rejected
+        assert_eq!(
+            parse_id(
+                Entity::Track,
+                "https://open.spotify.com/album/6rqhFgbbKwnb9MLmUQDhG6"
+            ),
+            None
+        );
+        assert_eq!(parse_id(Entity::Track, "spotify:track:short"), None);
+        assert_eq!(parse_id(Entity::Track, "bohemian rhapsody"), None);
+    }
+}
@@ -0,0 +1,186 @@
+//! Count, time range and genre arguments of the top lists, e.g.
+//! `/top_tracks 25 long` or `/top_tracks rock`
+
+use crate::detector::genre::Genre;
+use crate::models::spotify::TopRange;
+
+pub const DEFAULT_COUNT: usize = 10;
+/// Spotify ranks at most this many, and longer lists are hard to read
+pub const MAX_COUNT: usize = 50;
+
+/// What the user asked for; missing parts fall back to their defaults
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ListArgs {
+    pub count: Option<usize>,
+    pub range: Option<TopRange>,
+    /// Only keep tracks detected as this genre
+    pub genre: Option<Genre>,
+}
+
+impl ListArgs {
+    /// Pure function: numbers set the count (1..=MAX_COUNT), known range
+    /// words set the range, genre names set the genre, in any order. The last
+    /// of each wins. Anything else is an error with one line per bad word, so
+    /// a typo isn't silently answered with the defaults.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let mut args = ListArgs::default();
+        let mut problems = Vec::new();
+        let mut previous = "";
+        for word in input.split_whitespace() {
+            // "all time" reads as one range
+            let all_time =
+                previous.eq_ignore_ascii_case("all") && word.eq_ignore_ascii_case("time");
+            previous = word;
+            if all_time {
+                continue;
+            } else if let Ok(n) = word.parse::<i64>() {
+                if (1..=MAX_COUNT as i64).contains(&n) {
+                    args.count = Some(n as usize);
+                } else {
+                    problems.push(format!("• count {}: use 1–{}", n, MAX_COUNT));
+                }
+            } else if let Some(range) = parse_range(word) {
+                args.range = Some(range);
+            } else if let Some(genre) = Genre::parse(word) {
+                args.genre = Some(genre);
+            } else {
+                problems.push(format!(
+                    "• \"{}\": not a count, time range (short, medium, long) or genre",
+                    word
+                ));
+            }
+        }
+        if problems.is_empty() {
+            Ok(args)
+        } else {
+            Err(problems.join("\n"))
+        }
+    }
+
+    /// Pure function: like [`ListArgs::parse`], for lists a genre can't filter
+    pub fn parse_without_genre(input: &str) -> Result<Self, String> {
+        let args = Self::parse(input)?;
+        match args.genre {
+            Some(genre) => Err(format!(
+                "• {}: genres only filter top tracks",
+                genre.as_str()
+            )),
+            None => Ok(args),
+        }
+    }
+
+    /// Pure function: fill in what's missing from `defaults`, then from the
+    /// built-in defaults (10 tracks, last 6 months like Spotify)
+    pub fn resolve(self, defaults: ListArgs) -> (usize, TopRange) {
+        (
+            self.count.or(defaults.count).unwrap_or(DEFAULT_COUNT),
+            self.range.or(defaults.range).unwrap_or(TopRange::Medium),
+        )
+    }
+}
+
+fn parse_range(word: &str) -> Option<TopRange> {
+    match word.trim().to_lowercase().as_str() {
+        "short" | "month" | "4w" | "recent" => Some(TopRange::Short),
+        "medium" | "6m" | "half" => Some(TopRange::Medium),
+        "long" | "year" | "all" | "lifetime" | "alltime" => Some(TopRange::Long),
+        _ => None,
+    }
+}
+
+/// Pure function: how the range reads in a title, e.g. "last 4 weeks"
+pub fn range_label(range: TopRange) -> &'static str {
+    match range {
+        TopRange::Short => "last 4 weeks",
+        TopRange::Medium => "last 6 months",
+        TopRange::Long => "all time",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_count_and_range_in_any_order() {
+        assert_eq!(
+            ListArgs::parse("25 long"),
+            Ok(ListArgs {
+                count: Some(25),
+                range: Some(TopRange::Long),
+                genre: None
+            })
+        );
+        assert_eq!(
+            ListArgs::parse("Short 5"),
+            Ok(ListArgs {
+                count: Some(5),
+                range: Some(TopRange::Short),
+                genre: None
+            })
+        );
+        assert_eq!(ListArgs::parse(""), Ok(ListArgs::default()));
+    }
+
+    #[test]
+    fn test_parse_all_time() {
+        assert_eq!(
+            ListArgs::parse("all time 20"),
+            Ok(ListArgs {
+                count: Some(20),
+                range: Some(TopRange::Long),
+                genre: None
+            })
+        );
+        assert!(ListArgs::parse("time").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_words_one_line_each() {
+        assert_eq!(
+            ListArgs::parse("500"),
+            Err("• count 500: use 1–50".to_string())
+        );
+        assert!(ListArgs::parse("0").is_err());
+        assert!(ListArgs::parse("-3").is_err());
+
+        let problems = ListArgs::parse("please 99 long").unwrap_err();
+        assert_eq!(problems.lines().count(), 2);
+        assert!(problems.contains("\"please\""));
+        assert!(problems.contains("count 99"));
+    }
+
+    #[test]
+    fn test_parse_genre() {
+        let args = ListArgs::parse("rock 20").unwrap();
+        assert_eq!(args.genre, Some(Genre::Rock));
+        assert_eq!(args.count, Some(20));
+        assert_eq!(
+            ListArgs::parse("hip-hop short").unwrap().genre,
+            Some(Genre::HipHop)
+        );
+
+        assert!(ListArgs::parse_without_genre("rock 20").is_err());
+        assert_eq!(
+            ListArgs::parse_without_genre("20").map(|args| args.count),
+            Ok(Some(20))
+        );
+    }
+
+    #[test]
+    fn test_resolve_prefers_explicit_then_defaults() {
+        let defaults = ListArgs {
+            count: Some(20),
+            range: Some(TopRange::Long),
+            genre: None,
+        };
+        assert_eq!(
+            ListArgs::parse("5").unwrap().resolve(defaults),
+            (5, TopRange::Long)
+        );
+        assert_eq!(
+            ListArgs::default().resolve(ListArgs::default()),
+            (DEFAULT_COUNT, TopRange::Medium)
+        );
+    }
+}
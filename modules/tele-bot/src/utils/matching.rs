@@ -0,0 +1,172 @@
+//! Deciding which search result or library item a typed name refers to
+
+/// Lowercased, with spacing collapsed
+pub fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Title without a version suffix, e.g. "Imagine - Remastered 2010" → "imagine"
+fn base_title(name: &str) -> String {
+    let name = normalize(name);
+    let end = [" - ", " ("]
+        .iter()
+        .filter_map(|separator| name.find(separator))
+        .min()
+        .unwrap_or(name.len());
+    name[..end].to_string()
+}
+
+/// Whether `query` names this track: its title, optionally followed or
+/// preceded by one of its artists
+pub fn is_close_match(query: &str, name: &str, artists: &[String]) -> bool {
+    let query = normalize(query);
+    let title = base_title(name);
+    if query == title || query == normalize(name) {
+        return true;
+    }
+
+    query.contains(&title)
+        && artists.iter().any(|artist| {
+            let artist = normalize(artist);
+            let rest = query.replacen(&title, "", 1);
+            let rest = rest.trim_matches(|c: char| c.is_whitespace() || c == '-' || c == ',');
+            rest == artist || rest.strip_prefix("by ") == Some(artist.as_str())
+        })
+}
+
+/// Score at which the best fuzzy candidate is taken without asking
+const CONFIDENT: f32 = 0.75;
+/// How far the runner-up must trail for the best candidate to be taken
+const MARGIN: f32 = 0.1;
+/// Lowest score still offered as a suggestion
+const SUGGEST: f32 = 0.4;
+const MAX_SUGGESTIONS: usize = 3;
+/// Score for a name that contains the query, e.g. "chill" in "Chill Vibes"
+const CONTAINS: f32 = 0.8;
+
+#[derive(Debug, PartialEq)]
+pub enum FuzzyMatch {
+    /// Index of the candidate the query most likely means
+    Found(usize),
+    /// Closest candidates, best first, when none is convincing (may be empty)
+    Suggestions(Vec<usize>),
+}
+
+/// Edit distance in characters
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// 1.0 for equal names down to 0.0, ignoring case and spacing
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (normalize(a), normalize(b));
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    let score = 1.0 - levenshtein(&a, &b) as f32 / longest as f32;
+    if a.chars().count() >= 3 && b.contains(&a) {
+        score.max(CONTAINS)
+    } else {
+        score
+    }
+}
+
+/// Which of `candidates` the typed `query` refers to, tolerating typos
+pub fn fuzzy_pick(query: &str, candidates: &[&str]) -> FuzzyMatch {
+    let mut scored: Vec<(usize, f32)> = candidates
+        .iter()
+        .map(|candidate| similarity(query, candidate))
+        .enumerate()
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    match scored.as_slice() {
+        [(best, score), rest @ ..]
+            if *score >= CONFIDENT
+                && rest.first().is_none_or(|(_, next)| score - next >= MARGIN) =>
+        {
+            FuzzyMatch::Found(*best)
+        }
+        _ => FuzzyMatch::Suggestions(
+            scored
+                .into_iter()
+                .take_while(|(_, score)| *score >= SUGGEST)
+                .take(MAX_SUGGESTIONS)
+                .map(|(idx, _)| idx)
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artists(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_title_matches_ignoring_version_suffix() {
+        let lennon = artists(&["John Lennon"]);
+        assert!(is_close_match(
+            "imagine",
+            "Imagine - Remastered 2010",
+            &lennon
+        ));
+        assert!(is_close_match(" Imagine ", "Imagine (Live)", &lennon));
+        assert!(!is_close_match(
+            "imagine",
+            "Imagine Dragons Medley",
+            &lennon
+        ));
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("nhạc", "nhac"), 1);
+    }
+
+    #[test]
+    fn test_fuzzy_pick_tolerates_typos() {
+        let names = ["Workout", "Road Trip", "Chill Vibes 2024"];
+        assert_eq!(fuzzy_pick("road trp", &names), FuzzyMatch::Found(1));
+        assert_eq!(fuzzy_pick("chill", &names), FuzzyMatch::Found(2));
+        assert_eq!(fuzzy_pick("jazz", &names), FuzzyMatch::Suggestions(vec![]));
+    }
+
+    #[test]
+    fn test_fuzzy_pick_suggests_when_unsure() {
+        let names = ["Chill Morning", "Chill Evening", "Rock"];
+        assert_eq!(
+            fuzzy_pick("chill", &names),
+            FuzzyMatch::Suggestions(vec![0, 1])
+        );
+    }
+
+    #[test]
+    fn test_title_with_artist_matches() {
+        let lennon = artists(&["John Lennon"]);
+        assert!(is_close_match("imagine john lennon", "Imagine", &lennon));
+        assert!(is_close_match("John Lennon - Imagine", "Imagine", &lennon));
+        assert!(is_close_match("imagine by john lennon", "Imagine", &lennon));
+        assert!(!is_close_match("imagine ariana grande", "Imagine", &lennon));
+    }
+}
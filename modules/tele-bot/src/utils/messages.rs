@@ -0,0 +1,138 @@
+//! Keeping replies within Telegram's message size limit
+
+/// Longest message Telegram accepts, in UTF-16 code units
+pub const TELEGRAM_LIMIT: usize = 4096;
+
+/// Split HTML `text` into messages of at most `limit` UTF-16 units, breaking
+/// only between tags: at paragraph breaks where possible, then at line breaks,
+/// then between words. Only a single word longer than `limit` is cut mid-way.
+pub fn split_message(text: &str, limit: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut rest = text.trim();
+
+    while utf16_len(rest) > limit {
+        let window_end = rest
+            .char_indices()
+            .scan(0, |units, (idx, c)| {
+                *units += c.len_utf16();
+                Some((idx + c.len_utf8(), *units))
+            })
+            .take_while(|(_, units)| *units <= limit)
+            .last()
+            .map_or(0, |(end, _)| end);
+        let window = &rest[..window_end];
+        let cut = best_cut(window).unwrap_or(window_end.max(first_char_len(rest)));
+
+        let (chunk, tail) = rest.split_at(cut);
+        if !chunk.trim().is_empty() {
+            chunks.push(chunk.trim_end().to_string());
+        }
+        rest = tail.trim_start();
+    }
+    if !rest.is_empty() {
+        chunks.push(rest.to_string());
+    }
+
+    chunks
+}
+
+/// Byte offset just after the best separator in `window` that is outside
+/// any tag. A paragraph or line break in the second half of the window wins
+/// over a later space, so chunks end on whole lines.
+fn best_cut(window: &str) -> Option<usize> {
+    let half = window.len() / 2;
+    let (mut paragraph, mut line, mut space) = (None, None, None);
+    let mut depth = 0i32;
+    let mut in_tag = false;
+    let mut closing = false;
+
+    for (idx, c) in window.char_indices() {
+        match c {
+            '<' => {
+                in_tag = true;
+                closing = window[idx + 1..].starts_with('/');
+            }
+            '>' if in_tag => {
+                in_tag = false;
+                depth += if closing { -1 } else { 1 };
+            }
+            '\n' if !in_tag && depth <= 0 => {
+                if window[..idx].ends_with('\n') {
+                    paragraph = Some(idx + 1);
+                }
+                line = Some(idx + 1);
+            }
+            ' ' if !in_tag && depth <= 0 => space = Some(idx + 1),
+            _ => {}
+        }
+    }
+
+    let late = |cut: Option<usize>| cut.filter(|cut| *cut >= half);
+    late(paragraph)
+        .or(late(line))
+        .or([paragraph, line, space].into_iter().flatten().max())
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+fn first_char_len(text: &str) -> usize {
+    text.chars().next().map_or(0, char::len_utf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_message_is_kept_whole() {
+        assert_eq!(
+            split_message("<b>Hi</b>\n\nthere", 100),
+            vec!["<b>Hi</b>\n\nthere"]
+        );
+        assert!(split_message("  \n ", 100).is_empty());
+    }
+
+    #[test]
+    fn test_splits_at_paragraphs_then_lines() {
+        let text = "<b>1</b>. one\nfirst\n\n<b>2</b>. two\nsecond\n\n<b>3</b>. three\nthird";
+        let chunks = split_message(text, 45);
+        assert_eq!(
+            chunks,
+            vec![
+                "<b>1</b>. one\nfirst\n\n<b>2</b>. two\nsecond",
+                "<b>3</b>. three\nthird"
+            ]
+        );
+
+        let lines = split_message("aaaa\nbbbb\ncccc", 10);
+        assert_eq!(lines, vec!["aaaa\nbbbb", "cccc"]);
+    }
+
+    #[test]
+    fn test_never_splits_inside_tags() {
+        let text = "intro\n<pre>line one\nline two\nline three</pre>\nafter";
+        let chunks = split_message(text, 45);
+        assert_eq!(
+            chunks,
+            vec!["intro", "<pre>line one\nline two\nline three</pre>\nafter"]
+        );
+
+        let link = "see <a href=\"https://open.spotify.com/track/x\">this song</a> now";
+        for chunk in split_message(link, 60) {
+            assert_eq!(chunk.matches('<').count(), chunk.matches('>').count());
+        }
+    }
+
+    #[test]
+    fn test_chunks_respect_utf16_limit() {
+        let text = "🎵 ".repeat(100);
+        let chunks = split_message(&text, 31);
+        assert!(chunks.iter().all(|chunk| utf16_len(chunk) <= 31));
+        assert_eq!(chunks.concat().matches('🎵').count(), 100);
+
+        let word = "x".repeat(25);
+        assert_eq!(split_message(&word, 10).len(), 3);
+    }
+}
@@ -1 +1,14 @@
+pub mod audit;
+pub mod bounded_cache;
+pub mod format;
+pub mod jobs;
+pub mod links;
+pub mod list_args;
+pub mod matching;
+pub mod messages;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod single_flight;
 pub mod stream;
+pub mod usage;
+pub mod webhooks;
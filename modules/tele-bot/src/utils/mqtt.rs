@@ -0,0 +1,255 @@
+//! Now-playing updates published to an MQTT broker, with Home Assistant
+//! discovery so a sensor shows up without manual configuration.
+//!
+//! Built with the `mqtt` cargo feature and enabled by setting `MQTT_HOST`.
+//! Only what's needed for that is implemented: MQTT 3.1.1 CONNECT and
+//! QoS 0 PUBLISH over plain TCP.
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+use tracing::{error, info};
+
+use crate::models::spotify::Track;
+
+const DEFAULT_PORT: u16 = 1883;
+const DEFAULT_TOPIC: &str = "spotify_dashboard";
+const DISCOVERY_PREFIX: &str = "homeassistant";
+/// Limit for connecting and for each write, so a dead broker can't stall the
+/// playback poller
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Config {
+    host: String,
+    port: u16,
+    credentials: Option<(String, String)>,
+    /// Base topic; updates go to `<topic>/<telegram user id>/now_playing`
+    topic: String,
+}
+
+#[derive(Default)]
+struct Publisher {
+    /// Taken out while publishing, so the lock isn't held during network I/O
+    stream: Option<TcpStream>,
+    /// Last payload per user, so only changes are published
+    last: HashMap<u64, String>,
+    /// Users whose discovery config was already published
+    discovered: HashSet<u64>,
+}
+
+lazy_static::lazy_static! {
+    static ref CONFIG: Option<Config> = std::env::var("MQTT_HOST").ok().map(|host| Config {
+        host,
+        port: std::env::var("MQTT_PORT")
+            .ok()
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(DEFAULT_PORT),
+        credentials: std::env::var("MQTT_USERNAME")
+            .ok()
+            .map(|user| (user, std::env::var("MQTT_PASSWORD").unwrap_or_default())),
+        topic: std::env::var("MQTT_TOPIC").unwrap_or_else(|_| DEFAULT_TOPIC.to_string()),
+    });
+    static ref PUBLISHER: Mutex<Publisher> = Mutex::new(Publisher::default());
+}
+
+pub fn enabled() -> bool {
+    CONFIG.is_some()
+}
+
+/// Publish `user_id`'s playback state if it changed since the last poll.
+/// `track` is what's loaded in the player, playing or paused.
+pub async fn publish_now_playing(user_id: u64, is_playing: bool, track: Option<&Track>) {
+    let Some(config) = CONFIG.as_ref() else {
+        return;
+    };
+
+    let state = match track {
+        Some(_) if is_playing => "playing",
+        Some(_) => "paused",
+        None => "idle",
+    };
+    let payload = serde_json::json!({
+        "state": state,
+        "track": track.map(|track| &track.name),
+        "artists": track.map(|track| track.artists.join(", ")),
+        "album": track.map(|track| &track.album),
+        "album_art": track.and_then(|track| track.album_art.as_ref()),
+        "id": track.and_then(|track| track.canonical_id()),
+    })
+    .to_string();
+
+    let topic = format!("{}/{}/now_playing", config.topic, user_id);
+    let (packets, stream) = {
+        let mut publisher = PUBLISHER.lock().await;
+        if publisher.last.get(&user_id) == Some(&payload) {
+            return;
+        }
+
+        let mut packets = Vec::new();
+        if !publisher.discovered.contains(&user_id) {
+            let discovery_topic = format!(
+                "{}/sensor/{}_{}/config",
+                DISCOVERY_PREFIX, config.topic, user_id
+            );
+            let discovery = discovery_config(&config.topic, user_id, &topic);
+            packets.extend(encode_publish(&discovery_topic, discovery.as_bytes(), true));
+        }
+        packets.extend(encode_publish(&topic, payload.as_bytes(), true));
+        (packets, publisher.stream.take())
+    };
+
+    match send(config, stream, &packets).await {
+        Ok(stream) => {
+            let mut publisher = PUBLISHER.lock().await;
+            publisher.stream.get_or_insert(stream);
+            publisher.discovered.insert(user_id);
+            publisher.last.insert(user_id, payload);
+        }
+        Err(err) => error!("MQTT publish to {topic} failed: {err}"),
+    }
+}
+
+/// Write `packets`, reconnecting once if the connection dropped. Returns the
+/// connection to keep for the next publish.
+async fn send(
+    config: &Config,
+    stream: Option<TcpStream>,
+    packets: &[u8],
+) -> Result<TcpStream, String> {
+    if let Some(mut stream) = stream {
+        if write(&mut stream, packets).await.is_ok() {
+            return Ok(stream);
+        }
+    }
+
+    let mut stream = timeout(IO_TIMEOUT, connect(config))
+        .await
+        .map_err(|_| "timed out connecting to the broker".to_string())??;
+    write(&mut stream, packets).await?;
+    Ok(stream)
+}
+
+async fn write(stream: &mut TcpStream, bytes: &[u8]) -> Result<(), String> {
+    timeout(IO_TIMEOUT, stream.write_all(bytes))
+        .await
+        .map_err(|_| "timed out writing to the broker".to_string())?
+        .map_err(|err| err.to_string())
+}
+
+async fn connect(config: &Config) -> Result<TcpStream, String> {
+    let mut stream = TcpStream::connect((config.host.as_str(), config.port))
+        .await
+        .map_err(|err| err.to_string())?;
+    let client_id = format!("{}-{}", config.topic, std::process::id());
+    let credentials = config
+        .credentials
+        .as_ref()
+        .map(|(user, password)| (user.as_str(), password.as_str()));
+    stream
+        .write_all(&encode_connect(&client_id, credentials))
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let mut connack = [0u8; 4];
+    stream
+        .read_exact(&mut connack)
+        .await
+        .map_err(|err| err.to_string())?;
+    match connack {
+        [0x20, 0x02, _, 0] => {
+            info!("Connected to MQTT broker {}:{}", config.host, config.port);
+            Ok(stream)
+        }
+        [0x20, 0x02, _, code] => Err(format!("broker refused the connection (code {code})")),
+        _ => Err("unexpected reply from broker".to_string()),
+    }
+}
+
+/// Home Assistant MQTT discovery payload for a user's now-playing sensor
+fn discovery_config(base: &str, user_id: u64, state_topic: &str) -> String {
+    serde_json::json!({
+        "name": "Spotify now playing",
+        "unique_id": format!("{}_{}_now_playing", base, user_id),
+        "state_topic": state_topic,
+        "value_template": "{{ value_json.track if value_json.state != 'idle' else 'Idle' }}",
+        "json_attributes_topic": state_topic,
+        "icon": "mdi:spotify",
+    })
+    .to_string()
+}
+
+/// CONNECT with a clean session and no keep-alive, so an idle connection
+/// between track changes isn't dropped by the broker
+fn encode_connect(client_id: &str, credentials: Option<(&str, &str)>) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_str(&mut body, "MQTT");
+    body.push(4); // protocol level 3.1.1
+    body.push(if credentials.is_some() { 0xC2 } else { 0x02 });
+    body.extend_from_slice(&[0, 0]);
+    push_str(&mut body, client_id);
+    if let Some((user, password)) = credentials {
+        push_str(&mut body, user);
+        push_str(&mut body, password);
+    }
+    packet(0x10, body)
+}
+
+fn encode_publish(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_str(&mut body, topic);
+    body.extend_from_slice(payload);
+    packet(if retain { 0x31 } else { 0x30 }, body)
+}
+
+fn packet(header: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut packet = vec![header];
+    // Remaining length: 7 bits per byte, high bit set when more follow
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+fn push_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_connect() {
+        assert_eq!(
+            encode_connect("bot", None),
+            [0x10, 15, 0, 4, b'M', b'Q', b'T', b'T', 4, 0x02, 0, 0, 0, 3, b'b', b'o', b't']
+        );
+        let with_login = encode_connect("bot", Some(("u", "p")));
+        assert_eq!(with_login[9], 0xC2);
+        assert!(with_login.ends_with(&[0, 1, b'u', 0, 1, b'p']));
+    }
+
+    #[test]
+    fn test_encode_publish_uses_multibyte_length() {
+        let packet = encode_publish("a/b", &[b'x'; 200], true);
+        assert_eq!(packet[0], 0x31);
+        // 2 + 3 + 200 = 205 = 0x4D + 1 * 128
+        assert_eq!(&packet[1..3], &[0xCD, 0x01]);
+        assert_eq!(&packet[3..8], &[0, 3, b'a', b'/', b'b']);
+        assert_eq!(packet.len(), 3 + 205);
+    }
+}
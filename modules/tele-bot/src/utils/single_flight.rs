@@ -0,0 +1,107 @@
+//! Coalescing of identical requests: while one is in flight, callers asking
+//! for the same key wait for its result instead of making their own call.
+
+use futures::future::{BoxFuture, Shared};
+use futures::FutureExt;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::Mutex;
+
+pub struct SingleFlight<K, V> {
+    in_flight: Mutex<HashMap<K, Shared<BoxFuture<'static, V>>>>,
+}
+
+impl<K, V> Default for SingleFlight<K, V> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone + Send + Sync + 'static,
+{
+    /// Join the call already running for `key`, or start one with `call`.
+    /// The call is driven by whoever is still waiting, so it completes even
+    /// if the caller that started it goes away. Results aren't kept: the next
+    /// call after it finishes goes upstream again.
+    pub async fn run<F, Fut>(&self, key: K, call: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V> + Send + 'static,
+    {
+        let shared = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            match in_flight.get(&key) {
+                Some(shared) if shared.peek().is_none() => shared.clone(),
+                _ => {
+                    let shared = call().boxed().shared();
+                    in_flight.insert(key.clone(), shared.clone());
+                    shared
+                }
+            }
+        };
+
+        let value = shared.await;
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        if in_flight
+            .get(&key)
+            .is_some_and(|shared| shared.peek().is_some())
+        {
+            in_flight.remove(&key);
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn counted(calls: &Arc<AtomicUsize>, value: u32) -> impl Future<Output = u32> + Send {
+        let calls = calls.clone();
+        async move {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            value
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_upstream_call() {
+        let flight = SingleFlight::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let (a, b, c) = tokio::join!(
+            flight.run("top", || counted(&calls, 1)),
+            flight.run("top", || counted(&calls, 2)),
+            flight.run("top", || counted(&calls, 3)),
+        );
+        assert_eq!((a, b, c), (1, 1, 1));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        // Nothing is kept once the call finished
+        assert!(flight.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_different_keys_and_later_calls_go_upstream() {
+        let flight = SingleFlight::default();
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let (a, b) = tokio::join!(
+            flight.run("short", || counted(&calls, 1)),
+            flight.run("long", || counted(&calls, 2)),
+        );
+        assert_eq!((a, b), (1, 2));
+
+        assert_eq!(flight.run("short", || counted(&calls, 3)).await, 3);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
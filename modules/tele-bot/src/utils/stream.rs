@@ -1,4 +1,4 @@
-use futures::StreamExt;
+use futures::{StreamExt, TryStreamExt};
 
 pub async fn collect_stream<T, U, E, S, F>(mut stream: S, mut map_fn: F) -> Result<Vec<U>, E>
 where
@@ -18,3 +18,150 @@ where
 
     Ok(items)
 }
+
+/// Like `collect_stream`, but stops after `limit` items so later pages are
+/// never requested
+pub async fn collect_stream_n<T, U, E, S, F>(
+    mut stream: S,
+    limit: usize,
+    mut map_fn: F,
+) -> Result<Vec<U>, E>
+where
+    S: futures::Stream<Item = Result<T, E>> + Unpin,
+    F: FnMut(T) -> U,
+{
+    let mut items = Vec::new();
+
+    while items.len() < limit {
+        match stream.next().await {
+            Some(Ok(value)) => items.push(map_fn(value)),
+            Some(Err(err)) => return Err(err),
+            None => break,
+        }
+    }
+
+    Ok(items)
+}
+
+/// First mapped item matching `predicate`, fetching no further pages once found
+pub async fn find_in_stream<T, U, E, S, F, P>(
+    mut stream: S,
+    mut map_fn: F,
+    mut predicate: P,
+) -> Result<Option<U>, E>
+where
+    S: futures::Stream<Item = Result<T, E>> + Unpin,
+    F: FnMut(T) -> U,
+    P: FnMut(&U) -> bool,
+{
+    while let Some(item) = stream.next().await {
+        let value = map_fn(item?);
+        if predicate(&value) {
+            return Ok(Some(value));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Run `fetch` for every input with at most `concurrency` calls in flight,
+/// returning results in input order or the first error
+pub async fn fetch_all<I, T, E, F, Fut>(
+    inputs: I,
+    concurrency: usize,
+    fetch: F,
+) -> Result<Vec<T>, E>
+where
+    I: IntoIterator,
+    F: FnMut(I::Item) -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+{
+    // Creating the futures up front keeps the stream free of the closure type,
+    // which lets callers borrow the Spotify client from a Send context
+    let calls: Vec<Fut> = inputs.into_iter().map(fetch).collect();
+
+    futures::stream::iter(calls)
+        .buffered(concurrency.max(1))
+        .try_collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    async fn slow_double(value: u32) -> Result<u32, String> {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        Ok(value * 2)
+    }
+
+    #[tokio::test]
+    async fn test_collect_stream_n_stops_at_limit() {
+        let mut pulled = 0;
+        let stream = futures::stream::iter((1..=100).map(Ok::<u32, String>));
+        let items = collect_stream_n(stream, 3, |v| {
+            pulled += 1;
+            v * 10
+        })
+        .await;
+        assert_eq!(items, Ok(vec![10, 20, 30]));
+        assert_eq!(pulled, 3);
+
+        let short = futures::stream::iter((1..=2).map(Ok::<u32, String>));
+        assert_eq!(collect_stream_n(short, 5, |v| v).await, Ok(vec![1, 2]));
+    }
+
+    #[tokio::test]
+    async fn test_find_in_stream_stops_at_first_match() {
+        let mut pulled = 0;
+        let stream = futures::stream::iter((1..=100).map(Ok::<u32, String>));
+        let found = find_in_stream(
+            stream,
+            |v| {
+                pulled += 1;
+                v
+            },
+            |v| v % 7 == 0,
+        )
+        .await;
+        assert_eq!(found, Ok(Some(7)));
+        assert_eq!(pulled, 7);
+
+        let failing = futures::stream::iter(vec![Ok(1), Err("page 2".to_string()), Ok(14)]);
+        assert_eq!(
+            find_in_stream(failing, |v| v, |v| v % 7 == 0).await,
+            Err("page 2".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_preserves_order() {
+        let results = fetch_all(vec![3, 1, 2], 2, slow_double).await;
+        assert_eq!(results, Ok(vec![6, 2, 4]));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_returns_first_error() {
+        let results = fetch_all(vec![1, 2, 3], 2, |v| async move {
+            if v == 2 {
+                Err(format!("failed on {}", v))
+            } else {
+                Ok(v)
+            }
+        })
+        .await;
+        assert_eq!(results, Err("failed on 2".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_is_faster_than_sequential() {
+        // 8 calls of 100ms: ~800ms one by one, ~200ms with 4 in flight
+        let start = Instant::now();
+        let results = fetch_all(0..8, 4, slow_double).await.unwrap();
+        let elapsed = start.elapsed();
+
+        assert_eq!(results.len(), 8);
+        assert!(elapsed < Duration::from_millis(600), "took {:?}", elapsed);
+    }
+}
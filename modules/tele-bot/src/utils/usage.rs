@@ -0,0 +1,98 @@
+//! Opt-in, local-only command usage counters.
+//!
+//! Enabled with `USAGE_STATS=true`. Counts live in memory and are never sent
+//! anywhere; self-hosters can read them with `/usage` and share them if they want.
+
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use tokio::sync::Mutex;
+
+lazy_static::lazy_static! {
+    static ref ENABLED: bool = std::env::var("USAGE_STATS")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false);
+    static ref USAGE: Mutex<UsageStats> = Mutex::new(UsageStats::new(Utc::now()));
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageStats {
+    pub since: DateTime<Utc>,
+    pub counts: BTreeMap<String, u64>,
+}
+
+impl UsageStats {
+    pub fn new(since: DateTime<Utc>) -> Self {
+        Self {
+            since,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, command: &str) {
+        *self.counts.entry(command.to_string()).or_insert(0) += 1;
+    }
+
+    /// Commands sorted by use count, most used first
+    pub fn ranked(&self) -> Vec<(&str, u64)> {
+        let mut ranked: Vec<(&str, u64)> = self
+            .counts
+            .iter()
+            .map(|(name, count)| (name.as_str(), *count))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        ranked
+    }
+}
+
+pub fn is_enabled() -> bool {
+    *ENABLED
+}
+
+/// Count one use of a command given the raw message text (e.g. `/top_tracks@bot 10`)
+pub async fn record_command(text: &str) {
+    if !is_enabled() {
+        return;
+    }
+    if let Some(name) = command_name(text) {
+        USAGE.lock().await.record(name);
+    }
+}
+
+pub async fn snapshot() -> UsageStats {
+    USAGE.lock().await.clone()
+}
+
+/// Pure function: extract the bare command name from a message
+pub fn command_name(text: &str) -> Option<&str> {
+    let first = text.split_whitespace().next()?;
+    let command = first.strip_prefix('/')?;
+    let command = command.split('@').next().unwrap_or(command);
+    (!command.is_empty()).then_some(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_name_strips_bot_mention_and_args() {
+        assert_eq!(command_name("/top_tracks@my_bot 10"), Some("top_tracks"));
+        assert_eq!(command_name("/me"), Some("me"));
+        assert_eq!(command_name("hello"), None);
+        assert_eq!(command_name("/"), None);
+    }
+
+    #[test]
+    fn test_ranked_orders_by_count_then_name() {
+        let mut stats = UsageStats::new(Utc::now());
+        stats.record("me");
+        stats.record("top_tracks");
+        stats.record("top_tracks");
+        stats.record("help");
+
+        assert_eq!(
+            stats.ranked(),
+            vec![("top_tracks", 2), ("help", 1), ("me", 1)]
+        );
+    }
+}
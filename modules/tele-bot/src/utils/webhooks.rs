@@ -0,0 +1,315 @@
+//! Outgoing webhooks: events POSTed as JSON to a member's own URL, signed
+//! with HMAC-SHA256 so the receiver can check they came from this bot.
+//!
+//! Any chat member can register a URL, so only public HTTPS hosts are
+//! accepted. Before every delivery the host is resolved and checked again,
+//! and the request connects only to the addresses that passed, so a DNS
+//! answer changing in between can't redirect it. Redirects are never
+//! followed. Otherwise the bot could be pointed at its own network.
+
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::error;
+
+/// Header carrying `sha256=<hex hmac of the body>`
+pub const SIGNATURE_HEADER: &str = "X-Dashboard-Signature";
+const MAX_ATTEMPTS: u32 = 3;
+const MAX_LOGGED: usize = 20;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    pub url: String,
+    pub secret: String,
+    /// Newest first, at most `MAX_LOGGED`
+    pub deliveries: VecDeque<Delivery>,
+    /// Last track seen by the playback poller, to send each play once
+    last_track: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    pub event: String,
+    pub at: DateTime<Utc>,
+    pub attempts: u32,
+    /// HTTP status of the last attempt, or why it couldn't be sent
+    pub outcome: Result<u16, String>,
+}
+
+impl Webhook {
+    pub fn new(url: String, secret: String) -> Self {
+        Self {
+            url,
+            secret,
+            deliveries: VecDeque::new(),
+            last_track: None,
+        }
+    }
+
+    /// Whether `track_id` differs from the previous sample, i.e. a new play started
+    pub fn observe_track(&mut self, track_id: &str) -> bool {
+        if self.last_track.as_deref() == Some(track_id) {
+            return false;
+        }
+        self.last_track = Some(track_id.to_string());
+        true
+    }
+
+    fn log(&mut self, delivery: Delivery) {
+        self.deliveries.push_front(delivery);
+        self.deliveries.truncate(MAX_LOGGED);
+    }
+}
+
+/// Send `event` in the background, retrying failed attempts with backoff
+pub fn send(webhook: Arc<Mutex<Option<Webhook>>>, event: &'static str, data: serde_json::Value) {
+    tokio::spawn(async move {
+        let Some((url, secret)) = webhook
+            .lock()
+            .await
+            .as_ref()
+            .map(|hook| (hook.url.clone(), hook.secret.clone()))
+        else {
+            return;
+        };
+
+        let at = Utc::now();
+        let body = serde_json::json!({ "event": event, "sent_at": at, "data": data }).to_string();
+        let signature = format!(
+            "sha256={}",
+            to_hex(&hmac_sha256(secret.as_bytes(), body.as_bytes()))
+        );
+
+        let mut attempts = 0;
+        let outcome = loop {
+            attempts += 1;
+            // The host may resolve differently than at registration
+            let client = check_url(&url)
+                .await
+                .and_then(|target| Ok((target.client()?, target.url)));
+            let outcome = match client {
+                Ok((client, target)) => client
+                    .post(target)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header(SIGNATURE_HEADER, &signature)
+                    .body(body.clone())
+                    .send()
+                    .await
+                    .map(|response| response.status())
+                    .map_err(|err| err.to_string()),
+                Err(err) => break Err(err),
+            };
+            match &outcome {
+                Ok(status) if status.is_success() => break Ok(status.as_u16()),
+                // Client errors won't go away by retrying
+                Ok(status) if status.is_client_error() => break Ok(status.as_u16()),
+                _ if attempts >= MAX_ATTEMPTS => break outcome.map(|status| status.as_u16()),
+                _ => tokio::time::sleep(Duration::from_secs(2u64.pow(attempts))).await,
+            }
+        };
+        if let Err(err) = &outcome {
+            error!("Webhook delivery of {event} failed: {err}");
+        }
+
+        // The webhook may have been replaced or removed in the meantime
+        if let Some(hook) = webhook.lock().await.as_mut().filter(|hook| hook.url == url) {
+            hook.log(Delivery {
+                event: event.to_string(),
+                at,
+                attempts,
+                outcome,
+            });
+        }
+    });
+}
+
+/// A URL that passed [`check_url`], with the addresses its host resolved to
+pub struct Target {
+    pub url: reqwest::Url,
+    host: String,
+    addresses: Vec<SocketAddr>,
+}
+
+impl Target {
+    /// A client that connects only to the checked addresses and doesn't
+    /// follow redirects
+    fn client(&self) -> Result<reqwest::Client, String> {
+        reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(TIMEOUT)
+            .resolve_to_addrs(&self.host, &self.addresses)
+            .build()
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Parse `url` and make sure it is HTTPS and every address its host resolves
+/// to is public
+pub async fn check_url(url: &str) -> Result<Target, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| "Not a valid URL.".to_string())?;
+    if parsed.scheme() != "https" {
+        return Err("Webhook URLs must use https.".to_string());
+    }
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| "The URL has no host.".to_string())?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| format!("Couldn't resolve {}.", host))?
+        .collect();
+    if addresses.is_empty() || !addresses.iter().all(|address| is_public(address.ip())) {
+        return Err(format!("{} is not a public address.", host));
+    }
+    Ok(Target {
+        host: host.to_string(),
+        url: parsed,
+        addresses,
+    })
+}
+
+/// Pure function: false for loopback, private, link-local, unique-local,
+/// shared (CGNAT), unspecified, broadcast and multicast addresses, and for
+/// IPv6 addresses that embed an IPv4 one (NAT64, 6to4, IPv4-compatible),
+/// which could reach any of those
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let segments = v6.segments();
+            let first = segments[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0]
+                || first == 0x2002
+                || segments[..6] == [0; 6])
+        }
+    }
+}
+
+/// HMAC (RFC 2104) over SHA-256
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    const BLOCK: usize = 64;
+
+    let mut block = [0u8; BLOCK];
+    if key.len() > BLOCK {
+        block[..32].copy_from_slice(&Sha256::digest(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+
+    let inner = Sha256::new()
+        .chain_update(block.map(|b| b ^ 0x36))
+        .chain_update(message)
+        .finalize();
+    Sha256::new()
+        .chain_update(block.map(|b| b ^ 0x5c))
+        .chain_update(inner)
+        .finalize()
+        .into()
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        // Test cases 2 and 6 of RFC 4231 (short key, key longer than a block)
+        assert_eq!(
+            to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            to_hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn test_is_public_rejects_internal_addresses() {
+        for internal in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+            "2002:a00:1::1",
+            "::10.0.0.1",
+        ] {
+            assert!(!is_public(internal.parse().unwrap()), "{internal}");
+        }
+        assert!(is_public("93.184.216.34".parse().unwrap()));
+        assert!(is_public("2606:2800:220:1::1".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_check_url_requires_public_https() {
+        assert!(check_url("http://example.com/hook").await.is_err());
+        assert!(check_url("https://127.0.0.1/hook").await.is_err());
+        assert!(check_url("https://[::1]:8443/hook").await.is_err());
+        assert!(check_url("https://localhost/hook").await.is_err());
+        assert!(check_url("not a url").await.is_err());
+    }
+
+    #[test]
+    fn test_observe_track_reports_each_play_once() {
+        let mut hook = Webhook::new("https://example.com".to_string(), "s".to_string());
+        assert!(hook.observe_track("a"));
+        assert!(!hook.observe_track("a"));
+        assert!(hook.observe_track("b"));
+    }
+
+    #[test]
+    fn test_log_keeps_newest() {
+        let mut hook = Webhook::new("https://example.com".to_string(), "s".to_string());
+        for n in 0..MAX_LOGGED + 5 {
+            hook.log(Delivery {
+                event: format!("e{n}"),
+                at: Utc::now(),
+                attempts: 1,
+                outcome: Ok(200),
+            });
+        }
+        assert_eq!(hook.deliveries.len(), MAX_LOGGED);
+        assert_eq!(hook.deliveries[0].event, format!("e{}", MAX_LOGGED + 4));
+    }
+}